@@ -2,12 +2,79 @@
 out vec4 frag_color;
 
 in vec3 normal;
+in vec4 tangent;
 in vec2 tex_coord;
 in vec3 frag_pos;
 
+#define ALPHA_OPAQUE 0
+#define ALPHA_MASK 1
+#define ALPHA_BLEND 2
+
+// glTF metallic-roughness, see Material::Pbr
 struct Material {
     sampler2D albedo;
+    vec4 base_color_factor;
+    sampler2D metallic_roughness;
+    float metallic_factor;
+    float roughness_factor;
+    sampler2D normal;
+    bool has_normal;
+    float normal_scale;
+    sampler2D occlusion;
+    float occlusion_strength;
+    sampler2D emissive;
+    vec3 emissive_factor;
+    int alpha_mode;
+    float alpha_cutoff;
+};
+
+// Same lights as the phong program, diffuse is used as the radiance, specular is ignored
+struct DirLight {
+    vec3 dir;
+
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+};
+
+struct PointLight {
+    vec3 pos;
+
+    float constant;
+    float linear;
+    float quadratic;
+
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
 };
+
+struct SpotLight {
+    vec3 pos;
+    vec3 dir;
+
+    float cut_off;
+    float outer_cut_off;
+
+    float constant;
+    float linear;
+    float quadratic;
+
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+};
+
+#define NR_POINT_LIGHTS 4
+#define PI 3.14159265359
+
+uniform vec3 camera_pos;
+uniform bool has_dirlight;
+uniform DirLight dirlight;
+uniform int pointlight_count;
+uniform PointLight pointlights[NR_POINT_LIGHTS];
+uniform bool has_spotlight;
+uniform SpotLight spotlight;
 uniform Material material;
 
 // TODO move to ???
@@ -25,20 +92,143 @@ float dither(vec2 uv)
     return fract(sin(dot(uv.xy, vec2(12.9898, 78.233))) * 43758.5453) / 256.0 - 0.001953125;
 }
 
+// Uses the tangents of the mesh, without them the frame is rebuilt from the screen space
+// derivatives
+vec3 PerturbNormal(vec3 n, vec3 view_vec)
+{
+    vec3 map = texture(material.normal, tex_coord).xyz * 2.0 - 1.0;
+    map.xy *= material.normal_scale;
+
+    if (dot(tangent.xyz, tangent.xyz) > 0.0) {
+        vec3 t = normalize(tangent.xyz - n * dot(n, tangent.xyz));
+        vec3 b = cross(n, t) * tangent.w;
+        return normalize(mat3(t, b, n) * map);
+    }
+
+    vec3 dp1 = dFdx(-view_vec);
+    vec3 dp2 = dFdy(-view_vec);
+    vec2 duv1 = dFdx(tex_coord);
+    vec2 duv2 = dFdy(tex_coord);
+    vec3 dp2perp = cross(dp2, n);
+    vec3 dp1perp = cross(n, dp1);
+    vec3 t = dp2perp * duv1.x + dp1perp * duv2.x;
+    // Up in the texture like the tangents w, v grows downwards
+    vec3 b = -(dp2perp * duv1.y + dp1perp * duv2.y);
+    float inv_max = inversesqrt(max(dot(t, t), dot(b, b)));
+    return normalize(mat3(t * inv_max, b * inv_max, n) * map);
+}
+
+// Trowbridge-Reitz GGX normal distribution
+float DistributionGGX(float n_dot_h, float roughness)
+{
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+// Smith geometry with the Schlick-GGX approximation for direct lighting
+float GeometrySmith(float n_dot_v, float n_dot_l, float roughness)
+{
+    float k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    float g_v = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    float g_l = n_dot_l / (n_dot_l * (1.0 - k) + k);
+    return g_v * g_l;
+}
+
+vec3 FresnelSchlick(float cos_theta, vec3 f0)
+{
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// Cook-Torrance specular plus Lambert diffuse, for a light coming from light_dir
+vec3 BRDF(vec3 n, vec3 v, vec3 light_dir, vec3 radiance, vec3 albedo, float metallic, float roughness)
+{
+    vec3 h = normalize(v + light_dir);
+    float n_dot_v = max(dot(n, v), 1e-4);
+    float n_dot_l = max(dot(n, light_dir), 0.0);
+    float n_dot_h = max(dot(n, h), 0.0);
+
+    vec3 f0 = mix(vec3(0.04), albedo, metallic);
+    vec3 f = FresnelSchlick(max(dot(h, v), 0.0), f0);
+    float d = DistributionGGX(n_dot_h, roughness);
+    float g = GeometrySmith(n_dot_v, n_dot_l, roughness);
+    vec3 specular = d * g * f / (4.0 * n_dot_v * n_dot_l + 1e-4);
+
+    // Metals have no diffuse
+    vec3 k_d = (vec3(1.0) - f) * (1.0 - metallic);
+    return (k_d * albedo / PI + specular) * radiance * n_dot_l;
+}
+
+float Attenuation(float constant, float linear, float quadratic, vec3 pos)
+{
+    float distance = length(pos - frag_pos);
+    return 1.0 / max(constant + linear * distance + quadratic * (distance * distance), 1e-4);
+}
+
 void main()
 {
-    // vec4 result = vec4(0.0);
+    vec4 base_color = texture(material.albedo, tex_coord);
+    // Base color and emissive textures are sRGB
+    base_color.rgb = pow(base_color.rgb, vec3(2.2));
+    base_color *= material.base_color_factor;
+
+    if (material.alpha_mode == ALPHA_MASK && base_color.a < material.alpha_cutoff)
+        discard;
+
+    vec4 metallic_roughness = texture(material.metallic_roughness, tex_coord);
+    float metallic = clamp(metallic_roughness.b * material.metallic_factor, 0.0, 1.0);
+    // Perfectly smooth surfaces make the highlights vanish
+    float roughness = clamp(metallic_roughness.g * material.roughness_factor, 0.04, 1.0);
+
+    vec3 view_vec = camera_pos - frag_pos;
+    vec3 v = normalize(view_vec);
+    vec3 n = normalize(normal);
+    // Double sided back faces are lit from the other side
+    if (!gl_FrontFacing)
+        n = -n;
+    if (material.has_normal)
+        n = PerturbNormal(n, view_vec);
+
+    vec3 albedo = base_color.rgb;
+    vec3 color = vec3(0.0);
+    vec3 ambient = vec3(0.0);
+
+    if (has_dirlight) {
+        color += BRDF(n, v, normalize(-dirlight.dir), dirlight.diffuse, albedo, metallic, roughness);
+        ambient += dirlight.ambient;
+    }
+    for (int i = 0; i < min(pointlight_count, NR_POINT_LIGHTS); i++) {
+        PointLight light = pointlights[i];
+        float attenuation = Attenuation(light.constant, light.linear, light.quadratic, light.pos);
+        vec3 light_dir = normalize(light.pos - frag_pos);
+        color += BRDF(n, v, light_dir, light.diffuse * attenuation, albedo, metallic, roughness);
+        ambient += light.ambient * attenuation;
+    }
+    if (has_spotlight) {
+        vec3 light_dir = normalize(spotlight.pos - frag_pos);
+        float theta = dot(light_dir, normalize(-spotlight.dir));
+        float epsilon = spotlight.cut_off - spotlight.outer_cut_off;
+        float intensity = clamp((theta - spotlight.outer_cut_off) / epsilon, 0.0, 1.0);
+        float attenuation = Attenuation(spotlight.constant, spotlight.linear, spotlight.quadratic, spotlight.pos);
+        vec3 radiance = spotlight.diffuse * attenuation * intensity;
+        color += BRDF(n, v, light_dir, radiance, albedo, metallic, roughness);
+        ambient += spotlight.ambient * attenuation * intensity;
+    }
+
+    float occlusion = texture(material.occlusion, tex_coord).r;
+    occlusion = 1.0 + material.occlusion_strength * (occlusion - 1.0);
+    color += ambient * albedo * occlusion;
+
+    vec3 emissive = pow(texture(material.emissive, tex_coord).rgb, vec3(2.2));
+    color += emissive * material.emissive_factor;
 
-    // MATERIAL ALBEDO
-    // vec3 norm = normalize(normal);
-    // vec4 albedo = texture(material.albedo, tex_coord);
-    
     // DEPTH
     // float depth = LinearizeDepth(gl_FragCoord.z) / far; // divide by far for demonstration
-    // result += vec4(vec3(depth), 1.0);
+    // color = vec3(depth);
 
-    // DITHER
-    // result += vec4(vec3(dither(tex_coord)), 1.0);
-
-    frag_color = texture(material.albedo, tex_coord);
-}
\ No newline at end of file
+    // Lighting is done in linear space, the framebuffer expects sRGB
+    color = pow(color, vec3(1.0 / 2.2));
+    float alpha = material.alpha_mode == ALPHA_BLEND ? base_color.a : 1.0;
+    frag_color = vec4(color, alpha);
+}
@@ -2,19 +2,38 @@
 layout (location = 0) in vec3 a_pos;
 layout (location = 1) in vec3 a_normal;
 layout (location = 2) in vec2 a_tex_coord;
+layout (location = 3) in uvec4 a_joints;
+layout (location = 4) in vec4 a_weights;
+// (0, 0, 0, 1) when the mesh has no tangents
+layout (location = 5) in vec4 a_tangent;
+
+// Same as skin::MAX_JOINTS
+#define MAX_JOINTS 128
 
 out vec3 normal;
+out vec4 tangent;
 out vec2 tex_coord;
 out vec3 frag_pos;
 
 uniform mat4 model;
 uniform mat4 view;
 uniform mat4 projection;
+uniform bool skinned;
+uniform mat4 joints[MAX_JOINTS];
 
 void main()
 {
-    gl_Position = projection * view * model * vec4(a_pos, 1.0);
-    frag_pos = vec3(model * vec4(a_pos, 1.0));
-    normal = mat3(transpose(inverse(model))) * a_normal;
+    mat4 skin = mat4(1.0);
+    if (skinned) {
+        skin = a_weights.x * joints[a_joints.x]
+             + a_weights.y * joints[a_joints.y]
+             + a_weights.z * joints[a_joints.z]
+             + a_weights.w * joints[a_joints.w];
+    }
+    mat4 world = model * skin;
+    gl_Position = projection * view * world * vec4(a_pos, 1.0);
+    frag_pos = vec3(world * vec4(a_pos, 1.0));
+    normal = mat3(transpose(inverse(world))) * a_normal;
+    tangent = vec4(mat3(world) * a_tangent.xyz, a_tangent.w);
     tex_coord = a_tex_coord;
-} 
\ No newline at end of file
+}
@@ -51,8 +51,11 @@ in vec3 normal;
 in vec3 frag_pos;
 
 uniform vec3 camera_pos;
+uniform bool has_dirlight;
 uniform DirLight dirlight;
+uniform int pointlight_count;
 uniform PointLight pointlights[NR_POINT_LIGHTS];
+uniform bool has_spotlight;
 uniform SpotLight spotlight;
 uniform Material material;
 
@@ -65,10 +68,14 @@ void main()
     vec3 norm = normalize(normal);
     vec3 view_dir = normalize(camera_pos - frag_pos);
     
-    vec3 result = CalcDirLight(dirlight, norm, view_dir);
-    for(int i = 0; i < NR_POINT_LIGHTS; i++)
+    // Lights that were not uploaded are skipped, see Assets::upload_lights
+    vec3 result = vec3(0.0);
+    if (has_dirlight)
+        result += CalcDirLight(dirlight, norm, view_dir);
+    for(int i = 0; i < min(pointlight_count, NR_POINT_LIGHTS); i++)
         result += CalcPointLight(pointlights[i], norm, frag_pos, view_dir);
-    result += CalcSpotLight(spotlight, norm, frag_pos, view_dir);
+    if (has_spotlight)
+        result += CalcSpotLight(spotlight, norm, frag_pos, view_dir);
 
     vec3 emissive = texture(material.emissive, tex_coord).rgb;
     // emissive *= 2.0; // increase the emissive effect
@@ -2,6 +2,11 @@
 layout (location = 0) in vec3 a_pos;
 layout (location = 1) in vec3 a_normal;
 layout (location = 2) in vec2 a_texcoord;
+layout (location = 3) in uvec4 a_joints;
+layout (location = 4) in vec4 a_weights;
+
+// Same as skin::MAX_JOINTS
+#define MAX_JOINTS 128
 
 out vec2 tex_coord;
 out vec3 normal;
@@ -10,11 +15,21 @@ out vec3 frag_pos;
 uniform mat4 model;
 uniform mat4 view;
 uniform mat4 projection;
+uniform bool skinned;
+uniform mat4 joints[MAX_JOINTS];
 
 void main()
 {
-    gl_Position = projection * view * model * vec4(a_pos, 1.0);
-    frag_pos = vec3(model * vec4(a_pos, 1.0));
-    normal = mat3(transpose(inverse(model))) * a_normal;
+    mat4 skin = mat4(1.0);
+    if (skinned) {
+        skin = a_weights.x * joints[a_joints.x]
+             + a_weights.y * joints[a_joints.y]
+             + a_weights.z * joints[a_joints.z]
+             + a_weights.w * joints[a_joints.w];
+    }
+    mat4 world = model * skin;
+    gl_Position = projection * view * world * vec4(a_pos, 1.0);
+    frag_pos = vec3(world * vec4(a_pos, 1.0));
+    normal = mat3(transpose(inverse(world))) * a_normal;
     tex_coord = a_texcoord;
-}
\ No newline at end of file
+}
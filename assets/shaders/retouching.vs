@@ -1,6 +1,6 @@
 #version 460 core
 layout (location = 0) in vec2 a_pos;
-layout (location = 1) in vec2 a_tex_coord;
+layout (location = 2) in vec2 a_tex_coord;
 
 out vec2 tex_coord;
 
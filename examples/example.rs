@@ -1,20 +1,28 @@
-use cgmath::{point3, vec3, Deg, Matrix4, Quaternion, Rotation3, SquareMatrix, Vector3};
+use cgmath::{
+    point3, vec3, Deg, EuclideanSpace, Matrix4, Quaternion, Rotation3, SquareMatrix, Vector3,
+};
 use glfw::Key;
 use revenant::{
     assets::{
         camera::{Camera, CameraProjectionKind},
+        error::AssetError,
+        handle::Handle,
+        light::{DirLight, PointLight},
         mesh::Mesh,
-        Assets,
+        model::Model,
+        program::Program,
+        Assets, Cubemap, Framebuffer,
     },
     Revenant,
 };
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-fn main() {
+fn main() -> Result<(), AssetError> {
     let mut revenant = Revenant::new();
     let mut assets = Assets::new();
 
-    let icon_asset = assets.new_image_foreign("icon", "png");
+    let icon = assets.new_image_foreign("icon", "png")?;
+    let icon_asset = assets.get_image(icon)?;
     let mut icons = vec![icon_asset.to_glfw_pixelimage()];
     icons.push(icon_asset.to_glfw_pixelimage());
     revenant.set_window_icon(icons);
@@ -30,14 +38,32 @@ fn main() {
         max_fov_y: 130.0,
     };
 
-    init_assets(&mut assets);
+    let handles = init_assets(&mut assets, revenant.framebuffer_size())?;
+
+    // loading screen, the heavy decoding happens on the loader threads
+    while assets.loading_count() > 0 && !revenant.should_close() {
+        assets.process_uploads(Duration::from_millis(8));
+        unsafe {
+            gl::ClearColor(0.1, 0.0, 0.1, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+    }
+    if let Some(error) = assets.load_error(handles.cubemap_skybox) {
+        println!("Failed to load the skybox: {}", error);
+    }
+    if let Some(error) = assets.load_error(handles.cube) {
+        println!("Failed to load the cube: {}", error);
+    }
 
     let mut last_cycle_time = Instant::now();
     let mut last_frame_count_total = 0;
 
     while !revenant.should_close() {
-        input(&mut revenant, &mut assets, &mut camera_controller);
-        render(&mut assets);
+        for error in assets.reload_modified() {
+            println!("Hot reload failed: {}", error);
+        }
+        input(&mut revenant, &mut assets, &handles, &mut camera_controller)?;
+        render(&assets, &handles)?;
 
         if Instant::now().duration_since(last_cycle_time).as_secs() > 0 {
             println!(
@@ -48,14 +74,28 @@ fn main() {
             last_cycle_time = Instant::now();
         }
     }
+
+    Ok(())
+}
+
+struct Handles {
+    quad: Handle<Mesh>,
+    mesh_skybox: Handle<Mesh>,
+    cubemap_skybox: Handle<Cubemap>,
+    camera_main: Handle<Camera>,
+    program_pbr: Handle<Program>,
+    program_skybox: Handle<Program>,
+    program_retouching: Handle<Program>,
+    framebuffer_main: Handle<Framebuffer>,
+    cube: Handle<Model>,
 }
 
 // OPTIMIZE test with inline, without and always inlined
 #[inline]
-fn init_assets(assets: &mut Assets) {
-    assets.new_mesh("quad", Mesh::quad());
+fn init_assets(assets: &mut Assets, (width, height): (u32, u32)) -> Result<Handles, AssetError> {
+    let quad = assets.new_mesh("quad", Mesh::quad());
 
-    assets.new_cubemap_foreign(
+    let cubemap_skybox = assets.new_cubemap_foreign_async(
         "skybox",
         ("right", "jpg"),
         ("left", "jpg"),
@@ -63,41 +103,80 @@ fn init_assets(assets: &mut Assets) {
         ("bottom", "jpg"),
         ("front", "jpg"),
         ("back", "jpg"),
+    )?;
+    let mesh_skybox = assets.new_mesh("skybox", Mesh::skybox());
+
+    let camera_main = assets.new_camera("main", Camera::perspective(point3(1.84, 0.8, 3.1)));
+
+    let pbr_vs = assets.new_shader_foreign("pbr", "vs")?;
+    let pbr_fs = assets.new_shader_foreign("pbr", "fs")?;
+    let program_pbr = assets.new_program("pbr", &[pbr_vs, pbr_fs])?;
+
+    let phong_vs = assets.new_shader_foreign("phong", "vs")?;
+    let phong_fs = assets.new_shader_foreign("phong", "fs")?;
+    assets.new_program("phong", &[phong_vs, phong_fs])?;
+
+    assets.new_dirlight(
+        "sun",
+        DirLight {
+            dir: vec3(-0.3, -1.0, -0.5),
+            ambient: vec3(0.2, 0.2, 0.2),
+            diffuse: vec3(3.0, 3.0, 3.0),
+            specular: vec3(1.0, 1.0, 1.0),
+        },
+    );
+    assets.new_pointlight(
+        "lamp",
+        PointLight {
+            pos: point3(10.0, 2.0, 2.0),
+            constant: 1.0,
+            linear: 0.09,
+            quadratic: 0.032,
+            ambient: vec3(0.0, 0.0, 0.0),
+            diffuse: vec3(4.0, 3.0, 2.0),
+            specular: vec3(1.0, 1.0, 1.0),
+        },
     );
-    assets.new_mesh("skybox", Mesh::skybox());
-
-    assets.new_camera("main", Camera::perspective(point3(1.84, 0.8, 3.1)));
-
-    assets.new_shader_foreign("pbr", "vs");
-    assets.new_shader_foreign("pbr", "fs");
-    // TODO remove extension from shader names
-    assets.new_program("pbr", vec!["pbr_vs", "pbr_fs"]);
-
-    assets.new_shader_foreign("outliner", "vs");
-    assets.new_shader_foreign("outliner", "fs");
-    assets.new_program("outliner", vec!["outliner_vs", "outliner_fs"]);
-
-    assets.new_shader_foreign("skybox", "vs");
-    assets.new_shader_foreign("skybox", "fs");
-    assets.new_program("skybox", vec!["skybox_vs", "skybox_fs"]);
-
-    assets.new_shader_foreign("retouching", "vs");
-    assets.new_shader_foreign("retouching", "fs");
-    assets.new_program("retouching", vec!["retouching_vs", "retouching_fs"]);
-
-    // FIX use actual window size
-    const WIN_DIM_X: u32 = 1600;
-    const WIN_DIM_Y: u32 = 900;
-    assets.new_framebuffer("main", WIN_DIM_X, WIN_DIM_Y);
 
-    assets.new_model_foreign("cube", "gltf");
-    assets.new_model_foreign("window", "gltf");
-    assets.new_model_foreign("grass", "gltf");
+    let outliner_vs = assets.new_shader_foreign("outliner", "vs")?;
+    let outliner_fs = assets.new_shader_foreign("outliner", "fs")?;
+    assets.new_program("outliner", &[outliner_vs, outliner_fs])?;
+
+    let skybox_vs = assets.new_shader_foreign("skybox", "vs")?;
+    let skybox_fs = assets.new_shader_foreign("skybox", "fs")?;
+    let program_skybox = assets.new_program("skybox", &[skybox_vs, skybox_fs])?;
+
+    let retouching_vs = assets.new_shader_foreign("retouching", "vs")?;
+    let retouching_fs = assets.new_shader_foreign("retouching", "fs")?;
+    let program_retouching = assets.new_program("retouching", &[retouching_vs, retouching_fs])?;
+
+    let framebuffer_main = assets.new_framebuffer("main", width, height);
+
+    let cube = assets.new_model_foreign_async("cube", "gltf")?;
+    assets.new_model_foreign_async("window", "gltf")?;
+    assets.new_model_foreign_async("grass", "gltf")?;
+
+    Ok(Handles {
+        quad,
+        mesh_skybox,
+        cubemap_skybox,
+        camera_main,
+        program_pbr,
+        program_skybox,
+        program_retouching,
+        framebuffer_main,
+        cube,
+    })
 }
 
 #[inline]
-fn input(revenant: &mut Revenant, assets: &mut Assets, camera_controller: &mut CameraController) {
-    let camera_main = assets.get_mut_camera("main");
+fn input(
+    revenant: &mut Revenant,
+    assets: &mut Assets,
+    handles: &Handles,
+    camera_controller: &mut CameraController,
+) -> Result<(), AssetError> {
+    let camera_main = assets.get_mut_camera(handles.camera_main)?;
 
     if let Some((_, scroll_y)) = revenant.inputs.mouse_scroll {
         match camera_main.projection_kind {
@@ -183,20 +262,22 @@ fn input(revenant: &mut Revenant, assets: &mut Assets, camera_controller: &mut C
     }
 
     camera_controller.update(revenant.frame_time_delta as f32, revenant.inputs.mouse_pos);
+
+    Ok(())
 }
 
 #[inline]
-fn render(assets: &mut Assets) {
-    let quad = assets.get_mesh("quad");
-    let mesh_skybox = assets.get_mesh("skybox");
-    let cubemap_skybox = assets.get_cubemap("skybox");
-
-    let program_pbr = assets.get_program("pbr");
-    let program_retouching = assets.get_program("retouching");
-    let program_skybox = assets.get_program("skybox");
-    let cube = assets.get_model("cube");
-    let camera_main = assets.get_camera("main");
-    let framebuffer_main = assets.get_framebuffer("main");
+fn render(assets: &Assets, handles: &Handles) -> Result<(), AssetError> {
+    let quad = assets.get_mesh(handles.quad)?;
+    let mesh_skybox = assets.get_mesh(handles.mesh_skybox)?;
+    let cubemap_skybox = assets.get_cubemap(handles.cubemap_skybox)?;
+
+    let program_pbr = assets.get_program(handles.program_pbr)?;
+    let program_retouching = assets.get_program(handles.program_retouching)?;
+    let program_skybox = assets.get_program(handles.program_skybox)?;
+    let cube = assets.get_model(handles.cube)?;
+    let camera_main = assets.get_camera(handles.camera_main)?;
+    let framebuffer_main = assets.get_framebuffer(handles.framebuffer_main)?;
 
     framebuffer_main.gl_bind();
     unsafe {
@@ -218,12 +299,15 @@ fn render(assets: &mut Assets) {
         gl::DepthMask(gl::TRUE);
     }
 
-    program_pbr.use_program();
+    assets.upload_lights(handles.program_pbr)?;
     program_pbr.set_uniform_mat4("model", &Matrix4::identity());
     program_pbr.set_uniform_mat4("view", &camera_main.view);
     program_pbr.set_uniform_mat4("projection", &camera_main.projection);
-    program_pbr.set_uniform_mat4("model", &Matrix4::from_translation(vec3(12.0, 0.0, 0.0)));
-    cube.draw(program_pbr);
+    program_pbr.set_uniform_vec3("camera_pos", camera_main.pos.to_vec());
+    cube.draw(
+        program_pbr,
+        &Matrix4::from_translation(vec3(12.0, 0.0, 0.0)),
+    );
 
     framebuffer_main.gl_unbind();
     unsafe {
@@ -238,6 +322,8 @@ fn render(assets: &mut Assets) {
         gl::BindTexture(gl::TEXTURE_2D, framebuffer_main.gl_texturebuffer_id);
     }
     quad.draw();
+
+    Ok(())
 }
 
 struct CameraController {
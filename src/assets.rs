@@ -1,91 +1,117 @@
-use ::image::{DynamicImage, ImageBuffer, ImageFormat};
-use bincode::{deserialize, serialize};
-use serde::{de::DeserializeOwned, Serialize};
-use std::collections::HashMap;
+use serde::Serialize;
 use std::{
-    fs::File,
-    io::{Read, Write},
+    io::ErrorKind,
     path::PathBuf,
+    time::{Duration, Instant},
 };
 
 pub mod camera;
+pub mod error;
 pub mod font;
+pub mod handle;
 pub mod image;
 pub mod light;
+pub mod loader;
 pub mod material;
 pub mod mesh;
 pub mod model;
 pub mod program;
 pub mod shader;
+pub mod soul;
 pub mod texture;
-use crate::cubemap::Cubemap;
+pub mod uri;
+mod watcher;
+pub use crate::cubemap::{Cubemap, CubemapData};
 use crate::framebuffer::renderbuffer::Renderbuffer;
 use crate::framebuffer::texturebuffer::TextureFramebuffer;
-use crate::framebuffer::Framebuffer;
+pub use crate::framebuffer::Framebuffer;
 
 use self::camera::Camera;
-use self::font::Font;
+use self::error::AssetError;
+use self::font::{Font, FontData};
+use self::handle::{Handle, Storage};
 use self::image::Image;
-use self::light::{DirLight, PointLight, SpotLight};
-use self::mesh::Mesh;
-use self::model::Model;
+use self::light::{DirLight, PointLight, SpotLight, MAX_POINT_LIGHTS};
+use self::loader::{Decoded, LoadStatus, Loader, Target};
+use self::mesh::{Mesh, MeshData};
+use self::model::{Model, ModelData};
 use self::program::Program;
 use self::shader::Shader;
-use self::texture::Texture;
+use self::soul::{Compression, Soul};
+use self::texture::{Texture, TextureData};
+use self::watcher::{Source, Watcher};
 
-// OPTIMIZE use hashmap of ID number instead of string
 pub struct Assets {
-    pub(crate) images: HashMap<String, Image>,
-    pub(crate) textures: HashMap<String, Texture>,
-    pub(crate) fonts: HashMap<String, Font>,
-    pub(crate) cameras: HashMap<String, Camera>,
-    pub(crate) pointlights: HashMap<String, PointLight>,
-    pub(crate) dirlights: HashMap<String, DirLight>,
-    pub(crate) spotlights: HashMap<String, SpotLight>,
-    pub(crate) meshes: HashMap<String, Mesh>,
-    pub(crate) models: HashMap<String, Model>,
-    pub(crate) shaders: HashMap<String, Shader>,
-    pub(crate) programs: HashMap<String, Program>,
-    pub(crate) framebuffers: HashMap<String, Framebuffer>,
-    pub(crate) cubemaps: HashMap<String, Cubemap>,
+    pub(crate) images: Storage<Image>,
+    pub(crate) textures: Storage<Texture>,
+    pub(crate) fonts: Storage<Font>,
+    pub(crate) cameras: Storage<Camera>,
+    pub(crate) pointlights: Storage<PointLight>,
+    pub(crate) dirlights: Storage<DirLight>,
+    pub(crate) spotlights: Storage<SpotLight>,
+    pub(crate) meshes: Storage<Mesh>,
+    pub(crate) models: Storage<Model>,
+    pub(crate) shaders: Storage<Shader>,
+    pub(crate) programs: Storage<Program>,
+    pub(crate) framebuffers: Storage<Framebuffer>,
+    pub(crate) cubemaps: Storage<Cubemap>,
+    watcher: Watcher,
+    loader: Loader,
 }
 
 impl Assets {
     pub fn new() -> Self {
         Self {
-            images: HashMap::new(),
-            textures: HashMap::new(),
-            fonts: HashMap::new(),
-            cameras: HashMap::new(),
-            pointlights: HashMap::new(),
-            dirlights: HashMap::new(),
-            spotlights: HashMap::new(),
-            meshes: HashMap::new(),
-            models: HashMap::new(),
-            shaders: HashMap::new(),
-            programs: HashMap::new(),
-            framebuffers: HashMap::new(),
-            cubemaps: HashMap::new(),
+            images: Storage::new(),
+            textures: Storage::new(),
+            fonts: Storage::new(),
+            cameras: Storage::new(),
+            pointlights: Storage::new(),
+            dirlights: Storage::new(),
+            spotlights: Storage::new(),
+            meshes: Storage::new(),
+            models: Storage::new(),
+            shaders: Storage::new(),
+            programs: Storage::new(),
+            framebuffers: Storage::new(),
+            cubemaps: Storage::new(),
+            watcher: Watcher::new(),
+            loader: Loader::new(),
         }
     }
 
     // SECTION NEW
 
-    pub fn new_mesh(&mut self, name: &str, mesh: Mesh) {
-        self.meshes.insert(name.to_owned(), mesh);
+    pub fn new_mesh(&mut self, name: &str, mesh: Mesh) -> Handle<Mesh> {
+        self.meshes.insert_named(name, mesh)
     }
-    pub fn new_camera(&mut self, name: &str, camera: Camera) {
-        self.cameras.insert(name.to_owned(), camera);
+    pub fn new_camera(&mut self, name: &str, camera: Camera) -> Handle<Camera> {
+        self.cameras.insert_named(name, camera)
     }
-    pub fn new_program(&mut self, name: &str, shader_names: Vec<&str>) {
+    pub fn new_dirlight(&mut self, name: &str, light: DirLight) -> Handle<DirLight> {
+        self.dirlights.insert_named(name, light)
+    }
+    pub fn new_pointlight(&mut self, name: &str, light: PointLight) -> Handle<PointLight> {
+        self.pointlights.insert_named(name, light)
+    }
+    pub fn new_spotlight(&mut self, name: &str, light: SpotLight) -> Handle<SpotLight> {
+        self.spotlights.insert_named(name, light)
+    }
+    pub fn new_program(
+        &mut self,
+        name: &str,
+        shaders: &[Handle<Shader>],
+    ) -> Result<Handle<Program>, AssetError> {
         let mut shaders_gl_ids = Vec::new();
-        for shader_name in shader_names {
-            shaders_gl_ids.push(self.get_shader(shader_name).gl_id);
+        for shader in shaders {
+            shaders_gl_ids.push(self.get_shader(*shader)?.gl_id);
         }
-        let program = Program::new(shaders_gl_ids);
-        self.programs.insert(name.to_owned(), program);
+        let program = Program::new(shaders_gl_ids)?;
+        let handle = self.programs.insert_named(name, program);
+        self.watcher.watch_program(handle, shaders);
+        Ok(handle)
     }
-    pub fn new_framebuffer(&mut self, name: &str, width: u32, height: u32) {
+    pub fn new_framebuffer(&mut self, name: &str, width: u32, height: u32) -> Handle<Framebuffer> {
         let mut framebuffer = Framebuffer::new();
 
         let framebuffer_texture = TextureFramebuffer::new(width as i32, height as i32);
@@ -95,49 +121,89 @@ impl Assets {
         framebuffer.gl_attach_renderbuffer(renderbuffer);
 
         framebuffer.gl_unbind();
-        self.framebuffers.insert(name.to_owned(), framebuffer);
+        self.framebuffers.insert_named(name, framebuffer)
     }
 
     // SECTION NEW FOREIGN
 
-    pub fn new_font_foreign(&mut self, name: &str, extension: &str) -> &Font {
-        let path = get_path(FOREIGN_FOLDER, &name, extension);
+    pub fn new_font_foreign(
+        &mut self,
+        name: &str,
+        extension: &str,
+    ) -> Result<Handle<Font>, AssetError> {
+        let path = get_path(FOREIGN_FOLDER, name, extension)?;
         // TODO save_image_to_png(&font.sprite.image, name).expect("Failed to save image to png");
         let font = match extension.to_lowercase().as_str() {
-            "ttf" => Font::from_ttf(path),
-            _ => panic!("Unsupported font extension: {}", extension),
+            "ttf" => Font::from_ttf(path)?,
+            _ => return Err(unsupported("font", extension)),
         };
-        self.fonts.insert(name.to_owned(), font);
-        self.get_font(name)
+        Ok(self.fonts.insert_named(name, font))
     }
-    pub fn new_image_foreign(&mut self, name: &str, extension: &str) -> &Image {
-        let path = get_path(FOREIGN_FOLDER, &name, extension);
+    pub fn new_image_foreign(
+        &mut self,
+        name: &str,
+        extension: &str,
+    ) -> Result<Handle<Image>, AssetError> {
+        let path = get_path(FOREIGN_FOLDER, name, extension)?;
         let image = match extension.to_lowercase().as_str() {
-            "jpg" | "png" => Image::from_file(path, extension),
-            _ => panic!("Unsupported image extension: {}", extension),
+            "jpg" | "jpeg" | "png" => Image::from_file(path.clone(), extension)?,
+            _ => return Err(unsupported("image", extension)),
         };
-        self.images.insert(name.to_owned(), image);
-        self.get_image(name)
+        let handle = self.images.insert_named(name, image);
+        let source = Source::Image(handle, path.clone(), extension.to_owned());
+        self.watcher.watch(&path, source);
+        Ok(handle)
     }
-    pub fn new_shader_foreign(&mut self, name: &str, extension: &str) -> &Shader {
-        let path = get_path(SHADER_FOLDER, &name, extension);
-        let shader = match extension.to_lowercase().as_str() {
-            "vs" => Shader::from_foreign(path, extension),
-            "fs" => Shader::from_foreign(path, extension),
-            _ => panic!("Unknown shader extension: '{}'.", extension),
+    /// The image file straight to the GPU, reloaded with the same parameters when it changes
+    pub fn new_texture_foreign(
+        &mut self,
+        name: &str,
+        extension: &str,
+    ) -> Result<Handle<Texture>, AssetError> {
+        let path = get_path(FOREIGN_FOLDER, name, extension)?;
+        let texture = match extension.to_lowercase().as_str() {
+            "jpg" | "jpeg" | "png" => Texture::new(Image::from_file(path.clone(), extension)?),
+            _ => return Err(unsupported("texture", extension)),
         };
-        self.shaders
-            .insert(format!("{}_{}", name, extension), shader);
-        self.get_shader(&format!("{}_{}", name, extension))
+        let handle = self.textures.insert_named(name, texture);
+        let source = Source::Texture(handle, path.clone(), extension.to_owned());
+        self.watcher.watch(&path, source);
+        Ok(handle)
     }
-    pub fn new_model_foreign(&mut self, name: &str, extension: &str) -> &Model {
-        let path = get_path(FOREIGN_FOLDER, &name, extension);
+    /// Indexed by name as `<name>_<extension>`
+    pub fn new_shader_foreign(
+        &mut self,
+        name: &str,
+        extension: &str,
+    ) -> Result<Handle<Shader>, AssetError> {
+        let path = get_path(SHADER_FOLDER, name, extension)?;
+        let shader = match extension.to_lowercase().as_str() {
+            "vs" => Shader::from_foreign(path.clone(), extension)?,
+            "fs" => Shader::from_foreign(path.clone(), extension)?,
+            _ => return Err(unsupported("shader", extension)),
+        };
+        let handle = self
+            .shaders
+            .insert_named(&format!("{}_{}", name, extension), shader);
+        let source = Source::Shader(handle, path.clone(), extension.to_owned());
+        self.watcher.watch(&path, source);
+        Ok(handle)
+    }
+    pub fn new_model_foreign(
+        &mut self,
+        name: &str,
+        extension: &str,
+    ) -> Result<Handle<Model>, AssetError> {
+        let path = get_path(FOREIGN_FOLDER, name, extension)?;
         let model = match extension.to_lowercase().as_str() {
-            "glb" | "gltf" => Model::from_gltf(path),
-            _ => panic!("Unsupported file extension"),
+            "glb" | "gltf" | "obj" => Model::from_foreign(path.clone())?,
+            _ => return Err(unsupported("model", extension)),
         };
-        self.models.insert(name.to_owned(), model);
-        self.get_model(name)
+        let dependencies = ModelData::dependencies(&path).unwrap_or_default();
+        let handle = self.models.insert_named(name, model);
+        self.watcher
+            .watch_source(&Source::Model(handle, path), &dependencies);
+        Ok(handle)
     }
     pub fn new_cubemap_foreign(
         &mut self,
@@ -148,149 +214,614 @@ impl Assets {
         images_neg_y: (&str, &str),
         images_pos_z: (&str, &str),
         images_neg_z: (&str, &str),
-    ) {
+    ) -> Result<Handle<Cubemap>, AssetError> {
         // TODO improve this
-        let path_pos_x = get_path(FOREIGN_FOLDER, images_pos_x.0, images_pos_x.1);
-        let path_neg_x = get_path(FOREIGN_FOLDER, images_neg_x.0, images_neg_x.1);
-        let path_pos_y = get_path(FOREIGN_FOLDER, images_pos_y.0, images_pos_y.1);
-        let path_neg_y = get_path(FOREIGN_FOLDER, images_neg_y.0, images_neg_y.1);
-        let path_pos_z = get_path(FOREIGN_FOLDER, images_pos_z.0, images_pos_z.1);
-        let path_neg_z = get_path(FOREIGN_FOLDER, images_neg_z.0, images_neg_z.1);
-        let cubemap = Cubemap::from_images(
-            Image::from_file(path_pos_x, images_pos_x.1),
-            Image::from_file(path_neg_x, images_neg_x.1),
-            Image::from_file(path_pos_y, images_pos_y.1),
-            Image::from_file(path_neg_y, images_neg_y.1),
-            Image::from_file(path_pos_z, images_pos_z.1),
-            Image::from_file(path_neg_z, images_neg_z.1),
-        );
-        self.cubemaps.insert(name.to_owned(), cubemap);
+        let mut faces = Vec::new();
+        for (name, extension) in [
+            images_pos_x,
+            images_neg_x,
+            images_pos_y,
+            images_neg_y,
+            images_pos_z,
+            images_neg_z,
+        ] {
+            faces.push((
+                get_path(FOREIGN_FOLDER, name, extension)?,
+                extension.to_owned(),
+            ));
+        }
+        let cubemap = cubemap_from_faces(&faces)?.upload();
+        let handle = self.cubemaps.insert_named(name, cubemap);
+        for (path, _) in &faces {
+            self.watcher
+                .watch(path, Source::Cubemap(handle, faces.clone()));
+        }
+        Ok(handle)
+    }
+
+    // SECTION NEW FOREIGN ASYNC
+    // Decoded on the loader threads, the handle is valid right away but `get` fails until the
+    // upload happens in `process_uploads`, follow it with `load_status`
+
+    pub fn new_image_foreign_async(
+        &mut self,
+        name: &str,
+        extension: &str,
+    ) -> Result<Handle<Image>, AssetError> {
+        let path = get_path(FOREIGN_FOLDER, name, extension)?;
+        match extension.to_lowercase().as_str() {
+            "jpg" | "jpeg" | "png" => (),
+            _ => return Err(unsupported("image", extension)),
+        };
+        let handle = self.images.reserve_named(name);
+        let extension = extension.to_owned();
+        let source = Source::Image(handle, path.clone(), extension.clone());
+        self.loader
+            .queue(Target::Image(handle), Some(source), move |_| {
+                Ok(Decoded::Image(Image::from_file(path, &extension)?))
+            });
+        Ok(handle)
+    }
+    pub fn new_model_foreign_async(
+        &mut self,
+        name: &str,
+        extension: &str,
+    ) -> Result<Handle<Model>, AssetError> {
+        let path = get_path(FOREIGN_FOLDER, name, extension)?;
+        match extension.to_lowercase().as_str() {
+            "glb" | "gltf" | "obj" => (),
+            _ => return Err(unsupported("model", extension)),
+        };
+        let handle = self.models.reserve_named(name);
+        let source = Source::Model(handle, path.clone());
+        self.loader
+            .queue(Target::Model(handle), Some(source), move |_| {
+                let data = ModelData::from_foreign(path.clone())?;
+                // Broken references already failed the import, the rest are watched
+                let dependencies = ModelData::dependencies(&path).unwrap_or_default();
+                Ok(Decoded::Model(data, dependencies))
+            });
+        Ok(handle)
+    }
+    pub fn new_font_foreign_async(
+        &mut self,
+        name: &str,
+        extension: &str,
+    ) -> Result<Handle<Font>, AssetError> {
+        let path = get_path(FOREIGN_FOLDER, name, extension)?;
+        match extension.to_lowercase().as_str() {
+            "ttf" => (),
+            _ => return Err(unsupported("font", extension)),
+        };
+        let handle = self.fonts.reserve_named(name);
+        self.loader.queue(Target::Font(handle), None, move |_| {
+            Ok(Decoded::Font(FontData::from_ttf(path)?))
+        });
+        Ok(handle)
+    }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_cubemap_foreign_async(
+        &mut self,
+        name: &str,
+        images_pos_x: (&str, &str),
+        images_neg_x: (&str, &str),
+        images_pos_y: (&str, &str),
+        images_neg_y: (&str, &str),
+        images_pos_z: (&str, &str),
+        images_neg_z: (&str, &str),
+    ) -> Result<Handle<Cubemap>, AssetError> {
+        let mut faces = Vec::new();
+        for (name, extension) in [
+            images_pos_x,
+            images_neg_x,
+            images_pos_y,
+            images_neg_y,
+            images_pos_z,
+            images_neg_z,
+        ] {
+            faces.push((
+                get_path(FOREIGN_FOLDER, name, extension)?,
+                extension.to_owned(),
+            ));
+        }
+        let handle = self.cubemaps.reserve_named(name);
+        let source = Source::Cubemap(handle, faces.clone());
+        self.loader
+            .queue(Target::Cubemap(handle), Some(source), move |progress| {
+                let mut images = Vec::new();
+                for (path, extension) in &faces {
+                    images.push(Image::from_file(path.clone(), extension)?);
+                    progress.set(images.len() as f32 / faces.len() as f32);
+                }
+                Ok(Decoded::Cubemap(cubemap_from_images(images)?))
+            });
+        Ok(handle)
+    }
+
+    // SECTION LOADING
+
+    /// Uploads decoded assets until `budget` is spent, at least one per call, returns the count
+    pub fn process_uploads(&mut self, budget: Duration) -> usize {
+        let start = Instant::now();
+        for target in self.loader.collect() {
+            match target {
+                Target::Image(handle) => self.images.release(handle),
+                Target::Model(handle) => self.models.release(handle),
+                Target::Font(handle) => self.fonts.release(handle),
+                Target::Cubemap(handle) => self.cubemaps.release(handle),
+            }
+        }
+
+        let mut uploaded = 0;
+        while uploaded == 0 || start.elapsed() < budget {
+            let Some(upload) = self.loader.next_upload() else {
+                break;
+            };
+            let mut dependencies = Vec::new();
+            let filled = match (&upload.target, upload.decoded) {
+                (Target::Image(handle), Decoded::Image(image)) => self.images.fill(*handle, image),
+                (Target::Model(handle), Decoded::Model(data, model_dependencies)) => {
+                    dependencies = model_dependencies;
+                    self.models.fill(*handle, data.upload())
+                }
+                (Target::Font(handle), Decoded::Font(data)) => {
+                    self.fonts.fill(*handle, data.upload())
+                }
+                (Target::Cubemap(handle), Decoded::Cubemap(data)) => {
+                    self.cubemaps.fill(*handle, data.upload())
+                }
+                _ => unreachable!("Decoded asset queued for another asset type."),
+            };
+            if let (true, Some(source)) = (filled, upload.source) {
+                self.watcher.watch_source(&source, &dependencies);
+            }
+            self.loader.uploaded(&upload.target);
+            uploaded += 1;
+        }
+        uploaded
+    }
+
+    /// Assets created synchronously are always `Ready`
+    pub fn load_status<T: 'static>(&self, handle: Handle<T>) -> LoadStatus {
+        self.loader.status(handle)
+    }
+
+    /// Why an async load failed, kept until the assets are dropped
+    pub fn load_error<T: 'static>(&self, handle: Handle<T>) -> Option<&AssetError> {
+        self.loader.error(handle)
+    }
+
+    /// Async requests not yet uploaded nor failed
+    pub fn loading_count(&self) -> usize {
+        self.loader.pending()
+    }
+
+    // SECTION NEW DATA
+
+    pub fn new_mesh_data(&mut self, name: &str, data: MeshData) -> Handle<Mesh> {
+        self.meshes.insert_named(name, data.upload())
+    }
+    pub fn new_texture_data(&mut self, name: &str, data: TextureData) -> Handle<Texture> {
+        self.textures.insert_named(name, data.upload())
+    }
+    pub fn new_model_data(&mut self, name: &str, data: ModelData) -> Handle<Model> {
+        self.models.insert_named(name, data.upload())
+    }
+    pub fn new_font_data(&mut self, name: &str, data: FontData) -> Handle<Font> {
+        self.fonts.insert_named(name, data.upload())
+    }
+    pub fn new_cubemap_data(&mut self, name: &str, data: CubemapData) -> Handle<Cubemap> {
+        self.cubemaps.insert_named(name, data.upload())
+    }
+
+    // SECTION NEW NATIVE
+    // `name` is relative to the soul folder, e.g. `models/cube` as written by revenant-cook
+
+    pub fn new_image_native(&mut self, name: &str) -> Result<Handle<Image>, AssetError> {
+        let image = load::<Image>(name)?;
+        Ok(self.images.insert_named(name, image))
+    }
+    pub fn new_mesh_native(&mut self, name: &str) -> Result<Handle<Mesh>, AssetError> {
+        let data = load::<MeshData>(name)?;
+        Ok(self.new_mesh_data(name, data))
+    }
+    pub fn new_texture_native(&mut self, name: &str) -> Result<Handle<Texture>, AssetError> {
+        let data = load::<TextureData>(name)?;
+        Ok(self.new_texture_data(name, data))
+    }
+    pub fn new_model_native(&mut self, name: &str) -> Result<Handle<Model>, AssetError> {
+        let data = load::<ModelData>(name)?;
+        Ok(self.new_model_data(name, data))
+    }
+    pub fn new_font_native(&mut self, name: &str) -> Result<Handle<Font>, AssetError> {
+        let data = load::<FontData>(name)?;
+        Ok(self.new_font_data(name, data))
+    }
+    pub fn new_cubemap_native(&mut self, name: &str) -> Result<Handle<Cubemap>, AssetError> {
+        let data = load::<CubemapData>(name)?;
+        Ok(self.new_cubemap_data(name, data))
+    }
+
+    // SECTION HOT RELOAD
+
+    /// Minimum time between two checks of the watched files, 500ms by default
+    pub fn set_hot_reload_interval(&mut self, interval: Duration) {
+        self.watcher.interval = interval;
+    }
+
+    /// Rebuilds in place every asset whose files changed since the last call, handles stay valid
+    /// An asset that fails to rebuild keeps its previous version, the errors are returned
+    pub fn reload_modified(&mut self) -> Vec<AssetError> {
+        let mut errors = Vec::new();
+        for source in self.watcher.poll() {
+            if let Err(error) = self.reload(&source) {
+                errors.push(error);
+            }
+        }
+        errors
+    }
+
+    fn reload(&mut self, source: &Source) -> Result<(), AssetError> {
+        match source {
+            Source::Image(handle, path, extension) => {
+                let image = Image::from_file(path.clone(), extension)?;
+                match self.images.get_mut(*handle) {
+                    Some(current) => *current = image,
+                    None => self.watcher.unwatch(source),
+                }
+            }
+            Source::Texture(handle, path, extension) => {
+                let image = Image::from_file(path.clone(), extension)?;
+                match self.textures.get_mut(*handle) {
+                    // The previous one frees its GL texture when dropped
+                    Some(current) => {
+                        *current = TextureData {
+                            image,
+                            ..current.to_data()
+                        }
+                        .upload()
+                    }
+                    None => self.watcher.unwatch(source),
+                }
+            }
+            Source::Shader(handle, path, extension) => {
+                let shader = Shader::from_foreign(path.clone(), extension)?;
+                if self.shaders.get(*handle).is_none() {
+                    self.watcher.unwatch(source);
+                    return Ok(());
+                }
+                // Relink everything first, a single failure keeps the old shader and programs
+                let mut programs = Vec::new();
+                for (program, shaders) in &self.watcher.programs {
+                    if !shaders.contains(handle) || self.programs.get(*program).is_none() {
+                        continue;
+                    }
+                    let mut shaders_gl_ids = Vec::new();
+                    for other in shaders {
+                        shaders_gl_ids.push(if other == handle {
+                            shader.gl_id
+                        } else {
+                            self.get_shader(*other)?.gl_id
+                        });
+                    }
+                    programs.push((*program, Program::new(shaders_gl_ids)?));
+                }
+                for (program, relinked) in programs {
+                    if let Some(current) = self.programs.get_mut(program) {
+                        *current = relinked;
+                    }
+                }
+                if let Some(current) = self.shaders.get_mut(*handle) {
+                    *current = shader;
+                }
+            }
+            Source::Model(handle, path) => {
+                let model = Model::from_foreign(path.clone())?;
+                match self.models.get_mut(*handle) {
+                    Some(current) => {
+                        *current = model;
+                        // The model may reference other files now
+                        let dependencies = ModelData::dependencies(path).unwrap_or_default();
+                        self.watcher.watch_source(source, &dependencies);
+                    }
+                    None => self.watcher.unwatch(source),
+                }
+            }
+            Source::Cubemap(handle, faces) => {
+                let cubemap = cubemap_from_faces(faces)?.upload();
+                match self.cubemaps.get_mut(*handle) {
+                    Some(current) => *current = cubemap,
+                    None => self.watcher.unwatch(source),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // SECTION LIGHTS
+
+    /// Uses the program and sets its `dirlight`, `pointlights` and `spotlight` uniforms,
+    /// the first directional and spot lights win and extra point lights are ignored
+    pub fn upload_lights(&self, program: Handle<Program>) -> Result<(), AssetError> {
+        let program = self.get_program(program)?;
+        program.use_program();
+
+        let dirlight = self.dirlights.values().next();
+        program.set_uniform_bool("has_dirlight", dirlight.is_some());
+        if let Some(dirlight) = dirlight {
+            dirlight.gl_upload(program, "dirlight");
+        }
+
+        let mut pointlight_count = 0;
+        for (index, pointlight) in self.pointlights.values().take(MAX_POINT_LIGHTS).enumerate() {
+            pointlight.gl_upload(program, &format!("pointlights[{}]", index));
+            pointlight_count += 1;
+        }
+        program.set_uniform_int("pointlight_count", pointlight_count);
+
+        let spotlight = self.spotlights.values().next();
+        program.set_uniform_bool("has_spotlight", spotlight.is_some());
+        if let Some(spotlight) = spotlight {
+            spotlight.gl_upload(program, "spotlight");
+        }
+
+        Ok(())
     }
 
     // SECTION GET
 
-    pub fn get_image(&self, name: &str) -> &Image {
+    pub fn get_image(&self, handle: Handle<Image>) -> Result<&Image, AssetError> {
         self.images
-            .get(name)
-            .expect(&format!("Image '{}' not found.", name))
+            .get(handle)
+            .ok_or_else(|| stale("Image", handle))
     }
-    pub fn get_texture(&self, name: &str) -> &Texture {
+    pub fn get_texture(&self, handle: Handle<Texture>) -> Result<&Texture, AssetError> {
         self.textures
-            .get(name)
-            .expect(&format!("Texture '{}' not found.", name))
+            .get(handle)
+            .ok_or_else(|| stale("Texture", handle))
     }
-    pub fn get_font(&self, name: &str) -> &Font {
-        self.fonts
-            .get(name)
-            .expect(&format!("Font '{}' not found.", name))
+    pub fn get_font(&self, handle: Handle<Font>) -> Result<&Font, AssetError> {
+        self.fonts.get(handle).ok_or_else(|| stale("Font", handle))
     }
-    pub fn get_camera(&self, name: &str) -> &Camera {
+    pub fn get_camera(&self, handle: Handle<Camera>) -> Result<&Camera, AssetError> {
         self.cameras
-            .get(name)
-            .expect(&format!("Camera '{}' not found.", name))
+            .get(handle)
+            .ok_or_else(|| stale("Camera", handle))
     }
-    pub fn get_pointlight(&self, name: &str) -> &PointLight {
+    pub fn get_pointlight(&self, handle: Handle<PointLight>) -> Result<&PointLight, AssetError> {
         self.pointlights
-            .get(name)
-            .expect(&format!("PointLight '{}' not found.", name))
+            .get(handle)
+            .ok_or_else(|| stale("PointLight", handle))
     }
-    pub fn get_dirlight(&self, name: &str) -> &DirLight {
+    pub fn get_dirlight(&self, handle: Handle<DirLight>) -> Result<&DirLight, AssetError> {
         self.dirlights
-            .get(name)
-            .expect(&format!("DirLight '{}' not found.", name))
+            .get(handle)
+            .ok_or_else(|| stale("DirLight", handle))
     }
-    pub fn get_spotlight(&self, name: &str) -> &SpotLight {
+    pub fn get_spotlight(&self, handle: Handle<SpotLight>) -> Result<&SpotLight, AssetError> {
         self.spotlights
-            .get(name)
-            .expect(&format!("SpotLight '{}' not found.", name))
+            .get(handle)
+            .ok_or_else(|| stale("SpotLight", handle))
     }
-    pub fn get_mesh(&self, name: &str) -> &Mesh {
-        self.meshes
-            .get(name)
-            .expect(&format!("Mesh '{}' not found.", name))
+    pub fn get_mesh(&self, handle: Handle<Mesh>) -> Result<&Mesh, AssetError> {
+        self.meshes.get(handle).ok_or_else(|| stale("Mesh", handle))
     }
-    pub fn get_model(&self, name: &str) -> &Model {
+    pub fn get_model(&self, handle: Handle<Model>) -> Result<&Model, AssetError> {
         self.models
-            .get(name)
-            .expect(&format!("Model '{}' not found.", name))
+            .get(handle)
+            .ok_or_else(|| stale("Model", handle))
     }
-    pub fn get_shader(&self, name: &str) -> &Shader {
+    pub fn get_shader(&self, handle: Handle<Shader>) -> Result<&Shader, AssetError> {
         self.shaders
-            .get(name)
-            .expect(&format!("Shader '{}' not found.", name))
+            .get(handle)
+            .ok_or_else(|| stale("Shader", handle))
     }
-    pub fn get_program(&self, name: &str) -> &Program {
+    pub fn get_program(&self, handle: Handle<Program>) -> Result<&Program, AssetError> {
         self.programs
-            .get(name)
-            .expect(&format!("Program '{}' not found.", name))
+            .get(handle)
+            .ok_or_else(|| stale("Program", handle))
     }
-    pub fn get_framebuffer(&self, name: &str) -> &Framebuffer {
+    pub fn get_framebuffer(&self, handle: Handle<Framebuffer>) -> Result<&Framebuffer, AssetError> {
         self.framebuffers
-            .get(name)
-            .expect(&format!("Framebuffer '{}' not found.", name))
+            .get(handle)
+            .ok_or_else(|| stale("Framebuffer", handle))
     }
-    pub fn get_cubemap(&self, name: &str) -> &Cubemap {
+    pub fn get_cubemap(&self, handle: Handle<Cubemap>) -> Result<&Cubemap, AssetError> {
         self.cubemaps
-            .get(name)
-            .expect(&format!("Cubemap '{}' not found.", name))
+            .get(handle)
+            .ok_or_else(|| stale("Cubemap", handle))
     }
 
     // SECTION GET MUT
 
-    pub fn get_mut_image(&mut self, name: &str) -> &mut Image {
+    pub fn get_mut_image(&mut self, handle: Handle<Image>) -> Result<&mut Image, AssetError> {
         self.images
-            .get_mut(name)
-            .expect(&format!("Image '{}' not found.", name))
+            .get_mut(handle)
+            .ok_or_else(|| stale("Image", handle))
     }
-    pub fn get_mut_texture(&mut self, name: &str) -> &mut Texture {
+    pub fn get_mut_texture(&mut self, handle: Handle<Texture>) -> Result<&mut Texture, AssetError> {
         self.textures
-            .get_mut(name)
-            .expect(&format!("Texture '{}' not found.", name))
+            .get_mut(handle)
+            .ok_or_else(|| stale("Texture", handle))
     }
-    pub fn get_mut_font(&mut self, name: &str) -> &mut Font {
+    pub fn get_mut_font(&mut self, handle: Handle<Font>) -> Result<&mut Font, AssetError> {
         self.fonts
-            .get_mut(name)
-            .expect(&format!("Font '{}' not found.", name))
+            .get_mut(handle)
+            .ok_or_else(|| stale("Font", handle))
     }
-    pub fn get_mut_camera(&mut self, name: &str) -> &mut Camera {
+    pub fn get_mut_camera(&mut self, handle: Handle<Camera>) -> Result<&mut Camera, AssetError> {
         self.cameras
-            .get_mut(name)
-            .expect(&format!("Camera '{}' not found.", name))
+            .get_mut(handle)
+            .ok_or_else(|| stale("Camera", handle))
     }
-    pub fn get_mut_pointlight(&mut self, name: &str) -> &mut PointLight {
+    pub fn get_mut_pointlight(
+        &mut self,
+        handle: Handle<PointLight>,
+    ) -> Result<&mut PointLight, AssetError> {
         self.pointlights
-            .get_mut(name)
-            .expect(&format!("PointLight '{}' not found.", name))
+            .get_mut(handle)
+            .ok_or_else(|| stale("PointLight", handle))
     }
-    pub fn get_mut_dirlight(&mut self, name: &str) -> &mut DirLight {
+    pub fn get_mut_dirlight(
+        &mut self,
+        handle: Handle<DirLight>,
+    ) -> Result<&mut DirLight, AssetError> {
         self.dirlights
-            .get_mut(name)
-            .expect(&format!("DirLight '{}' not found.", name))
+            .get_mut(handle)
+            .ok_or_else(|| stale("DirLight", handle))
     }
-    pub fn get_mut_spotlight(&mut self, name: &str) -> &mut SpotLight {
+    pub fn get_mut_spotlight(
+        &mut self,
+        handle: Handle<SpotLight>,
+    ) -> Result<&mut SpotLight, AssetError> {
         self.spotlights
-            .get_mut(name)
-            .expect(&format!("SpotLight '{}' not found.", name))
+            .get_mut(handle)
+            .ok_or_else(|| stale("SpotLight", handle))
     }
-    pub fn get_mut_mesh(&mut self, name: &str) -> &mut Mesh {
+    pub fn get_mut_mesh(&mut self, handle: Handle<Mesh>) -> Result<&mut Mesh, AssetError> {
         self.meshes
-            .get_mut(name)
-            .expect(&format!("Mesh '{}' not found.", name))
+            .get_mut(handle)
+            .ok_or_else(|| stale("Mesh", handle))
     }
-    pub fn get_mut_model(&mut self, name: &str) -> &mut Model {
+    pub fn get_mut_model(&mut self, handle: Handle<Model>) -> Result<&mut Model, AssetError> {
         self.models
-            .get_mut(name)
-            .expect(&format!("Model '{}' not found.", name))
+            .get_mut(handle)
+            .ok_or_else(|| stale("Model", handle))
     }
-    pub fn get_mut_shader(&mut self, name: &str) -> &mut Shader {
+    pub fn get_mut_shader(&mut self, handle: Handle<Shader>) -> Result<&mut Shader, AssetError> {
         self.shaders
-            .get_mut(name)
-            .expect(&format!("Shader '{}' not found.", name))
+            .get_mut(handle)
+            .ok_or_else(|| stale("Shader", handle))
+    }
+    pub fn get_mut_program(&mut self, handle: Handle<Program>) -> Result<&mut Program, AssetError> {
+        self.programs
+            .get_mut(handle)
+            .ok_or_else(|| stale("Program", handle))
     }
-    pub fn get_mut_framebuffer(&mut self, name: &str) -> &mut Framebuffer {
+    pub fn get_mut_framebuffer(
+        &mut self,
+        handle: Handle<Framebuffer>,
+    ) -> Result<&mut Framebuffer, AssetError> {
         self.framebuffers
-            .get_mut(name)
-            .expect(&format!("Framebuffer '{}' not found.", name))
+            .get_mut(handle)
+            .ok_or_else(|| stale("Framebuffer", handle))
+    }
+    pub fn get_mut_cubemap(&mut self, handle: Handle<Cubemap>) -> Result<&mut Cubemap, AssetError> {
+        self.cubemaps
+            .get_mut(handle)
+            .ok_or_else(|| stale("Cubemap", handle))
+    }
+
+    // SECTION HANDLE BY NAME
+
+    pub fn image_handle(&self, name: &str) -> Result<Handle<Image>, AssetError> {
+        self.images
+            .handle(name)
+            .ok_or_else(|| missing("Image", name))
+    }
+    pub fn texture_handle(&self, name: &str) -> Result<Handle<Texture>, AssetError> {
+        self.textures
+            .handle(name)
+            .ok_or_else(|| missing("Texture", name))
+    }
+    pub fn font_handle(&self, name: &str) -> Result<Handle<Font>, AssetError> {
+        self.fonts.handle(name).ok_or_else(|| missing("Font", name))
+    }
+    pub fn camera_handle(&self, name: &str) -> Result<Handle<Camera>, AssetError> {
+        self.cameras
+            .handle(name)
+            .ok_or_else(|| missing("Camera", name))
+    }
+    pub fn pointlight_handle(&self, name: &str) -> Result<Handle<PointLight>, AssetError> {
+        self.pointlights
+            .handle(name)
+            .ok_or_else(|| missing("PointLight", name))
+    }
+    pub fn dirlight_handle(&self, name: &str) -> Result<Handle<DirLight>, AssetError> {
+        self.dirlights
+            .handle(name)
+            .ok_or_else(|| missing("DirLight", name))
+    }
+    pub fn spotlight_handle(&self, name: &str) -> Result<Handle<SpotLight>, AssetError> {
+        self.spotlights
+            .handle(name)
+            .ok_or_else(|| missing("SpotLight", name))
+    }
+    pub fn mesh_handle(&self, name: &str) -> Result<Handle<Mesh>, AssetError> {
+        self.meshes
+            .handle(name)
+            .ok_or_else(|| missing("Mesh", name))
+    }
+    pub fn model_handle(&self, name: &str) -> Result<Handle<Model>, AssetError> {
+        self.models
+            .handle(name)
+            .ok_or_else(|| missing("Model", name))
+    }
+    pub fn shader_handle(&self, name: &str) -> Result<Handle<Shader>, AssetError> {
+        self.shaders
+            .handle(name)
+            .ok_or_else(|| missing("Shader", name))
+    }
+    pub fn program_handle(&self, name: &str) -> Result<Handle<Program>, AssetError> {
+        self.programs
+            .handle(name)
+            .ok_or_else(|| missing("Program", name))
+    }
+    pub fn framebuffer_handle(&self, name: &str) -> Result<Handle<Framebuffer>, AssetError> {
+        self.framebuffers
+            .handle(name)
+            .ok_or_else(|| missing("Framebuffer", name))
+    }
+    pub fn cubemap_handle(&self, name: &str) -> Result<Handle<Cubemap>, AssetError> {
+        self.cubemaps
+            .handle(name)
+            .ok_or_else(|| missing("Cubemap", name))
+    }
+
+    // SECTION REMOVE
+
+    pub fn remove_image(&mut self, handle: Handle<Image>) -> Option<Image> {
+        self.loader.cancel(handle);
+        self.images.remove(handle)
+    }
+    pub fn remove_texture(&mut self, handle: Handle<Texture>) -> Option<Texture> {
+        self.textures.remove(handle)
+    }
+    pub fn remove_font(&mut self, handle: Handle<Font>) -> Option<Font> {
+        self.loader.cancel(handle);
+        self.fonts.remove(handle)
+    }
+    pub fn remove_camera(&mut self, handle: Handle<Camera>) -> Option<Camera> {
+        self.cameras.remove(handle)
+    }
+    pub fn remove_pointlight(&mut self, handle: Handle<PointLight>) -> Option<PointLight> {
+        self.pointlights.remove(handle)
+    }
+    pub fn remove_dirlight(&mut self, handle: Handle<DirLight>) -> Option<DirLight> {
+        self.dirlights.remove(handle)
+    }
+    pub fn remove_spotlight(&mut self, handle: Handle<SpotLight>) -> Option<SpotLight> {
+        self.spotlights.remove(handle)
+    }
+    pub fn remove_mesh(&mut self, handle: Handle<Mesh>) -> Option<Mesh> {
+        self.meshes.remove(handle)
+    }
+    pub fn remove_model(&mut self, handle: Handle<Model>) -> Option<Model> {
+        self.loader.cancel(handle);
+        self.models.remove(handle)
+    }
+    pub fn remove_shader(&mut self, handle: Handle<Shader>) -> Option<Shader> {
+        self.shaders.remove(handle)
+    }
+    pub fn remove_program(&mut self, handle: Handle<Program>) -> Option<Program> {
+        self.programs.remove(handle)
+    }
+    pub fn remove_framebuffer(&mut self, handle: Handle<Framebuffer>) -> Option<Framebuffer> {
+        self.framebuffers.remove(handle)
+    }
+    pub fn remove_cubemap(&mut self, handle: Handle<Cubemap>) -> Option<Cubemap> {
+        self.loader.cancel(handle);
+        self.cubemaps.remove(handle)
     }
 }
 
@@ -304,7 +835,7 @@ const NATIVE_EXT: &str = "soul";
 // OPTIMIZE there's certainly a better way, compiler should be able to optimize this
 #[cfg(not(debug_assertions))]
 fn assets_path() -> PathBuf {
-    let exe_path = env::current_exe().expect("Failed to get current executable path.");
+    let exe_path = std::env::current_exe().expect("Failed to get current executable path.");
     let mut assets_path =
         PathBuf::from(exe_path.parent().expect("Failed to get parent directory."));
     assets_path.push(ASSETS_FOLDER);
@@ -318,56 +849,91 @@ fn assets_path() -> PathBuf {
     assets_path
 }
 
-fn get_path(folder: &str, name: &str, extension: &str) -> PathBuf {
-    let path = assets_path()
+fn new_path(folder: &str, name: &str, extension: &str) -> PathBuf {
+    assets_path()
         .join(folder)
         .join(name)
-        .with_extension(extension);
+        .with_extension(extension)
+}
+
+fn get_path(folder: &str, name: &str, extension: &str) -> Result<PathBuf, AssetError> {
+    let path = new_path(folder, name, extension);
     if !path.exists() {
-        panic!("File does not exist '{:?}'", path);
+        return Err(AssetError::Io(path, ErrorKind::NotFound.into()));
+    }
+    Ok(path)
+}
+
+/// Creates the parent folders if needed
+pub fn write_file(path: PathBuf, data: &[u8]) -> Result<(), AssetError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|error| AssetError::Io(parent.to_owned(), error))?;
     }
-    path
+    std::fs::write(&path, data).map_err(|error| AssetError::Io(path, error))
+}
+
+fn cubemap_from_faces(faces: &[(PathBuf, String)]) -> Result<CubemapData, AssetError> {
+    let mut images = Vec::new();
+    for (path, extension) in faces {
+        images.push(Image::from_file(path.clone(), extension)?);
+    }
+    cubemap_from_images(images)
+}
+
+fn cubemap_from_images(images: Vec<Image>) -> Result<CubemapData, AssetError> {
+    let [pos_x, neg_x, pos_y, neg_y, pos_z, neg_z]: [Image; 6] = images
+        .try_into()
+        .map_err(|_| AssetError::Decode("A cubemap needs exactly 6 faces.".to_owned()))?;
+    Ok(CubemapData::from_images(
+        pos_x, neg_x, pos_y, neg_y, pos_z, neg_z,
+    ))
+}
+
+fn missing(kind: &'static str, name: &str) -> AssetError {
+    AssetError::Missing {
+        kind,
+        name: name.to_owned(),
+    }
+}
+
+fn stale<T>(kind: &'static str, handle: Handle<T>) -> AssetError {
+    AssetError::Missing {
+        kind,
+        name: handle.to_string(),
+    }
+}
+
+fn unsupported(kind: &str, extension: &str) -> AssetError {
+    AssetError::UnsupportedFormat(format!("{} extension '{}'", kind, extension))
 }
 
 // TODO load and export with a prefix from the struct name
 // NOTE either this or you make a shit tons of folders, what will it be cupcake?
-pub fn save<T>(name: &str, data: T)
+pub fn save<T>(name: &str, data: &T, compression: Compression) -> Result<(), AssetError>
 where
-    T: Serialize,
+    T: Soul,
 {
-    let path = get_path(NATIVE_FOLDER, &name, NATIVE_EXT);
-    let mut file = File::create(path).expect("Failed to create file.");
-    let encoded = serialize(&data).expect("Failed to serialize data.");
-    file.write_all(&encoded).expect("Failed to write to file.");
+    let encoded = soul::encode(data, compression)?;
+    write_file(new_path(NATIVE_FOLDER, name, NATIVE_EXT), &encoded)
 }
-pub fn load<T>(name: &str) -> T
+pub fn load<T>(name: &str) -> Result<T, AssetError>
 where
-    T: DeserializeOwned,
+    T: Soul,
 {
-    let path = get_path(NATIVE_FOLDER, &name, NATIVE_EXT);
-    let mut file = File::open(path).expect("Failed to open file.");
-    let mut encoded = Vec::new();
-    file.read_to_end(&mut encoded)
-        .expect("Failed to read file.");
-    let serialized = deserialize::<T>(&encoded).expect("Failed to deserialize data.");
-    serialized
+    let path = get_path(NATIVE_FOLDER, name, NATIVE_EXT)?;
+    let encoded = std::fs::read(&path).map_err(|error| AssetError::Io(path, error))?;
+    soul::decode(&encoded)
 }
 
-pub fn save_image_to_png(image: &Image, name: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let data = image.data.clone();
-    let path = get_path(FOREIGN_FOLDER, &name, "png");
-    let image_buffer =
-        ImageBuffer::from_raw(image.width, image.height, data).ok_or("Invalid image data")?;
-    let dynamic_image = DynamicImage::ImageRgba8(image_buffer);
-    dynamic_image.save_with_format(path, ImageFormat::Png)?;
-    Ok(())
+pub fn save_image_to_png(image: &Image, name: &str) -> Result<(), AssetError> {
+    image.to_file(new_path(FOREIGN_FOLDER, name, "png"))
 }
 
-pub fn save_json<T>(name: &str, data: T)
+pub fn save_json<T>(name: &str, data: T) -> Result<(), AssetError>
 where
     T: Serialize,
 {
-    let path = get_path(NATIVE_FOLDER, &name, "json");
-    let serialized = serde_json::to_string(&data).expect("Failed to serialize data.");
-    std::fs::write(path, serialized).expect("Failed to write to file.");
+    let serialized = serde_json::to_string(&data)?;
+    write_file(new_path(NATIVE_FOLDER, name, "json"), serialized.as_bytes())
 }
@@ -0,0 +1,69 @@
+use std::{fmt, io, path::PathBuf};
+
+#[derive(Debug)]
+pub enum AssetError {
+    Io(PathBuf, io::Error),
+    Decode(String),
+    UnsupportedFormat(String),
+    ShaderCompile(String),
+    ProgramLink(String),
+    Missing { kind: &'static str, name: String },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Io(path, error) => write!(f, "IO error on '{}': {}", path.display(), error),
+            AssetError::Decode(why) => write!(f, "Failed to decode asset: {}", why),
+            AssetError::UnsupportedFormat(format) => write!(f, "Unsupported format: {}", format),
+            AssetError::ShaderCompile(log) => write!(f, "Failed to compile shader: {}", log),
+            AssetError::ProgramLink(log) => write!(f, "Failed to link shader program: {}", log),
+            AssetError::Missing { kind, name } => write!(f, "{} '{}' not found.", kind, name),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssetError::Io(_, error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<::image::ImageError> for AssetError {
+    fn from(error: ::image::ImageError) -> Self {
+        AssetError::Decode(error.to_string())
+    }
+}
+
+impl From<gltf::Error> for AssetError {
+    fn from(error: gltf::Error) -> Self {
+        AssetError::Decode(error.to_string())
+    }
+}
+
+impl From<freetype::Error> for AssetError {
+    fn from(error: freetype::Error) -> Self {
+        AssetError::Decode(error.to_string())
+    }
+}
+
+impl From<base64::DecodeError> for AssetError {
+    fn from(error: base64::DecodeError) -> Self {
+        AssetError::Decode(error.to_string())
+    }
+}
+
+impl From<bincode::Error> for AssetError {
+    fn from(error: bincode::Error) -> Self {
+        AssetError::Decode(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AssetError {
+    fn from(error: serde_json::Error) -> Self {
+        AssetError::Decode(error.to_string())
+    }
+}
@@ -3,10 +3,14 @@ use image::{ImageBuffer, Rgba};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, path::PathBuf};
 
-use super::{image::Image, texture::TextureKind, Texture};
+use super::{
+    error::AssetError,
+    image::Image,
+    texture::{Texture, TextureData, TextureKind},
+};
 
 // TODO remove debug everywhere
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Debug)]
 pub struct Font {
     pub sprite: Texture,
     pub glyphs: HashMap<char, Glyph>,
@@ -27,26 +31,49 @@ pub struct Glyph {
     pub advance_y: i64,
 }
 
+/// CPU side of a `Font`, the glyphs are rasterized but no GL is involved until `upload`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FontData {
+    pub sprite: TextureData,
+    pub glyphs: HashMap<char, Glyph>,
+    pub width: u32,
+    pub height: u32,
+    pub line_height: u32,
+}
+
 impl Font {
-    pub fn from_ttf(path: PathBuf) -> Font {
-        let library = Library::init().expect("Could not init freetype library");
-        let face = library.new_face(path, 0).expect("Could not create face.");
+    pub fn from_ttf(path: PathBuf) -> Result<Font, AssetError> {
+        Ok(FontData::from_ttf(path)?.upload())
+    }
+}
+
+impl FontData {
+    pub fn upload(self) -> Font {
+        Font {
+            sprite: self.sprite.upload(),
+            glyphs: self.glyphs,
+            width: self.width,
+            height: self.height,
+            line_height: self.line_height,
+        }
+    }
+
+    pub fn from_ttf(path: PathBuf) -> Result<FontData, AssetError> {
+        let library = Library::init()?;
+        let face = library.new_face(path, 0)?;
         let font_width: u32 = 30;
         let font_height: u32 = 30;
         let line_height = font_height / 2;
         let total_glyphs = face.num_glyphs() as u32;
-        face.set_pixel_sizes(font_width, font_height)
-            .expect("Could not set pixel sizes.");
+        face.set_pixel_sizes(font_width, font_height)?;
 
         let mut omega_glyph_width = 0;
         let mut highest_glyph_height = 0;
         let mut glyphs: HashMap<char, Glyph> = HashMap::new();
         for glyph_index in 0..total_glyphs {
-            face.load_glyph(glyph_index, freetype::face::LoadFlag::RENDER)
-                .expect("Could not load glyph.");
+            face.load_glyph(glyph_index, freetype::face::LoadFlag::RENDER)?;
 
-            let character = std::char::from_u32(glyph_index)
-                .expect("Could not convert glyph index to character.");
+            let character = glyph_character(glyph_index)?;
 
             let glyphslot = face.glyph();
             let bitmap = glyphslot.bitmap();
@@ -65,10 +92,11 @@ impl Font {
             omega_glyph_width += glyph.width as u32;
             highest_glyph_height = highest_glyph_height.max(glyph.height as u32);
 
-            if glyphs.get(&character).is_none() {
-                glyphs.insert(character, glyph);
-            } else {
-                panic!("Duplicate character in font.");
+            if glyphs.insert(character, glyph).is_some() {
+                return Err(AssetError::Decode(format!(
+                    "Duplicate character '{}' in font.",
+                    character
+                )));
             }
         }
 
@@ -82,18 +110,18 @@ impl Font {
         );
 
         for glyph_index in 0..total_glyphs {
-            face.load_glyph(glyph_index, freetype::face::LoadFlag::RENDER)
-                .expect("Could not load glyph.");
+            face.load_glyph(glyph_index, freetype::face::LoadFlag::RENDER)?;
 
-            let character = std::char::from_u32(glyph_index)
-                .expect("Could not convert glyph index to character.");
+            let character = glyph_character(glyph_index)?;
 
             let glyphslot = face.glyph();
             let bitmap = glyphslot.bitmap();
             let bitmap_width = bitmap.width() as u32;
             let bitmap_height = bitmap.rows() as u32;
 
-            let glyph = glyphs.get_mut(&character).expect("Could not get glyph.");
+            let glyph = glyphs
+                .get_mut(&character)
+                .expect("Glyph registered in the first pass.");
             glyph.sprite_x = sprite_x;
             glyph.sprite_y = sprite_y;
 
@@ -110,21 +138,35 @@ impl Font {
             sprite_x += bitmap_width;
         }
 
-        let image = Image::from_data(&sprite_sheet.into_raw());
+        let image = Image::from_raw(
+            sprite_sheet.width(),
+            sprite_sheet.height(),
+            gl::RGBA,
+            sprite_sheet.into_raw(),
+        );
 
-        let mut sprite = Texture::new(image);
+        let mut sprite = TextureData::new(image);
         sprite.kind = TextureKind::Diffuse;
         sprite.gl_s_wrapping = gl::REPEAT;
         sprite.gl_t_wrapping = gl::REPEAT;
         sprite.gl_min_filtering = gl::LINEAR;
         sprite.gl_mag_filtering = gl::LINEAR;
 
-        Font {
+        Ok(FontData {
             sprite,
             glyphs,
             width: font_width,
             height: font_height,
             line_height,
-        }
+        })
     }
 }
+
+fn glyph_character(glyph_index: u32) -> Result<char, AssetError> {
+    std::char::from_u32(glyph_index).ok_or_else(|| {
+        AssetError::Decode(format!(
+            "Glyph index {} is not a valid character.",
+            glyph_index
+        ))
+    })
+}
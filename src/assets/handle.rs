@@ -0,0 +1,198 @@
+use std::{collections::HashMap, fmt, hash::Hash, marker::PhantomData};
+
+/// Generational index into an `Assets` storage, stale once its asset is removed
+pub struct Handle<T> {
+    index: u32,
+    generation: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+// Derives would require T: Clone, T: PartialEq...
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for Handle<T> {}
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+impl<T> Eq for Handle<T> {}
+impl<T> Hash for Handle<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle(#{}v{})", self.index, self.generation)
+    }
+}
+impl<T> fmt::Display for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}v{}", self.index, self.generation)
+    }
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Slot map with an optional name index
+pub(crate) struct Storage<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    names: HashMap<String, Handle<T>>,
+}
+
+impl<T> Storage<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, value: T) -> Handle<T> {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.value = Some(value);
+            return Handle {
+                index,
+                generation: slot.generation,
+                _marker: PhantomData,
+            };
+        }
+        self.slots.push(Slot {
+            generation: 0,
+            value: Some(value),
+        });
+        Handle {
+            index: self.slots.len() as u32 - 1,
+            generation: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Inserting under an existing name replaces the asset in place, the handle stays valid. A
+    /// reserved slot is filled the same way, its pending `fill` then replaces the value again
+    pub(crate) fn insert_named(&mut self, name: &str, value: T) -> Handle<T> {
+        if let Some(handle) = self.names.get(name).copied() {
+            if let Some(slot) = self
+                .slots
+                .get_mut(handle.index as usize)
+                .filter(|slot| slot.generation == handle.generation)
+            {
+                slot.value = Some(value);
+                return handle;
+            }
+        }
+        let handle = self.insert(value);
+        self.names.insert(name.to_owned(), handle);
+        handle
+    }
+
+    /// Takes a slot and a name for an asset that is not there yet, `get` fails until `fill`
+    pub(crate) fn reserve_named(&mut self, name: &str) -> Handle<T> {
+        let handle = match self.free.pop() {
+            Some(index) => Handle {
+                index,
+                generation: self.slots[index as usize].generation,
+                _marker: PhantomData,
+            },
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: None,
+                });
+                Handle {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                    _marker: PhantomData,
+                }
+            }
+        };
+        self.names.insert(name.to_owned(), handle);
+        handle
+    }
+
+    /// Returns false if the handle is stale
+    pub(crate) fn fill(&mut self, handle: Handle<T>, value: T) -> bool {
+        match self
+            .slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+        {
+            Some(slot) => {
+                slot.value = Some(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Gives back a reserved slot that will never be filled
+    pub(crate) fn release(&mut self, handle: Handle<T>) {
+        let Some(slot) = self
+            .slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation && slot.value.is_none())
+        else {
+            return;
+        };
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.index);
+        self.names.retain(|_, named| *named != handle);
+    }
+
+    pub(crate) fn get(&self, handle: Handle<T>) -> Option<&T> {
+        self.slots
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.value.as_ref())
+    }
+
+    pub(crate) fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.value.as_mut())
+    }
+
+    /// Reserved slots are given back too, their `fill` then fails
+    pub(crate) fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        let slot = self
+            .slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)?;
+        let value = slot.value.take();
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.index);
+        self.names.retain(|_, named| *named != handle);
+        value
+    }
+
+    /// Filled slots, in slot order
+    pub(crate) fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+
+    pub(crate) fn handle(&self, name: &str) -> Option<Handle<T>> {
+        self.names.get(name).copied()
+    }
+}
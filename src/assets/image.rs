@@ -1,7 +1,9 @@
 use gl::types::GLenum;
-use image::DynamicImage;
+use image::{DynamicImage, ImageBuffer, ImageFormat};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use super::{error::AssetError, uri};
 
 // TODO remove debug everywhere
 #[non_exhaustive]
@@ -16,54 +18,156 @@ pub struct Image {
 }
 
 impl Image {
+    /// Grey, 16 bit and float images are converted to 8 bit RGB or RGBA, the formats textures use
     pub fn new(dynamic_image: DynamicImage) -> Self {
+        let (width, height) = (dynamic_image.width(), dynamic_image.height());
+        let (gl_format, data) = if dynamic_image.color().has_alpha() {
+            (gl::RGBA, dynamic_image.into_rgba8().into_raw())
+        } else {
+            (gl::RGB, dynamic_image.into_rgb8().into_raw())
+        };
+        let image = Self {
+            gl_format,
+            gl_target: gl::TEXTURE_2D,
+            width,
+            height,
+            data,
+        };
+        #[cfg(debug_assertions)]
+        integrity_check(&image);
+        image
+    }
+
+    pub fn from_raw(width: u32, height: u32, gl_format: GLenum, data: Vec<u8>) -> Self {
         let image = Self {
-            gl_format: match dynamic_image.color() {
-                image::ColorType::Rgb8 => gl::RGB,
-                image::ColorType::Rgba8 => gl::RGBA,
-                _ => panic!("Texture format not supported."),
-            },
+            gl_format,
             gl_target: gl::TEXTURE_2D,
-            width: dynamic_image.width(),
-            height: dynamic_image.height(),
-            data: match dynamic_image {
-                DynamicImage::ImageRgb8(texture_image) => texture_image.into_raw(),
-                DynamicImage::ImageRgba8(texture_image) => texture_image.into_raw(),
-                _ => panic!("Image format not supported"),
-            },
+            width,
+            height,
+            data,
         };
         #[cfg(debug_assertions)]
         integrity_check(&image);
         image
     }
 
-    pub fn from_file(path: PathBuf, extension: &str) -> Self {
-        let image = match extension.to_lowercase().as_str() {
-            "jpg" | "png" => image::open(path).expect("Failed to load image."),
-            _ => panic!("Unsupported image extension: {}", extension),
+    pub fn from_file(path: PathBuf, extension: &str) -> Result<Self, AssetError> {
+        match extension.to_lowercase().as_str() {
+            "jpg" | "jpeg" | "png" => {}
+            _ => {
+                return Err(AssetError::UnsupportedFormat(format!(
+                    "image extension '{}'",
+                    extension
+                )))
+            }
         };
+        let data = std::fs::read(&path).map_err(|error| AssetError::Io(path, error))?;
+
+        Self::from_data(&data)
+    }
+
+    pub fn from_data(data: &[u8]) -> Result<Self, AssetError> {
+        Ok(Self::new(image::load_from_memory(data)?))
+    }
+
+    /// Relative URIs are resolved against `folder`, the one of the file referencing the image
+    pub fn from_uri(uri: &str, folder: &Path) -> Result<Image, AssetError> {
+        Self::from_data(&uri::read(uri, folder)?)
+    }
 
-        Self::new(image)
+    pub fn width(&self) -> u32 {
+        self.width
     }
 
-    // TODO ability to fail gracefully just like me :D
-    pub fn from_data(data: &[u8]) -> Self {
-        let image = image::load_from_memory(data).expect("Failed to load image.");
-        Self::new(image)
+    pub fn height(&self) -> u32 {
+        self.height
     }
 
-    pub fn from_uri(uri: &str) -> Image {
-        let url = url::Url::parse(uri).expect("Failed to parse image URI.");
-        if url.scheme() != "file" {
-            panic!("Unsupported image URI scheme: {}", url.scheme());
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn to_file(&self, path: PathBuf) -> Result<(), AssetError> {
+        self.to_dynamic_image()?
+            .save_with_format(&path, ImageFormat::Png)
+            .map_err(|error| AssetError::Io(path, std::io::Error::other(error)))
+    }
+
+    /// Encoded as a png file in memory
+    pub fn to_png(&self) -> Result<Vec<u8>, AssetError> {
+        let mut png = std::io::Cursor::new(Vec::new());
+        self.to_dynamic_image()?
+            .write_to(&mut png, ImageFormat::Png)
+            .map_err(|error| AssetError::Decode(format!("Png encoding failed: {}", error)))?;
+        Ok(png.into_inner())
+    }
+
+    fn to_dynamic_image(&self) -> Result<DynamicImage, AssetError> {
+        let data = self.data.clone();
+        let invalid_data = || AssetError::Decode("Invalid image data".to_owned());
+        Ok(match self.gl_format {
+            gl::RGB => DynamicImage::ImageRgb8(
+                ImageBuffer::from_raw(self.width, self.height, data).ok_or_else(invalid_data)?,
+            ),
+            gl::RGBA => DynamicImage::ImageRgba8(
+                ImageBuffer::from_raw(self.width, self.height, data).ok_or_else(invalid_data)?,
+            ),
+            gl_format => {
+                return Err(AssetError::UnsupportedFormat(format!(
+                    "image gl format {}",
+                    gl_format
+                )))
+            }
+        })
+    }
+
+    /// Pixel by pixel comparison, a pixel mismatches when any channel differs by more than `tolerance`
+    /// Returns `None` when the dimensions or formats differ, or the format is neither RGB nor RGBA
+    pub fn compare(&self, reference: &Image, tolerance: u8) -> Option<ImageDiff> {
+        if self.width != reference.width
+            || self.height != reference.height
+            || self.gl_format != reference.gl_format
+        {
+            return None;
         }
+        let channels = match self.gl_format {
+            gl::RGB => 3,
+            gl::RGBA => 4,
+            _ => return None,
+        };
 
-        let path = url
-            .to_file_path()
-            .expect("Failed to convert image URI to file path.");
-        let image_result = image::open(path).expect("Failed to load image.");
+        let mut mismatched_pixels = 0;
+        let mut max_channel_delta = 0;
+        let mut data = Vec::with_capacity(self.data.len());
+        for (pixel, reference_pixel) in self
+            .data
+            .chunks_exact(channels)
+            .zip(reference.data.chunks_exact(channels))
+        {
+            let delta = pixel
+                .iter()
+                .zip(reference_pixel)
+                .map(|(channel, reference_channel)| channel.abs_diff(*reference_channel))
+                .max()
+                .unwrap_or(0);
+            max_channel_delta = max_channel_delta.max(delta);
 
-        Self::new(image_result)
+            // mismatches in red, matches as a darkened copy of the reference
+            let diff_pixel = if delta > tolerance {
+                mismatched_pixels += 1;
+                [255, 0, 0, 255]
+            } else {
+                let [r, g, b] = [reference_pixel[0], reference_pixel[1], reference_pixel[2]];
+                [r / 4, g / 4, b / 4, 255]
+            };
+            data.extend_from_slice(&diff_pixel[..channels]);
+        }
+
+        Some(ImageDiff {
+            mismatched_pixels,
+            max_channel_delta,
+            image: Image::from_raw(self.width, self.height, self.gl_format, data),
+        })
     }
 
     pub fn to_glfw_pixelimage(&self) -> glfw::PixelImage {
@@ -81,6 +185,12 @@ impl Image {
     }
 }
 
+pub struct ImageDiff {
+    pub mismatched_pixels: usize,
+    pub max_channel_delta: u8,
+    pub image: Image,
+}
+
 fn integrity_check(image: &Image) {
     let expected_size = image.width
         * image.height
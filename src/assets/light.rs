@@ -1,7 +1,12 @@
+use cgmath::EuclideanSpace;
 use serde::{Deserialize, Serialize};
 
+use super::program::Program;
 use crate::types::{Direction, Position, Rgb};
 
+/// Most point lights the phong and pbr programs take, see `NR_POINT_LIGHTS`
+pub const MAX_POINT_LIGHTS: usize = 4;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DirLight {
     pub dir: Direction,
@@ -40,3 +45,42 @@ pub struct SpotLight {
     pub diffuse: Rgb,
     pub specular: Rgb,
 }
+
+impl DirLight {
+    /// Sets the `name` struct uniform, the program must be in use
+    pub fn gl_upload(&self, program: &Program, name: &str) {
+        program.set_uniform_vec3(&format!("{}.dir", name), self.dir);
+        program.set_uniform_vec3(&format!("{}.ambient", name), self.ambient);
+        program.set_uniform_vec3(&format!("{}.diffuse", name), self.diffuse);
+        program.set_uniform_vec3(&format!("{}.specular", name), self.specular);
+    }
+}
+
+impl PointLight {
+    /// Sets the `name` struct uniform, the program must be in use
+    pub fn gl_upload(&self, program: &Program, name: &str) {
+        program.set_uniform_vec3(&format!("{}.pos", name), self.pos.to_vec());
+        program.set_uniform_float(&format!("{}.constant", name), self.constant);
+        program.set_uniform_float(&format!("{}.linear", name), self.linear);
+        program.set_uniform_float(&format!("{}.quadratic", name), self.quadratic);
+        program.set_uniform_vec3(&format!("{}.ambient", name), self.ambient);
+        program.set_uniform_vec3(&format!("{}.diffuse", name), self.diffuse);
+        program.set_uniform_vec3(&format!("{}.specular", name), self.specular);
+    }
+}
+
+impl SpotLight {
+    /// Sets the `name` struct uniform, the program must be in use
+    pub fn gl_upload(&self, program: &Program, name: &str) {
+        program.set_uniform_vec3(&format!("{}.pos", name), self.pos.to_vec());
+        program.set_uniform_vec3(&format!("{}.dir", name), self.dir);
+        program.set_uniform_float(&format!("{}.cut_off", name), self.cut_off);
+        program.set_uniform_float(&format!("{}.outer_cut_off", name), self.outer_cut_off);
+        program.set_uniform_float(&format!("{}.constant", name), self.constant);
+        program.set_uniform_float(&format!("{}.linear", name), self.linear);
+        program.set_uniform_float(&format!("{}.quadratic", name), self.quadratic);
+        program.set_uniform_vec3(&format!("{}.ambient", name), self.ambient);
+        program.set_uniform_vec3(&format!("{}.diffuse", name), self.diffuse);
+        program.set_uniform_vec3(&format!("{}.specular", name), self.specular);
+    }
+}
@@ -0,0 +1,264 @@
+use super::{
+    error::AssetError, font::FontData, handle::Handle, image::Image, model::ModelData,
+    watcher::Source, Font, Model,
+};
+use crate::cubemap::{Cubemap, CubemapData};
+use std::{
+    any::TypeId,
+    collections::{HashMap, VecDeque},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadStatus {
+    /// Decoding on a worker or waiting for its upload, `progress` goes from 0 to 1
+    Pending {
+        progress: f32,
+    },
+    Ready,
+    /// See `Assets::load_error`
+    Failed,
+}
+
+/// Decode progress shared with the worker, an f32 stored as bits
+#[derive(Default)]
+pub(crate) struct Progress(AtomicU32);
+
+impl Progress {
+    pub(crate) fn set(&self, progress: f32) {
+        self.0.store(progress.to_bits(), Ordering::Relaxed);
+    }
+
+    fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// Where a decoded asset goes once uploaded
+pub(crate) enum Target {
+    Image(Handle<Image>),
+    Model(Handle<Model>),
+    Font(Handle<Font>),
+    Cubemap(Handle<Cubemap>),
+}
+
+impl Target {
+    fn key(&self) -> Key {
+        match self {
+            Target::Image(handle) => Key::of(*handle),
+            Target::Model(handle) => Key::of(*handle),
+            Target::Font(handle) => Key::of(*handle),
+            Target::Cubemap(handle) => Key::of(*handle),
+        }
+    }
+}
+
+pub(crate) enum Decoded {
+    Image(Image),
+    /// With the files it references, listed on the loader thread for the watcher
+    Model(ModelData, Vec<PathBuf>),
+    Font(FontData),
+    Cubemap(CubemapData),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Key(TypeId, u32, u32);
+
+impl Key {
+    fn of<T: 'static>(handle: Handle<T>) -> Self {
+        Key(TypeId::of::<T>(), handle.index(), handle.generation())
+    }
+}
+
+struct Request {
+    progress: Arc<Progress>,
+    /// Set once the worker is done, the request then waits in the upload queue
+    decoded: bool,
+    uploaded: bool,
+    error: Option<AssetError>,
+}
+
+/// Decoded and waiting for the main thread to upload it
+pub(crate) struct Upload {
+    pub(crate) target: Target,
+    pub(crate) decoded: Decoded,
+    /// Watched for hot reload once uploaded
+    pub(crate) source: Option<Source>,
+}
+
+struct Done {
+    target: Target,
+    source: Option<Source>,
+    result: Result<Decoded, AssetError>,
+}
+
+type Job = Box<dyn FnOnce(&Progress) -> Result<Decoded, AssetError> + Send>;
+type Queued = (Job, Arc<Progress>, Target, Option<Source>);
+
+/// Worker pool decoding files off the main thread, uploads stay on the main thread
+pub(crate) struct Loader {
+    jobs: Option<Sender<Queued>>,
+    workers: Vec<JoinHandle<()>>,
+    done_sender: Sender<Done>,
+    done_receiver: Receiver<Done>,
+    requests: HashMap<Key, Request>,
+    uploads: VecDeque<Upload>,
+}
+
+impl Loader {
+    pub(crate) fn new() -> Self {
+        let (done_sender, done_receiver) = mpsc::channel();
+        Self {
+            jobs: None,
+            workers: Vec::new(),
+            done_sender,
+            done_receiver,
+            requests: HashMap::new(),
+            uploads: VecDeque::new(),
+        }
+    }
+
+    /// Workers are only spawned on the first request
+    pub(crate) fn queue(
+        &mut self,
+        target: Target,
+        source: Option<Source>,
+        job: impl FnOnce(&Progress) -> Result<Decoded, AssetError> + Send + 'static,
+    ) {
+        let progress = Arc::new(Progress::default());
+        self.requests.insert(
+            target.key(),
+            Request {
+                progress: progress.clone(),
+                decoded: false,
+                uploaded: false,
+                error: None,
+            },
+        );
+        let jobs = self.jobs.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel::<Queued>();
+            let receiver = Arc::new(Mutex::new(receiver));
+            let count = thread::available_parallelism()
+                .map(|count| count.get().saturating_sub(1).clamp(1, 4))
+                .unwrap_or(1);
+            for index in 0..count {
+                let receiver = receiver.clone();
+                let done_sender = self.done_sender.clone();
+                let worker = thread::Builder::new()
+                    .name(format!("revenant-loader-{}", index))
+                    .spawn(move || loop {
+                        let message = receiver.lock().map(|receiver| receiver.recv());
+                        let Ok(Ok((job, progress, target, source))) = message else {
+                            break;
+                        };
+                        // A panicking decoder fails its request instead of leaving it pending
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| job(&progress)))
+                            .unwrap_or_else(|payload| {
+                                let why = payload
+                                    .downcast_ref::<&str>()
+                                    .map(|why| why.to_string())
+                                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                                    .unwrap_or_default();
+                                Err(AssetError::Decode(format!("Loader job panicked: {}", why)))
+                            });
+                        progress.set(1.0);
+                        let done = Done {
+                            target,
+                            source,
+                            result,
+                        };
+                        if done_sender.send(done).is_err() {
+                            break;
+                        }
+                    })
+                    .expect("Failed to spawn loader thread.");
+                self.workers.push(worker);
+            }
+            sender
+        });
+        // Workers only stop once the sender is dropped
+        let job: Job = Box::new(job);
+        let _ = jobs.send((job, progress, target, source));
+    }
+
+    /// Moves finished decodes to the upload queue, failed ones are returned to release their slot
+    pub(crate) fn collect(&mut self) -> Vec<Target> {
+        let mut failed = Vec::new();
+        while let Ok(done) = self.done_receiver.try_recv() {
+            let Some(request) = self.requests.get_mut(&done.target.key()) else {
+                continue;
+            };
+            request.decoded = true;
+            match done.result {
+                Ok(decoded) => self.uploads.push_back(Upload {
+                    target: done.target,
+                    decoded,
+                    source: done.source,
+                }),
+                Err(error) => {
+                    request.error = Some(error);
+                    failed.push(done.target);
+                }
+            }
+        }
+        failed
+    }
+
+    pub(crate) fn next_upload(&mut self) -> Option<Upload> {
+        self.uploads.pop_front()
+    }
+
+    pub(crate) fn uploaded(&mut self, target: &Target) {
+        if let Some(request) = self.requests.get_mut(&target.key()) {
+            request.uploaded = true;
+        }
+    }
+
+    /// Handles that never went through the loader are ready since their creation
+    pub(crate) fn status<T: 'static>(&self, handle: Handle<T>) -> LoadStatus {
+        let Some(request) = self.requests.get(&Key::of(handle)) else {
+            return LoadStatus::Ready;
+        };
+        match (&request.error, request.decoded, request.uploaded) {
+            (Some(_), _, _) => LoadStatus::Failed,
+            (None, _, true) => LoadStatus::Ready,
+            // Keep some room for the upload
+            (None, false, _) => LoadStatus::Pending {
+                progress: request.progress.get() * 0.9,
+            },
+            (None, true, false) => LoadStatus::Pending { progress: 0.9 },
+        }
+    }
+
+    /// Forgets the request of a removed handle, its decode is dropped once done
+    pub(crate) fn cancel<T: 'static>(&mut self, handle: Handle<T>) {
+        self.requests.remove(&Key::of(handle));
+    }
+
+    pub(crate) fn error<T: 'static>(&self, handle: Handle<T>) -> Option<&AssetError> {
+        self.requests.get(&Key::of(handle))?.error.as_ref()
+    }
+
+    pub(crate) fn pending(&self) -> usize {
+        self.requests
+            .values()
+            .filter(|request| request.error.is_none() && !request.uploaded)
+            .count()
+    }
+}
+
+impl Drop for Loader {
+    fn drop(&mut self) {
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
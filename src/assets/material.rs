@@ -1,11 +1,53 @@
-use super::{program::Program, texture::Texture};
+use super::{
+    program::Program,
+    texture::{Texture, TextureData},
+};
+use cgmath::{Vector3, Vector4};
 use serde::{Deserialize, Serialize};
 
+/// How the alpha of the base color is used, as defined by glTF
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// Alpha is ignored
+    Opaque,
+    /// Fragments under `alpha_cutoff` are discarded
+    Mask,
+    /// Alpha blended, drawn after every opaque primitive
+    Blend,
+}
+
+// Texture units used by `Material::Pbr`
+const UNIT_ALBEDO: u32 = 0;
+const UNIT_METALLIC_ROUGHNESS: u32 = 1;
+const UNIT_NORMAL: u32 = 2;
+const UNIT_OCCLUSION: u32 = 3;
+const UNIT_EMISSIVE: u32 = 4;
+// Texture units used by `Material::Phong`
+const UNIT_DIFFUSE: u32 = 0;
+const UNIT_SPECULAR: u32 = 1;
+const UNIT_PHONG_EMISSIVE: u32 = 2;
+
 // TODO remove debug everywhere
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Debug)]
 pub enum Material {
+    /// glTF metallic-roughness, every texture is multiplied by its factor
     Pbr {
         albedo: Texture,
+        base_color_factor: [f32; 4],
+        /// Roughness in the green channel, metalness in the blue one
+        metallic_roughness: Texture,
+        metallic_factor: f32,
+        roughness_factor: f32,
+        normal: Option<Texture>,
+        normal_scale: f32,
+        /// Red channel only
+        occlusion: Texture,
+        occlusion_strength: f32,
+        emissive: Texture,
+        emissive_factor: [f32; 3],
+        alpha_mode: AlphaMode,
+        alpha_cutoff: f32,
+        double_sided: bool,
     },
     Phong {
         diffuse: Texture,
@@ -18,26 +60,295 @@ pub enum Material {
     },
 }
 
+/// CPU side of a `Material`, no GL involved until `upload`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum MaterialData {
+    Pbr {
+        albedo: TextureData,
+        base_color_factor: [f32; 4],
+        metallic_roughness: TextureData,
+        metallic_factor: f32,
+        roughness_factor: f32,
+        normal: Option<TextureData>,
+        normal_scale: f32,
+        occlusion: TextureData,
+        occlusion_strength: f32,
+        emissive: TextureData,
+        emissive_factor: [f32; 3],
+        alpha_mode: AlphaMode,
+        alpha_cutoff: f32,
+        double_sided: bool,
+    },
+    Phong {
+        diffuse: TextureData,
+        specular: TextureData,
+        specular_strength: f32,
+        emissive: TextureData,
+    },
+    Normal {
+        normal: TextureData,
+    },
+}
+
+impl MaterialData {
+    pub fn upload(self) -> Material {
+        match self {
+            MaterialData::Pbr {
+                albedo,
+                base_color_factor,
+                metallic_roughness,
+                metallic_factor,
+                roughness_factor,
+                normal,
+                normal_scale,
+                occlusion,
+                occlusion_strength,
+                emissive,
+                emissive_factor,
+                alpha_mode,
+                alpha_cutoff,
+                double_sided,
+            } => Material::Pbr {
+                albedo: albedo.upload(),
+                base_color_factor,
+                metallic_roughness: metallic_roughness.upload(),
+                metallic_factor,
+                roughness_factor,
+                normal: normal.map(TextureData::upload),
+                normal_scale,
+                occlusion: occlusion.upload(),
+                occlusion_strength,
+                emissive: emissive.upload(),
+                emissive_factor,
+                alpha_mode,
+                alpha_cutoff,
+                double_sided,
+            },
+            MaterialData::Phong {
+                diffuse,
+                specular,
+                specular_strength,
+                emissive,
+            } => Material::Phong {
+                diffuse: diffuse.upload(),
+                specular: specular.upload(),
+                specular_strength,
+                emissive: emissive.upload(),
+            },
+            MaterialData::Normal { normal } => Material::Normal {
+                normal: normal.upload(),
+            },
+        }
+    }
+}
+
 impl Material {
+    /// Copy of the CPU side kept since the upload
+    pub fn to_data(&self) -> MaterialData {
+        match self {
+            Material::Pbr {
+                albedo,
+                base_color_factor,
+                metallic_roughness,
+                metallic_factor,
+                roughness_factor,
+                normal,
+                normal_scale,
+                occlusion,
+                occlusion_strength,
+                emissive,
+                emissive_factor,
+                alpha_mode,
+                alpha_cutoff,
+                double_sided,
+            } => MaterialData::Pbr {
+                albedo: albedo.to_data(),
+                base_color_factor: *base_color_factor,
+                metallic_roughness: metallic_roughness.to_data(),
+                metallic_factor: *metallic_factor,
+                roughness_factor: *roughness_factor,
+                normal: normal.as_ref().map(Texture::to_data),
+                normal_scale: *normal_scale,
+                occlusion: occlusion.to_data(),
+                occlusion_strength: *occlusion_strength,
+                emissive: emissive.to_data(),
+                emissive_factor: *emissive_factor,
+                alpha_mode: *alpha_mode,
+                alpha_cutoff: *alpha_cutoff,
+                double_sided: *double_sided,
+            },
+            Material::Phong {
+                diffuse,
+                specular,
+                specular_strength,
+                emissive,
+            } => MaterialData::Phong {
+                diffuse: diffuse.to_data(),
+                specular: specular.to_data(),
+                specular_strength: *specular_strength,
+                emissive: emissive.to_data(),
+            },
+            Material::Normal { normal } => MaterialData::Normal {
+                normal: normal.to_data(),
+            },
+        }
+    }
+
+    /// Binds the textures and factors, and sets the blending and culling the material needs
     pub fn activate(&self, program: &Program) {
         match self {
-            Material::Pbr { ref albedo, .. } => {
-                // TODO bind texture unit
-                albedo.gl_bind(0);
-                program.set_uniform_int("material.albedo", 0);
+            Material::Pbr {
+                albedo,
+                base_color_factor,
+                metallic_roughness,
+                metallic_factor,
+                roughness_factor,
+                normal,
+                normal_scale,
+                occlusion,
+                occlusion_strength,
+                emissive,
+                emissive_factor,
+                alpha_mode,
+                alpha_cutoff,
+                double_sided,
+            } => {
+                albedo.gl_bind(UNIT_ALBEDO);
+                metallic_roughness.gl_bind(UNIT_METALLIC_ROUGHNESS);
+                occlusion.gl_bind(UNIT_OCCLUSION);
+                emissive.gl_bind(UNIT_EMISSIVE);
+                if let Some(normal) = normal {
+                    normal.gl_bind(UNIT_NORMAL);
+                }
+                unsafe {
+                    gl::ActiveTexture(gl::TEXTURE0);
+                }
+
+                program.set_uniform_int("material.albedo", UNIT_ALBEDO as i32);
+                program.set_uniform_vec4(
+                    "material.base_color_factor",
+                    Vector4::from(*base_color_factor),
+                );
+                program.set_uniform_int(
+                    "material.metallic_roughness",
+                    UNIT_METALLIC_ROUGHNESS as i32,
+                );
+                program.set_uniform_float("material.metallic_factor", *metallic_factor);
+                program.set_uniform_float("material.roughness_factor", *roughness_factor);
+                program.set_uniform_int("material.normal", UNIT_NORMAL as i32);
+                program.set_uniform_bool("material.has_normal", normal.is_some());
+                program.set_uniform_float("material.normal_scale", *normal_scale);
+                program.set_uniform_int("material.occlusion", UNIT_OCCLUSION as i32);
+                program.set_uniform_float("material.occlusion_strength", *occlusion_strength);
+                program.set_uniform_int("material.emissive", UNIT_EMISSIVE as i32);
+                program
+                    .set_uniform_vec3("material.emissive_factor", Vector3::from(*emissive_factor));
+                program.set_uniform_int("material.alpha_mode", *alpha_mode as i32);
+                program.set_uniform_float("material.alpha_cutoff", *alpha_cutoff);
+
+                gl_set_state(*alpha_mode == AlphaMode::Blend, *double_sided);
+            }
+            Material::Phong {
+                diffuse,
+                specular,
+                specular_strength,
+                emissive,
+            } => {
+                diffuse.gl_bind(UNIT_DIFFUSE);
+                specular.gl_bind(UNIT_SPECULAR);
+                emissive.gl_bind(UNIT_PHONG_EMISSIVE);
+                unsafe {
+                    gl::ActiveTexture(gl::TEXTURE0);
+                }
+
+                program.set_uniform_int("material.diffuse", UNIT_DIFFUSE as i32);
+                program.set_uniform_int("material.specular", UNIT_SPECULAR as i32);
+                program.set_uniform_float("material.specular_strength", *specular_strength);
+                program.set_uniform_int("material.emissive", UNIT_PHONG_EMISSIVE as i32);
+                // Nothing tells the winding of these meshes, never cull them
+                gl_set_state(false, true);
+            }
+            Material::Normal { normal } => {
+                normal.gl_bind(0);
+                unsafe {
+                    gl::ActiveTexture(gl::TEXTURE0);
+                }
+                program.set_uniform_int("material.normal", 0);
+                gl_set_state(false, true);
             }
-            _ => panic!("Phong material not implemented"),
         }
     }
 
+    pub fn is_blended(&self) -> bool {
+        matches!(
+            self,
+            Material::Pbr {
+                alpha_mode: AlphaMode::Blend,
+                ..
+            }
+        )
+    }
+
     #[cfg(feature = "pillow")]
     pub fn deactivate(&self) {
-        match self {
-            Material::Pbr { ref albedo, .. } => {
-                // TODO unbind texture unit
-                albedo.gl_unbind();
+        let textures = match self {
+            Material::Pbr {
+                albedo,
+                metallic_roughness,
+                normal,
+                occlusion,
+                emissive,
+                ..
+            } => vec![
+                (UNIT_ALBEDO, Some(albedo)),
+                (UNIT_METALLIC_ROUGHNESS, Some(metallic_roughness)),
+                (UNIT_NORMAL, normal.as_ref()),
+                (UNIT_OCCLUSION, Some(occlusion)),
+                (UNIT_EMISSIVE, Some(emissive)),
+            ],
+            Material::Phong {
+                diffuse,
+                specular,
+                emissive,
+                ..
+            } => vec![
+                (UNIT_DIFFUSE, Some(diffuse)),
+                (UNIT_SPECULAR, Some(specular)),
+                (UNIT_PHONG_EMISSIVE, Some(emissive)),
+            ],
+            Material::Normal { normal } => vec![(0, Some(normal))],
+        };
+        for (unit, texture) in textures
+            .into_iter()
+            .filter_map(|(unit, texture)| Some((unit, texture?)))
+        {
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0 + unit);
             }
-            _ => panic!("Phong material not implemented"),
+            texture.gl_unbind();
+        }
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+}
+
+fn gl_set_state(blend: bool, double_sided: bool) {
+    unsafe {
+        if blend {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            // Blended primitives must not hide the ones behind them
+            gl::DepthMask(gl::FALSE);
+        } else {
+            gl::Disable(gl::BLEND);
+            gl::DepthMask(gl::TRUE);
+        }
+        if double_sided {
+            gl::Disable(gl::CULL_FACE);
+        } else {
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(gl::BACK);
         }
     }
 }
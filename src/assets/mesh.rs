@@ -1,20 +1,71 @@
+pub mod layout;
+pub mod lod;
+pub mod normals;
+pub mod optimize;
+pub mod primitives;
+pub mod simplify;
+
+use self::{
+    layout::{Semantic, VertexLayout},
+    lod::{Lod, LodSettings},
+    optimize::OptimizeStats,
+};
 use crate::types::{Indice, Normal, Position, TexCoord};
+use cgmath::InnerSpace;
 use gl::types::{GLenum, GLsizei, GLsizeiptr, GLuint, GLvoid};
 use serde::{Deserialize, Serialize};
-use std::ffi::c_void;
+use std::{cell::RefCell, ffi::c_void};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Debug)]
 pub struct Mesh {
     pub(crate) gl_mode: GLenum,
 
     pub(crate) vertices: Vec<Vertex>,
     pub(crate) indices: Vec<Indice>,
 
+    /// Empty unless the mesh is skinned, one per vertex otherwise
+    pub(crate) skin: Vec<VertexSkin>,
+    pub(crate) attributes: VertexAttributes,
+    /// Uploaded after `indices` in the same element buffer
+    pub(crate) lods: Vec<Lod>,
+    pub(crate) morph_targets: Vec<MorphTarget>,
+    /// Weights the vertex buffer was last blended with
+    morph_weights: RefCell<Vec<f32>>,
+
     pub vao: GLuint, // FIX set private
     pub vbo: GLuint, // FIX set private
     pub ebo: GLuint, // FIX set private
+    /// Of the uploaded indices, see `MeshData::index_type`
+    index_type: GLenum,
+    skin_vbo: GLuint,
+    attributes_vbo: GLuint,
+
+    pub draw_type: MeshDrawType,
+}
 
+/// CPU side of a `Mesh`, no GL involved until `upload`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MeshData {
+    pub gl_mode: GLenum,
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<Indice>,
     pub draw_type: MeshDrawType,
+    /// Empty unless the mesh is skinned, one per vertex otherwise
+    pub skin: Vec<VertexSkin>,
+    pub morph_targets: Vec<MorphTarget>,
+    pub attributes: VertexAttributes,
+    /// Coarser and coarser versions of `indices` over the same vertices, see `lod::generate`
+    pub lods: Vec<Lod>,
+}
+
+/// Displacements added to the vertices, scaled by the weight of the target. Every list is either
+/// empty or has one entry per vertex
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MorphTarget {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// Added to the xyz of `VertexAttributes::tangents`, the handedness is kept
+    pub tangents: Vec<[f32; 3]>,
 }
 
 // IMPLEMENT
@@ -23,13 +74,14 @@ pub enum MeshDrawMode {
     Dynamic,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum MeshDrawType {
     DrawElements,
     DrawArrays,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Attributes every mesh has, anything else goes in `VertexSkin` or `VertexAttributes`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[repr(C)]
 pub struct Vertex {
     pub position: Position,
@@ -37,6 +89,186 @@ pub struct Vertex {
     pub tex_coord: TexCoord,
 }
 
+/// Kept out of `Vertex` in its own buffer, most meshes are not skinned
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct VertexSkin {
+    /// Indices into the joints of the skin
+    pub joints: [u16; 4],
+    pub weights: [f32; 4],
+}
+
+/// Optional attributes, interleaved in a third buffer. Every list is either empty or has one
+/// entry per vertex
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct VertexAttributes {
+    /// Direction of u, w is the handedness of the bitangent: cross(normal, tangent) * w points up
+    /// in the texture, towards decreasing v
+    pub tangents: Vec<[f32; 4]>,
+    /// Linear RGBA
+    pub colors: Vec<[f32; 4]>,
+    /// Sets after `Vertex::tex_coord`, up to `MAX_TEX_COORD_SETS` with it
+    pub tex_coords: Vec<Vec<[f32; 2]>>,
+}
+
+impl Vertex {
+    pub fn layout() -> VertexLayout {
+        let layout = VertexLayout::new()
+            .with(Semantic::Position, 3, gl::FLOAT, false)
+            .with(Semantic::Normal, 3, gl::FLOAT, false)
+            .with(Semantic::TexCoord(0), 2, gl::FLOAT, false);
+        debug_assert_eq!(layout.stride(), std::mem::size_of::<Vertex>());
+        layout
+    }
+}
+
+impl VertexSkin {
+    pub fn layout() -> VertexLayout {
+        let layout = VertexLayout::new()
+            .with(Semantic::Joints, 4, gl::UNSIGNED_SHORT, false)
+            .with(Semantic::Weights, 4, gl::FLOAT, false);
+        debug_assert_eq!(layout.stride(), std::mem::size_of::<VertexSkin>());
+        layout
+    }
+}
+
+impl VertexAttributes {
+    pub fn is_empty(&self) -> bool {
+        self.tangents.is_empty() && self.colors.is_empty() && self.tex_coords.is_empty()
+    }
+
+    /// Only the lists that are not empty, in the order of the fields
+    pub fn layout(&self) -> VertexLayout {
+        let mut layout = VertexLayout::new();
+        if !self.tangents.is_empty() {
+            layout = layout.with(Semantic::Tangent, 4, gl::FLOAT, false);
+        }
+        if !self.colors.is_empty() {
+            layout = layout.with(Semantic::Color, 4, gl::FLOAT, false);
+        }
+        for (set, tex_coords) in self.tex_coords.iter().enumerate() {
+            if !tex_coords.is_empty() {
+                layout = layout.with(Semantic::TexCoord(set + 1), 2, gl::FLOAT, false);
+            }
+        }
+        layout
+    }
+
+    /// Vertex buffer matching `layout`
+    pub fn interleave(&self, vertex_count: usize) -> Vec<f32> {
+        let mut data = Vec::with_capacity(vertex_count * self.layout().stride() / 4);
+        for vertex in 0..vertex_count {
+            if let Some(tangent) = self.tangents.get(vertex) {
+                data.extend(tangent);
+            }
+            if let Some(color) = self.colors.get(vertex) {
+                data.extend(color);
+            }
+            for tex_coords in &self.tex_coords {
+                if let Some(tex_coord) = tex_coords.get(vertex) {
+                    data.extend(tex_coord);
+                }
+            }
+        }
+        data
+    }
+}
+
+impl MeshData {
+    pub fn upload(self) -> Mesh {
+        let mut mesh = Mesh {
+            gl_mode: self.gl_mode,
+            vertices: self.vertices,
+            indices: self.indices,
+            skin: self.skin,
+            attributes: self.attributes,
+            lods: self.lods,
+            morph_targets: self.morph_targets,
+            morph_weights: RefCell::new(Vec::new()),
+            vao: 0,
+            vbo: 0,
+            ebo: 0,
+            index_type: gl::UNSIGNED_INT,
+            skin_vbo: 0,
+            attributes_vbo: 0,
+            draw_type: self.draw_type,
+        };
+        mesh.setup_vao_vbo_ebo();
+        Vertex::layout().gl_setup();
+        mesh.gl_setup_skin();
+        mesh.gl_setup_attributes();
+        mesh
+    }
+
+    /// `UNSIGNED_SHORT` when every vertex can be indexed with 16 bits, `UNSIGNED_INT` otherwise
+    pub fn index_type(&self) -> GLenum {
+        index_type(self.vertices.len())
+    }
+
+    /// Welds and reorders the vertices and triangles for the GPU caches, see `optimize::optimize`
+    pub fn optimize(&mut self) -> OptimizeStats {
+        optimize::optimize(self)
+    }
+
+    /// Replaces the normals, see `normals::smooth_normals`. Only triangle lists are changed, they
+    /// end up indexed and without levels of detail
+    pub fn generate_normals(&mut self, max_angle: f32) {
+        if self.gl_mode != gl::TRIANGLES {
+            return;
+        }
+        let split = normals::smooth_normals(&self.vertices, &self.indices, max_angle);
+        self.remap(&split.origins);
+        self.vertices = split.vertices;
+        self.indices = split.indices;
+        self.lods.clear();
+        self.draw_type = MeshDrawType::DrawElements;
+    }
+
+    /// Replaces the levels of detail, see `lod::generate`
+    pub fn generate_lods(&mut self, settings: &LodSettings) {
+        self.lods = lod::generate(self, settings);
+    }
+
+    /// Replaces the tangents, see `normals::tangents`. Only triangle lists are changed
+    pub fn generate_tangents(&mut self) {
+        if self.gl_mode == gl::TRIANGLES {
+            self.attributes.tangents = normals::tangents(&self.vertices, &self.indices);
+        }
+    }
+
+    /// Rebuilds every per vertex list but `vertices`, the new vertex `i` copies the old vertex
+    /// `origins[i]`
+    pub(crate) fn remap(&mut self, origins: &[u32]) {
+        fn pick<T: Clone>(list: &mut Vec<T>, origins: &[u32]) {
+            if !list.is_empty() {
+                *list = origins
+                    .iter()
+                    .map(|origin| list[*origin as usize].clone())
+                    .collect();
+            }
+        }
+        pick(&mut self.skin, origins);
+        pick(&mut self.attributes.tangents, origins);
+        pick(&mut self.attributes.colors, origins);
+        for tex_coords in &mut self.attributes.tex_coords {
+            pick(tex_coords, origins);
+        }
+        for target in &mut self.morph_targets {
+            pick(&mut target.positions, origins);
+            pick(&mut target.normals, origins);
+            pick(&mut target.tangents, origins);
+        }
+    }
+}
+
+fn index_type(vertex_count: usize) -> GLenum {
+    if vertex_count <= u16::MAX as usize + 1 {
+        gl::UNSIGNED_SHORT
+    } else {
+        gl::UNSIGNED_INT
+    }
+}
+
 // TODO default texture similar to garry's mod (white & black checkerboard)
 impl Mesh {
     pub fn new(
@@ -45,72 +277,36 @@ impl Mesh {
         indices: Vec<Indice>,
         draw_type: MeshDrawType,
     ) -> Self {
-        let mut mesh = Self {
+        MeshData {
             gl_mode,
             vertices,
             indices,
-            vao: 0,
-            vbo: 0,
-            ebo: 0,
             draw_type,
-        };
-        mesh.setup_vao_vbo_ebo();
-        mesh.gl_setup_vertex_attribs();
-        mesh
+            skin: Vec::new(),
+            morph_targets: Vec::new(),
+            attributes: VertexAttributes::default(),
+            lods: Vec::new(),
+        }
+        .upload()
     }
 
-    // TEMP needs to make vertex attrib more flexible
-    // FIX remake this whole thing ( DISGUSTANG )
     pub fn quad() -> Self {
-        let mut mesh = Self {
-            vao: 0,
-            vbo: 0,
-            ebo: 0,
-            vertices: vec![
-                Vertex {
-                    position: Position::new(1.0, 1.0, 0.0),
-                    normal: Normal::new(0.0, 0.0, 1.0),
-                    tex_coord: TexCoord::new(1.0, 1.0),
-                },
-                Vertex {
-                    position: Position::new(1.0, -1.0, 0.0),
-                    normal: Normal::new(0.0, 0.0, 1.0),
-                    tex_coord: TexCoord::new(1.0, 0.0),
-                },
-                Vertex {
-                    position: Position::new(-1.0, -1.0, 0.0),
-                    normal: Normal::new(0.0, 0.0, 1.0),
-                    tex_coord: TexCoord::new(0.0, 0.0),
-                },
-                Vertex {
-                    position: Position::new(-1.0, 1.0, 0.0),
-                    normal: Normal::new(0.0, 0.0, 1.0),
-                    tex_coord: TexCoord::new(0.0, 1.0),
-                },
-            ],
-            indices: vec![0, 1, 3, 1, 2, 3],
-            gl_mode: gl::TRIANGLES,
-            draw_type: MeshDrawType::DrawElements,
+        let vertex = |x: f32, y: f32| Vertex {
+            position: Position::new(x, y, 0.0),
+            normal: Normal::new(0.0, 0.0, 1.0),
+            tex_coord: TexCoord::new((x + 1.0) / 2.0, (y + 1.0) / 2.0),
         };
-        mesh.setup_vao_vbo_ebo();
-        let stride = std::mem::size_of::<Vertex>() as GLsizei;
-        let offset_tex_coords = std::mem::size_of::<Position>() + std::mem::size_of::<Normal>();
-        unsafe {
-            // vertex positions
-            gl::EnableVertexAttribArray(0);
-            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, std::ptr::null());
-            // vertex texture coords
-            gl::EnableVertexAttribArray(1);
-            gl::VertexAttribPointer(
-                1,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                stride,
-                offset_tex_coords as *const c_void,
-            );
-        }
-        mesh
+        Self::new(
+            gl::TRIANGLES,
+            vec![
+                vertex(1.0, 1.0),
+                vertex(1.0, -1.0),
+                vertex(-1.0, -1.0),
+                vertex(-1.0, 1.0),
+            ],
+            vec![0, 1, 3, 1, 2, 3],
+            MeshDrawType::DrawElements,
+        )
     }
 
     pub fn skybox() -> Self {
@@ -135,40 +331,36 @@ impl Mesh {
                 tex_coord: TexCoord::new(0.0, 0.0),
             });
         }
-        let mut mesh = Mesh {
-            vao: 0,
-            vbo: 0,
-            ebo: 0,
-            vertices,
-            indices: vec![],
-            gl_mode: gl::TRIANGLES,
-            draw_type: MeshDrawType::DrawArrays,
-        };
-        mesh.setup_vao_vbo_ebo();
-        let stride = std::mem::size_of::<Vertex>() as GLsizei;
-        let offset_tex_coords = std::mem::size_of::<Position>() + std::mem::size_of::<Normal>();
-        unsafe {
-            // vertex positions
-            gl::EnableVertexAttribArray(0);
-            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, std::ptr::null());
-            // vertex texture coords
-            gl::EnableVertexAttribArray(1);
-            gl::VertexAttribPointer(
-                1,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                stride,
-                offset_tex_coords as *const c_void,
-            );
-        }
-        mesh
+        Self::new(gl::TRIANGLES, vertices, vec![], MeshDrawType::DrawArrays)
     }
 
     pub fn setup_vao_vbo_ebo(&mut self) {
         let size = (self.vertices.len() * std::mem::size_of::<Vertex>()) as GLsizeiptr;
         let data = self.vertices.as_ptr();
-        let ebo_size = (self.indices.len() * std::mem::size_of::<Indice>()) as GLsizeiptr;
+        // Every level of detail after the full one, in 16 bits when the vertex count allows it
+        self.index_type = index_type(self.vertices.len());
+        let indices: Vec<Indice> = self
+            .indices
+            .iter()
+            .chain(self.lods.iter().flat_map(|lod| &lod.indices))
+            .copied()
+            .collect();
+        let short_indices: Vec<u16> = if self.index_type == gl::UNSIGNED_SHORT {
+            indices.iter().map(|index| *index as u16).collect()
+        } else {
+            Vec::new()
+        };
+        let (ebo_size, ebo_data) = if self.index_type == gl::UNSIGNED_SHORT {
+            (
+                short_indices.len() * std::mem::size_of::<u16>(),
+                short_indices.as_ptr() as *const GLvoid,
+            )
+        } else {
+            (
+                indices.len() * std::mem::size_of::<Indice>(),
+                indices.as_ptr() as *const GLvoid,
+            )
+        };
         unsafe {
             gl::GenBuffers(1, &mut self.vbo);
             gl::GenVertexArrays(1, &mut self.vao);
@@ -178,81 +370,180 @@ impl Mesh {
             gl::BindVertexArray(self.vao);
             gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
 
-            // VBO
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                size,
-                data as *const c_void,
-                gl::STATIC_DRAW,
-            );
+            // VBO, morphed meshes are blended again whenever their weights change
+            let usage = if self.morph_targets.is_empty() {
+                gl::STATIC_DRAW
+            } else {
+                gl::DYNAMIC_DRAW
+            };
+            gl::BufferData(gl::ARRAY_BUFFER, size, data as *const c_void, usage);
 
             // EBO
             gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
             gl::BufferData(
                 gl::ELEMENT_ARRAY_BUFFER,
-                ebo_size,
-                self.indices.as_ptr() as *const GLvoid,
+                ebo_size as GLsizeiptr,
+                ebo_data,
                 gl::STATIC_DRAW,
             );
         }
     }
 
-    #[inline]
-    fn gl_setup_vertex_attribs(&self) {
-        /*
-        // TODO check if current_vertex_attribs <= max_vertex_attribs before initializing each vertex attributes
-        let mut gl_max_vertex_attribs = 0;
+    /// Joints and weights, in a second buffer of the same vao
+    fn gl_setup_skin(&mut self) {
+        if self.skin.is_empty() {
+            return;
+        }
         unsafe {
-            gl::GetIntegerv(gl::MAX_VERTEX_ATTRIBS, &mut gl_max_vertex_attribs);
+            gl::BindVertexArray(self.vao);
+            gl::GenBuffers(1, &mut self.skin_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.skin_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (self.skin.len() * std::mem::size_of::<VertexSkin>()) as GLsizeiptr,
+                self.skin.as_ptr() as *const c_void,
+                gl::STATIC_DRAW,
+            );
         }
-         */
+        VertexSkin::layout().gl_setup();
+    }
 
-        let stride = std::mem::size_of::<Vertex>() as GLsizei;
-        let offset_normals = std::mem::size_of::<Position>();
-        let offset_tex_coords = std::mem::size_of::<Position>() + std::mem::size_of::<Normal>();
+    /// Tangents, colors and extra texture coordinates, in a third buffer of the same vao
+    fn gl_setup_attributes(&mut self) {
+        if self.attributes.is_empty() {
+            return;
+        }
+        let data = self.attributes.interleave(self.vertices.len());
+        // Blended again with the vertices when a target moves the tangents
+        let usage = if self.morphs_tangents() {
+            gl::DYNAMIC_DRAW
+        } else {
+            gl::STATIC_DRAW
+        };
         unsafe {
-            // FIX flexiblity of vertex attributes
-            // vertex positions
-            gl::EnableVertexAttribArray(0);
-            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, std::ptr::null());
-            // vertex normals
-            gl::EnableVertexAttribArray(1);
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                stride,
-                offset_normals as *const c_void,
+            gl::BindVertexArray(self.vao);
+            gl::GenBuffers(1, &mut self.attributes_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.attributes_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (data.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
+                data.as_ptr() as *const c_void,
+                usage,
             );
-            // vertex texture coords
-            gl::EnableVertexAttribArray(2);
-            gl::VertexAttribPointer(
-                2,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                stride,
-                offset_tex_coords as *const c_void,
+        }
+        self.attributes.layout().gl_setup();
+    }
+
+    /// Copy of the CPU side kept since the upload, morph targets not applied
+    pub fn to_data(&self) -> MeshData {
+        MeshData {
+            gl_mode: self.gl_mode,
+            vertices: self.vertices.clone(),
+            indices: self.indices.clone(),
+            draw_type: self.draw_type,
+            skin: self.skin.clone(),
+            morph_targets: self.morph_targets.clone(),
+            attributes: self.attributes.clone(),
+            lods: self.lods.clone(),
+        }
+    }
+
+    /// Blends the morph targets into the vertex buffer, only when the weights changed
+    pub fn gl_apply_morph(&self, weights: &[f32]) {
+        if self.morph_targets.is_empty() || *self.morph_weights.borrow() == weights {
+            return;
+        }
+        let vertices = blend_morph_targets(&self.vertices, &self.morph_targets, weights);
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                (vertices.len() * std::mem::size_of::<Vertex>()) as GLsizeiptr,
+                vertices.as_ptr() as *const c_void,
             );
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+        if self.morphs_tangents() {
+            let attributes = VertexAttributes {
+                tangents: blend_morph_tangents(
+                    &self.attributes.tangents,
+                    &self.morph_targets,
+                    weights,
+                ),
+                ..self.attributes.clone()
+            };
+            let data = attributes.interleave(self.vertices.len());
+            unsafe {
+                gl::BindBuffer(gl::ARRAY_BUFFER, self.attributes_vbo);
+                gl::BufferSubData(
+                    gl::ARRAY_BUFFER,
+                    0,
+                    (data.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
+                    data.as_ptr() as *const c_void,
+                );
+                gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            }
         }
+        *self.morph_weights.borrow_mut() = weights.to_vec();
+    }
+
+    fn morphs_tangents(&self) -> bool {
+        !self.attributes.tangents.is_empty()
+            && self
+                .morph_targets
+                .iter()
+                .any(|target| !target.tangents.is_empty())
+    }
+
+    pub fn is_skinned(&self) -> bool {
+        !self.skin.is_empty()
+    }
+
+    /// Levels of detail, the full one included
+    pub fn lod_count(&self) -> usize {
+        1 + self.lods.len()
     }
 
     pub fn draw(&self) {
+        self.draw_lod(0);
+    }
+
+    /// `level` 0 is the full detail, past the last level the last one is drawn
+    pub fn draw_lod(&self, level: usize) {
         self.gl_bind_vao();
 
         match self.draw_type {
             MeshDrawType::DrawArrays => unsafe {
                 gl::DrawArrays(self.gl_mode, 0, self.vertices.len() as GLsizei);
             },
-            MeshDrawType::DrawElements => unsafe {
-                gl::DrawElements(
-                    self.gl_mode,
-                    self.indices.len() as GLsizei,
-                    gl::UNSIGNED_INT,
-                    std::ptr::null(),
-                );
-            },
+            MeshDrawType::DrawElements => {
+                let level = level.min(self.lods.len());
+                let (offset, count) = match level {
+                    0 => (0, self.indices.len()),
+                    _ => (
+                        self.indices.len()
+                            + self.lods[..level - 1]
+                                .iter()
+                                .map(|lod| lod.indices.len())
+                                .sum::<usize>(),
+                        self.lods[level - 1].indices.len(),
+                    ),
+                };
+                let index_size = if self.index_type == gl::UNSIGNED_SHORT {
+                    std::mem::size_of::<u16>()
+                } else {
+                    std::mem::size_of::<Indice>()
+                };
+                unsafe {
+                    gl::DrawElements(
+                        self.gl_mode,
+                        count as GLsizei,
+                        self.index_type,
+                        (offset * index_size) as *const GLvoid,
+                    );
+                }
+            }
         }
 
         #[cfg(debug_assertions)]
@@ -287,6 +578,66 @@ impl Drop for Mesh {
             gl::DeleteVertexArrays(1, &self.vao);
             gl::DeleteBuffers(1, &self.vbo);
             gl::DeleteBuffers(1, &self.ebo);
+            gl::DeleteBuffers(1, &self.skin_vbo);
+            gl::DeleteBuffers(1, &self.attributes_vbo);
         };
     }
 }
+
+/// Base vertices plus every target scaled by its weight, missing weights count as 0
+pub fn blend_morph_targets(
+    vertices: &[Vertex],
+    targets: &[MorphTarget],
+    weights: &[f32],
+) -> Vec<Vertex> {
+    let mut blended = vertices.to_vec();
+    for (target, weight) in targets.iter().zip(weights) {
+        if *weight == 0.0 {
+            continue;
+        }
+        for (vertex, delta) in blended.iter_mut().zip(&target.positions) {
+            vertex.position += Normal::from(*delta) * *weight;
+        }
+        for (vertex, delta) in blended.iter_mut().zip(&target.normals) {
+            vertex.normal += Normal::from(*delta) * *weight;
+        }
+    }
+    // Blended normals are no longer unit length
+    if targets.iter().any(|target| !target.normals.is_empty()) {
+        for vertex in &mut blended {
+            if vertex.normal.magnitude2() > 0.0 {
+                vertex.normal = vertex.normal.normalize();
+            }
+        }
+    }
+    blended
+}
+
+/// Tangents plus every target scaled by its weight, renormalized. The handedness in w is kept
+pub fn blend_morph_tangents(
+    tangents: &[[f32; 4]],
+    targets: &[MorphTarget],
+    weights: &[f32],
+) -> Vec<[f32; 4]> {
+    let mut blended = tangents.to_vec();
+    for (target, weight) in targets.iter().zip(weights) {
+        if *weight == 0.0 {
+            continue;
+        }
+        for (tangent, delta) in blended.iter_mut().zip(&target.tangents) {
+            for axis in 0..3 {
+                tangent[axis] += delta[axis] * *weight;
+            }
+        }
+    }
+    if targets.iter().any(|target| !target.tangents.is_empty()) {
+        for tangent in &mut blended {
+            let direction = Normal::new(tangent[0], tangent[1], tangent[2]);
+            if direction.magnitude2() > 0.0 {
+                let direction = direction.normalize();
+                *tangent = [direction.x, direction.y, direction.z, tangent[3]];
+            }
+        }
+    }
+    blended
+}
@@ -0,0 +1,140 @@
+use gl::types::{GLenum, GLint, GLsizei, GLuint};
+use std::ffi::c_void;
+
+/// Texture coordinate sets a mesh can carry, the first one included
+pub const MAX_TEX_COORD_SETS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Semantic {
+    Position,
+    Normal,
+    /// Index of the set, 0 is `Vertex::tex_coord`
+    TexCoord(usize),
+    Joints,
+    Weights,
+    Tangent,
+    Color,
+}
+
+impl Semantic {
+    /// Location the shaders expect it at, the same for every mesh
+    pub fn location(&self) -> GLuint {
+        match self {
+            Semantic::Position => 0,
+            Semantic::Normal => 1,
+            Semantic::TexCoord(0) => 2,
+            Semantic::Joints => 3,
+            Semantic::Weights => 4,
+            Semantic::Tangent => 5,
+            Semantic::Color => 6,
+            Semantic::TexCoord(set) => 6 + *set as GLuint,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VertexAttribute {
+    pub semantic: Semantic,
+    /// From 1 to 4
+    pub components: GLint,
+    pub gl_type: GLenum,
+    /// Integers reach the shader as floats in [0, 1] or [-1, 1] when set, as integers otherwise
+    pub normalized: bool,
+    pub location: GLuint,
+    /// In bytes from the start of the vertex
+    pub offset: usize,
+}
+
+/// Attributes interleaved in one vertex buffer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VertexLayout {
+    attributes: Vec<VertexAttribute>,
+    stride: usize,
+}
+
+impl VertexLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an attribute right after the previous one, at the location of its semantic
+    pub fn with(
+        mut self,
+        semantic: Semantic,
+        components: GLint,
+        gl_type: GLenum,
+        normalized: bool,
+    ) -> Self {
+        self.attributes.push(VertexAttribute {
+            semantic,
+            components,
+            gl_type,
+            normalized,
+            location: semantic.location(),
+            offset: self.stride,
+        });
+        self.stride += components as usize * type_size(gl_type);
+        self
+    }
+
+    pub fn attributes(&self) -> &[VertexAttribute] {
+        &self.attributes
+    }
+
+    pub fn get(&self, semantic: Semantic) -> Option<&VertexAttribute> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.semantic == semantic)
+    }
+
+    /// Bytes of one vertex
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Points every attribute at the buffer bound to `ARRAY_BUFFER`, in the bound vao
+    pub fn gl_setup(&self) {
+        // TODO check the locations against GL_MAX_VERTEX_ATTRIBS
+        let stride = self.stride as GLsizei;
+        for attribute in &self.attributes {
+            let offset = attribute.offset as *const c_void;
+            unsafe {
+                gl::EnableVertexAttribArray(attribute.location);
+                if is_integer(attribute.gl_type) && !attribute.normalized {
+                    gl::VertexAttribIPointer(
+                        attribute.location,
+                        attribute.components,
+                        attribute.gl_type,
+                        stride,
+                        offset,
+                    );
+                } else {
+                    gl::VertexAttribPointer(
+                        attribute.location,
+                        attribute.components,
+                        attribute.gl_type,
+                        attribute.normalized as u8,
+                        stride,
+                        offset,
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub fn type_size(gl_type: GLenum) -> usize {
+    match gl_type {
+        gl::BYTE | gl::UNSIGNED_BYTE => 1,
+        gl::SHORT | gl::UNSIGNED_SHORT | gl::HALF_FLOAT => 2,
+        gl::DOUBLE => 8,
+        _ => 4,
+    }
+}
+
+fn is_integer(gl_type: GLenum) -> bool {
+    matches!(
+        gl_type,
+        gl::BYTE | gl::UNSIGNED_BYTE | gl::SHORT | gl::UNSIGNED_SHORT | gl::INT | gl::UNSIGNED_INT
+    )
+}
@@ -0,0 +1,108 @@
+// Levels of detail: simplified index lists sharing the vertices of their mesh, picked by how much
+// of the screen the model covers
+use super::{
+    optimize::{reorder_triangles, CACHE_SIZE},
+    simplify::simplify,
+    MeshData,
+};
+use crate::types::Indice;
+use cgmath::{Matrix4, Point3};
+use serde::{Deserialize, Serialize};
+
+/// One level after the full detail indices of a mesh
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Lod {
+    pub indices: Vec<Indice>,
+    /// Estimated distance to the full detail surface, in model units
+    pub error: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LodSettings {
+    /// Levels after the full detail one, fewer when a mesh cannot be simplified enough
+    pub levels: usize,
+    /// Fraction of the triangles of the previous level each level keeps
+    pub ratio: f32,
+    /// Largest error of a level, relative to the radius of the mesh
+    pub max_error: f32,
+    /// Screen sizes under which each level after the first is drawn, decreasing. See
+    /// `screen_size`
+    pub screen_sizes: Vec<f32>,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            levels: 3,
+            ratio: 0.5,
+            max_error: 0.05,
+            screen_sizes: vec![0.5, 0.25, 0.125],
+        }
+    }
+}
+
+/// Simplified from the full detail indices every time so the errors do not add up. Levels that
+/// would not drop at least a tenth of the triangles of the previous one end the chain
+pub fn generate(mesh: &MeshData, settings: &LodSettings) -> Vec<Lod> {
+    let mut lods = Vec::new();
+    if mesh.gl_mode != gl::TRIANGLES || mesh.indices.is_empty() {
+        return lods;
+    }
+    let (mut min, mut max) = ([f32::MAX; 3], [f32::MIN; 3]);
+    for vertex in &mesh.vertices {
+        let position: [f32; 3] = vertex.position.into();
+        for ((min, max), value) in min.iter_mut().zip(&mut max).zip(position) {
+            *min = min.min(value);
+            *max = max.max(value);
+        }
+    }
+    let radius = min
+        .iter()
+        .zip(max)
+        .map(|(min, max)| (max - min).powi(2))
+        .sum::<f32>()
+        .sqrt()
+        / 2.0;
+
+    let mut previous = mesh.indices.len();
+    for _ in 0..settings.levels {
+        let target = (previous as f32 * settings.ratio) as usize / 3 * 3;
+        let (indices, error) = simplify(
+            &mesh.vertices,
+            &mesh.indices,
+            target,
+            settings.max_error * radius,
+        );
+        if indices.is_empty() || indices.len() * 10 > previous * 9 {
+            break;
+        }
+        previous = indices.len();
+        lods.push(Lod {
+            indices: reorder_triangles(&indices, mesh.vertices.len(), CACHE_SIZE),
+            error,
+        });
+    }
+    lods
+}
+
+/// Fraction of the screen height covered by a sphere, `center` in view space. Infinite when the
+/// camera is inside or behind
+pub fn screen_size(center: Point3<f32>, radius: f32, projection: &Matrix4<f32>) -> f32 {
+    // Clip w, the distance along the view for perspective and 1 for orthographic projections
+    let w = projection.x.w * center.x
+        + projection.y.w * center.y
+        + projection.z.w * center.z
+        + projection.w.w;
+    if w <= radius * projection.z.w.abs() || w <= 0.0 {
+        return f32::INFINITY;
+    }
+    radius * projection.y.y / w
+}
+
+/// Level to draw at `screen_size`, one past the last threshold it is under
+pub fn select(screen_size: f32, screen_sizes: &[f32]) -> usize {
+    screen_sizes
+        .iter()
+        .take_while(|threshold| screen_size < **threshold)
+        .count()
+}
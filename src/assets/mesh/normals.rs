@@ -0,0 +1,188 @@
+// Normals and tangents computed from the triangles, for meshes imported without them. Indices are a
+// triangle list, when empty every three vertices are a triangle
+use super::Vertex;
+use cgmath::{vec3, InnerSpace, Point3, Vector3};
+use std::collections::HashMap;
+
+/// Vertices after their corners were split, each one copies the input vertex at its entry of
+/// `origins`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Split {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub origins: Vec<u32>,
+}
+
+/// Every corner faces its triangle, vertices shared by triangles facing different ways are split
+pub fn flat_normals(vertices: &[Vertex], indices: &[u32]) -> Split {
+    smooth_normals(vertices, indices, 0.0)
+}
+
+/// Average of the triangles sharing a position, weighted by their angle at it so it does not
+/// depend on how faces are split. Triangles meeting at more than `max_angle` radians keep apart,
+/// 0 is flat and PI smooths everything
+pub fn smooth_normals(vertices: &[Vertex], indices: &[u32], max_angle: f32) -> Split {
+    let triangles = triangles(vertices.len(), indices);
+    let faces: Vec<Vector3<f32>> = triangles
+        .iter()
+        .map(|triangle| {
+            let [a, b, c] = triangle.map(|index| vertices[index as usize].position);
+            let face = (b - a).cross(c - a);
+            if face.magnitude2() > 0.0 {
+                face.normalize()
+            } else {
+                face
+            }
+        })
+        .collect();
+
+    // Triangles around every position with their angle there, split vertices are shared again on
+    // the way
+    let mut around: HashMap<[u32; 3], Vec<(usize, f32)>> = HashMap::new();
+    for (face, triangle) in triangles.iter().enumerate() {
+        let corners = triangle.map(|index| vertices[index as usize].position);
+        for (corner, position) in corners.iter().enumerate() {
+            around
+                .entry(bits([position.x, position.y, position.z]))
+                .or_default()
+                .push((face, corner_angle(corners, corner)));
+        }
+    }
+
+    let min_cos = max_angle.cos();
+    let mut split = Split {
+        vertices: Vec::new(),
+        indices: Vec::with_capacity(triangles.len() * 3),
+        origins: Vec::new(),
+    };
+    let mut corners: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+    for (face, triangle) in triangles.iter().enumerate() {
+        for index in triangle {
+            let vertex = &vertices[*index as usize];
+            let normal: Vector3<f32> = if max_angle <= 0.0 {
+                faces[face]
+            } else {
+                around[&bits([vertex.position.x, vertex.position.y, vertex.position.z])]
+                    .iter()
+                    .filter(|(other, _)| {
+                        *other == face || faces[face].dot(faces[*other]) >= min_cos
+                    })
+                    .map(|(other, angle)| faces[*other] * *angle)
+                    .sum()
+            };
+            let normal = if normal.magnitude2() > 0.0 {
+                normal.normalize()
+            } else {
+                normal
+            };
+            let corner = *corners
+                .entry((*index, bits([normal.x, normal.y, normal.z])))
+                .or_insert_with(|| {
+                    split.vertices.push(Vertex {
+                        normal,
+                        ..vertex.clone()
+                    });
+                    split.origins.push(*index);
+                    split.vertices.len() as u32 - 1
+                });
+            split.indices.push(corner);
+        }
+    }
+    split
+}
+
+/// MikkTSpace style, one per vertex: the tangents of the triangles follow u, are weighted by the
+/// angle of each corner and made orthogonal to the normal. cross(normal, tangent) * w points up in
+/// the texture, towards decreasing v, as glTF expects
+pub fn tangents(vertices: &[Vertex], indices: &[u32]) -> Vec<[f32; 4]> {
+    // Vertices equal in position, normal and texture coordinates share their tangent, apart for
+    // each handedness
+    let key = |vertex: &Vertex| {
+        let (p, n, t) = (vertex.position, vertex.normal, vertex.tex_coord);
+        bits([p.x, p.y, p.z, n.x, n.y, n.z, t.x, t.y])
+    };
+    let mut sums: HashMap<([u32; 8], bool), Vector3<f32>> = HashMap::new();
+    for triangle in triangles(vertices.len(), indices) {
+        let corners = triangle.map(|index| &vertices[index as usize]);
+        let [a, b, c] = corners.map(|vertex| vertex.position);
+        let [ta, tb, tc] = corners.map(|vertex| vertex.tex_coord);
+        let (e1, e2, d1, d2) = (b - a, c - a, tb - ta, tc - ta);
+        let det = d1.x * d2.y - d2.x * d1.y;
+        if det.abs() <= f32::EPSILON * (d1.magnitude2() + d2.magnitude2()) {
+            continue;
+        }
+        let towards_u = (e1 * d2.y - e2 * d1.y) / det;
+        let towards_v = (e2 * d1.x - e1 * d2.x) / det;
+        for (corner, vertex) in corners.into_iter().enumerate() {
+            let Some(tangent) = orthogonal(towards_u, vertex.normal) else {
+                continue;
+            };
+            let angle = corner_angle([a, b, c], corner);
+            let up = vertex.normal.cross(tangent).dot(towards_v) <= 0.0;
+            *sums.entry((key(vertex), up)).or_insert(vec3(0.0, 0.0, 0.0)) += tangent * angle;
+        }
+    }
+
+    vertices
+        .iter()
+        .map(|vertex| {
+            let zero = vec3(0.0, 0.0, 0.0);
+            let up = sums.get(&(key(vertex), true)).copied().unwrap_or(zero);
+            let down = sums.get(&(key(vertex), false)).copied().unwrap_or(zero);
+            // Mirrored triangles sharing a vertex would need it split, the larger side wins
+            let (sum, w) = if up.magnitude2() >= down.magnitude2() {
+                (up, 1.0)
+            } else {
+                (down, -1.0)
+            };
+            let tangent = orthogonal(sum, vertex.normal)
+                .or_else(|| orthogonal(vec3(1.0, 0.0, 0.0), vertex.normal))
+                .or_else(|| orthogonal(vec3(0.0, 1.0, 0.0), vertex.normal))
+                .unwrap_or(vec3(1.0, 0.0, 0.0));
+            [tangent.x, tangent.y, tangent.z, w]
+        })
+        .collect()
+}
+
+fn triangles(vertex_count: usize, indices: &[u32]) -> Vec<[u32; 3]> {
+    if indices.is_empty() {
+        (0..vertex_count as u32 / 3)
+            .map(|triangle| [0, 1, 2].map(|corner| triangle * 3 + corner))
+            .collect()
+    } else {
+        indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect()
+    }
+}
+
+/// 0 when the triangle has no area there
+fn corner_angle(corners: [Point3<f32>; 3], corner: usize) -> f32 {
+    let position = corners[corner];
+    let (to_next, to_previous) = (
+        corners[(corner + 1) % 3] - position,
+        corners[(corner + 2) % 3] - position,
+    );
+    if to_next.magnitude2() == 0.0 || to_previous.magnitude2() == 0.0 {
+        0.0
+    } else {
+        to_next.angle(to_previous).0
+    }
+}
+
+/// Unit length part of `vector` orthogonal to `normal`, none if nothing is left
+fn orthogonal(vector: Vector3<f32>, normal: Vector3<f32>) -> Option<Vector3<f32>> {
+    let normal = if normal.magnitude2() > 0.0 {
+        normal.normalize()
+    } else {
+        normal
+    };
+    let vector = vector - normal * normal.dot(vector);
+    (vector.magnitude2() > 1e-12).then(|| vector.normalize())
+}
+
+/// -0.0 and 0.0 are the same key
+fn bits<const N: usize>(values: [f32; N]) -> [u32; N] {
+    values.map(|value| (value + 0.0).to_bits())
+}
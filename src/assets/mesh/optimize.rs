@@ -0,0 +1,328 @@
+// Vertex welding, triangle order for the post transform cache (Tipsify, Sander et al. 2007) and
+// vertex order for fetch locality. Only triangle lists are touched
+use super::{MeshData, MeshDrawType, Vertex};
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::{Add, AddAssign},
+};
+
+/// Entries of the FIFO post transform cache the triangles are ordered for and measured with
+pub const CACHE_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub triangles: usize,
+    pub vertices: usize,
+    /// Vertices transformed, every lookup missing the cache
+    pub misses: usize,
+}
+
+/// Meshes before and after `optimize`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OptimizeStats {
+    pub before: CacheStats,
+    pub after: CacheStats,
+}
+
+impl CacheStats {
+    /// Simulates a FIFO cache of `cache_size` entries
+    pub fn measure(indices: &[u32], vertex_count: usize, cache_size: usize) -> Self {
+        let mut cache = VecDeque::with_capacity(cache_size + 1);
+        let mut misses = 0;
+        for index in indices {
+            if !cache.contains(index) {
+                misses += 1;
+                cache.push_back(*index);
+                if cache.len() > cache_size {
+                    cache.pop_front();
+                }
+            }
+        }
+        Self {
+            triangles: indices.len() / 3,
+            vertices: vertex_count,
+            misses,
+        }
+    }
+
+    /// Average cache miss ratio, vertices transformed per triangle. 3 is no reuse at all, about
+    /// 0.5 is the best a large regular mesh can get
+    pub fn acmr(&self) -> f32 {
+        if self.triangles == 0 {
+            0.0
+        } else {
+            self.misses as f32 / self.triangles as f32
+        }
+    }
+
+    /// Average transform to vertex ratio, 1 is every vertex transformed once
+    pub fn atvr(&self) -> f32 {
+        if self.vertices == 0 {
+            0.0
+        } else {
+            self.misses as f32 / self.vertices as f32
+        }
+    }
+}
+
+impl Add for CacheStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            triangles: self.triangles + other.triangles,
+            vertices: self.vertices + other.vertices,
+            misses: self.misses + other.misses,
+        }
+    }
+}
+
+impl AddAssign for CacheStats {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Add for OptimizeStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            before: self.before + other.before,
+            after: self.after + other.after,
+        }
+    }
+}
+
+impl AddAssign for OptimizeStats {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+/// Welds, orders the triangles then the vertices. Triangle lists end up indexed, other modes are
+/// left as they are
+pub fn optimize(mesh: &mut MeshData) -> OptimizeStats {
+    let before = stats(mesh);
+    if mesh.gl_mode != gl::TRIANGLES {
+        return OptimizeStats {
+            before,
+            after: before,
+        };
+    }
+    if mesh.indices.is_empty() {
+        mesh.indices = (0..mesh.vertices.len() as u32 / 3 * 3).collect();
+        mesh.draw_type = MeshDrawType::DrawElements;
+    }
+    weld(mesh);
+    mesh.indices = reorder_triangles(&mesh.indices, mesh.vertices.len(), CACHE_SIZE);
+    reorder_vertices(mesh);
+    OptimizeStats {
+        before,
+        after: stats(mesh),
+    }
+}
+
+fn stats(mesh: &MeshData) -> CacheStats {
+    if mesh.indices.is_empty() {
+        // Nothing is shared when drawing arrays
+        CacheStats {
+            triangles: mesh.vertices.len() / 3,
+            vertices: mesh.vertices.len(),
+            misses: mesh.vertices.len() / 3 * 3,
+        }
+    } else {
+        CacheStats::measure(&mesh.indices, mesh.vertices.len(), CACHE_SIZE)
+    }
+}
+
+/// Merges vertices equal in every attribute, skin and morph targets included
+pub fn weld(mesh: &mut MeshData) {
+    let keys = vertex_keys(mesh);
+    let mut unique: HashMap<&[u32], u32> = HashMap::new();
+    let mut origins = Vec::new();
+    let remap: Vec<u32> = keys
+        .iter()
+        .enumerate()
+        .map(|(vertex, key)| {
+            *unique.entry(key).or_insert_with(|| {
+                origins.push(vertex as u32);
+                origins.len() as u32 - 1
+            })
+        })
+        .collect();
+    if origins.len() == mesh.vertices.len() {
+        return;
+    }
+    apply(mesh, &origins, &remap);
+}
+
+/// Tipsify: fans around the vertex most likely to still be cached, jumps to recently used
+/// vertices at dead ends. Linear in the triangle count
+pub fn reorder_triangles(indices: &[u32], vertex_count: usize, cache_size: usize) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+    // Triangles around every vertex
+    let mut offsets = vec![0usize; vertex_count + 1];
+    for index in &indices[..triangle_count * 3] {
+        offsets[*index as usize + 1] += 1;
+    }
+    for vertex in 0..vertex_count {
+        offsets[vertex + 1] += offsets[vertex];
+    }
+    let mut adjacency = vec![0usize; offsets[vertex_count]];
+    let mut filled = offsets.clone();
+    for triangle in 0..triangle_count {
+        for index in &indices[triangle * 3..triangle * 3 + 3] {
+            adjacency[filled[*index as usize]] = triangle;
+            filled[*index as usize] += 1;
+        }
+    }
+
+    let mut live: Vec<usize> = (0..vertex_count)
+        .map(|vertex| offsets[vertex + 1] - offsets[vertex])
+        .collect();
+    let mut cache_time = vec![0usize; vertex_count];
+    let mut emitted = vec![false; triangle_count];
+    let mut dead_ends = Vec::new();
+    let mut output = Vec::with_capacity(triangle_count * 3);
+    let mut time = cache_size + 1;
+    let mut cursor = 0;
+
+    let mut fan = (0..vertex_count).find(|vertex| live[*vertex] > 0);
+    while let Some(vertex) = fan {
+        let mut candidates = Vec::new();
+        for &triangle in &adjacency[offsets[vertex]..offsets[vertex + 1]] {
+            if emitted[triangle] {
+                continue;
+            }
+            emitted[triangle] = true;
+            for &index in &indices[triangle * 3..triangle * 3 + 3] {
+                output.push(index);
+                let index = index as usize;
+                dead_ends.push(index);
+                candidates.push(index);
+                live[index] -= 1;
+                if time - cache_time[index] > cache_size {
+                    cache_time[index] = time;
+                    time += 1;
+                }
+            }
+        }
+
+        // The oldest candidate that stays in the cache while fanning around it, any with
+        // triangles left otherwise
+        let mut best = None;
+        let mut best_priority = 0;
+        for candidate in candidates {
+            if live[candidate] == 0 {
+                continue;
+            }
+            let age = time - cache_time[candidate];
+            let priority = if age + 2 * live[candidate] <= cache_size {
+                age
+            } else {
+                0
+            };
+            if best.is_none() || priority > best_priority {
+                best = Some(candidate);
+                best_priority = priority;
+            }
+        }
+        fan = best.or_else(|| {
+            while let Some(vertex) = dead_ends.pop() {
+                if live[vertex] > 0 {
+                    return Some(vertex);
+                }
+            }
+            while cursor < vertex_count {
+                if live[cursor] > 0 {
+                    return Some(cursor);
+                }
+                cursor += 1;
+            }
+            None
+        });
+    }
+    output
+}
+
+/// Vertices in the order the triangles first use them, unused ones are dropped
+pub fn reorder_vertices(mesh: &mut MeshData) {
+    let mut remap = vec![u32::MAX; mesh.vertices.len()];
+    let mut origins = Vec::with_capacity(mesh.vertices.len());
+    for index in &mesh.indices {
+        if remap[*index as usize] == u32::MAX {
+            remap[*index as usize] = origins.len() as u32;
+            origins.push(*index);
+        }
+    }
+    apply(mesh, &origins, &remap);
+}
+
+/// The new vertex `i` copies the old vertex `origins[i]`, the old vertex `v` is now `remap[v]`
+fn apply(mesh: &mut MeshData, origins: &[u32], remap: &[u32]) {
+    mesh.remap(origins);
+    mesh.vertices = origins
+        .iter()
+        .map(|origin| mesh.vertices[*origin as usize].clone())
+        .collect();
+    let lods = mesh.lods.iter_mut().map(|lod| &mut lod.indices);
+    for index in std::iter::once(&mut mesh.indices).chain(lods).flatten() {
+        *index = remap[*index as usize];
+    }
+}
+
+/// Bits of everything a vertex holds
+fn vertex_keys(mesh: &MeshData) -> Vec<Vec<u32>> {
+    fn floats(key: &mut Vec<u32>, values: &[f32]) {
+        // -0.0 and 0.0 are the same
+        key.extend(values.iter().map(|value| (value + 0.0).to_bits()));
+    }
+    (0..mesh.vertices.len())
+        .map(|vertex| {
+            let mut key = Vec::new();
+            let Vertex {
+                position,
+                normal,
+                tex_coord,
+            } = &mesh.vertices[vertex];
+            floats(
+                &mut key,
+                &[
+                    position.x,
+                    position.y,
+                    position.z,
+                    normal.x,
+                    normal.y,
+                    normal.z,
+                    tex_coord.x,
+                    tex_coord.y,
+                ],
+            );
+            if let Some(skin) = mesh.skin.get(vertex) {
+                key.extend(skin.joints.map(u32::from));
+                floats(&mut key, &skin.weights);
+            }
+            let attributes = &mesh.attributes;
+            if let Some(tangent) = attributes.tangents.get(vertex) {
+                floats(&mut key, tangent);
+            }
+            if let Some(color) = attributes.colors.get(vertex) {
+                floats(&mut key, color);
+            }
+            for tex_coords in &attributes.tex_coords {
+                if let Some(tex_coord) = tex_coords.get(vertex) {
+                    floats(&mut key, tex_coord);
+                }
+            }
+            for target in &mesh.morph_targets {
+                for list in [&target.positions, &target.normals, &target.tangents] {
+                    if let Some(value) = list.get(vertex) {
+                        floats(&mut key, value);
+                    }
+                }
+            }
+            key
+        })
+        .collect()
+}
@@ -0,0 +1,392 @@
+// Generated meshes, centered on the origin with y up. Every one has normals, texture coordinates
+// and tangents, subdivision counts below the minimum of a shape are raised to it
+use super::{MeshData, MeshDrawType, Vertex, VertexAttributes};
+use crate::types::{Position, TexCoord};
+use cgmath::{vec3, InnerSpace, Vector3};
+use std::{collections::HashMap, f32::consts::PI};
+
+/// Flat on y, `columns` along x and `rows` along z
+pub fn plane(width: f32, depth: f32, columns: u32, rows: u32) -> MeshData {
+    let (columns, rows) = (columns.max(1), rows.max(1));
+    let mut builder = Builder::default();
+    builder.grid(columns, rows, |s, t| Sample {
+        position: vec3((s - 0.5) * width, 0.0, (t - 0.5) * depth),
+        normal: vec3(0.0, 1.0, 0.0),
+        tex_coord: [s, t],
+        tangent: vec3(1.0, 0.0, 0.0),
+        up: vec3(0.0, 0.0, -1.0),
+    });
+    builder.build()
+}
+
+/// Every face is a grid of `subdivisions` squared quads with the whole texture on it
+pub fn cube(size: f32, subdivisions: u32) -> MeshData {
+    let subdivisions = subdivisions.max(1);
+    let half = size / 2.0;
+    let mut builder = Builder::default();
+    // Normal, right and up of every face seen from the outside
+    for (normal, right, up) in [
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ] {
+        let (normal, right, up) = (normal.into(), right.into(), up.into());
+        builder.grid(subdivisions, subdivisions, |s, t| Sample {
+            position: normal * half + right * ((s - 0.5) * size) - up * ((t - 0.5) * size),
+            normal,
+            tex_coord: [s, t],
+            tangent: right,
+            up,
+        });
+    }
+    builder.build()
+}
+
+/// `segments` around y and `rings` from pole to pole
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> MeshData {
+    let rings = rings.max(2);
+    let profile: Vec<[f32; 2]> = (0..=rings)
+        .map(|ring| {
+            let angle = ring as f32 / rings as f32 * PI;
+            [angle.sin(), angle.cos()]
+        })
+        .collect();
+    let mut builder = Builder::default();
+    builder.lathe(
+        segments,
+        &profile
+            .iter()
+            .map(|[r, y]| ([r * radius, y * radius], [*r, *y]))
+            .collect::<Vec<_>>(),
+    );
+    builder.build()
+}
+
+/// Subdivided icosahedron, its triangles are all about the same size
+pub fn icosphere(radius: f32, subdivisions: u32) -> MeshData {
+    let golden = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let mut points: Vec<Vector3<f32>> = [
+        [-1.0, golden, 0.0],
+        [1.0, golden, 0.0],
+        [-1.0, -golden, 0.0],
+        [1.0, -golden, 0.0],
+        [0.0, -1.0, golden],
+        [0.0, 1.0, golden],
+        [0.0, -1.0, -golden],
+        [0.0, 1.0, -golden],
+        [golden, 0.0, -1.0],
+        [golden, 0.0, 1.0],
+        [-golden, 0.0, -1.0],
+        [-golden, 0.0, 1.0],
+    ]
+    .iter()
+    .map(|point| Vector3::from(*point).normalize())
+    .collect();
+    let mut triangles: Vec<[usize; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+    for _ in 0..subdivisions {
+        let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut midpoint = |a: usize, b: usize| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                points.push((points[a] + points[b]).normalize());
+                points.len() - 1
+            })
+        };
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    // Same mapping as the uv sphere, corners are split where the texture wraps around
+    let longitude =
+        |point: Vector3<f32>| (-point.z).atan2(point.x).rem_euclid(2.0 * PI) / (2.0 * PI);
+    let mut builder = Builder::default();
+    let mut corners: HashMap<(usize, u32), u32> = HashMap::new();
+    for triangle in triangles {
+        let mut us = triangle.map(|point| longitude(points[point]));
+        let poles = triangle.map(|point| points[point].y.abs() > 1.0 - 1e-6);
+        let max = us.iter().copied().fold(f32::MIN, f32::max);
+        if us.iter().any(|u| max - u > 0.5) {
+            for u in &mut us {
+                if *u < 0.5 {
+                    *u += 1.0;
+                }
+            }
+        }
+        // Poles take the longitude of the triangle they are part of
+        for corner in 0..3 {
+            if poles[corner] {
+                us[corner] = (us[(corner + 1) % 3] + us[(corner + 2) % 3]) / 2.0;
+            }
+        }
+        let indices = [0, 1, 2].map(|corner| {
+            let point = points[triangle[corner]];
+            let u = us[corner];
+            *corners
+                .entry((triangle[corner], u.to_bits()))
+                .or_insert_with(|| {
+                    let angle = u * 2.0 * PI;
+                    let tangent = vec3(-angle.sin(), 0.0, -angle.cos());
+                    // Towards the north pole along the meridian
+                    let up = vec3(0.0, 1.0, 0.0) - point * point.y;
+                    builder.vertex(Sample {
+                        position: point * radius,
+                        normal: point,
+                        tex_coord: [u, point.y.clamp(-1.0, 1.0).acos() / PI],
+                        tangent,
+                        up: if up.magnitude2() > 0.0 {
+                            up
+                        } else {
+                            -tangent.cross(point)
+                        },
+                    })
+                })
+        });
+        builder.triangle(indices[0], indices[1], indices[2]);
+    }
+    builder.build()
+}
+
+/// Capped, `stacks` along y
+pub fn cylinder(radius: f32, height: f32, segments: u32, stacks: u32) -> MeshData {
+    let stacks = stacks.max(1);
+    let half = height / 2.0;
+    let profile: Vec<([f32; 2], [f32; 2])> = (0..=stacks)
+        .map(|stack| {
+            let y = half - stack as f32 / stacks as f32 * height;
+            ([radius, y], [1.0, 0.0])
+        })
+        .collect();
+    let mut builder = Builder::default();
+    builder.lathe(segments, &profile);
+    builder.disc(radius, half, segments, 1, true);
+    builder.disc(radius, -half, segments, 1, false);
+    builder.build()
+}
+
+/// Apex up, capped at the base, `stacks` along y
+pub fn cone(radius: f32, height: f32, segments: u32, stacks: u32) -> MeshData {
+    let stacks = stacks.max(1);
+    let half = height / 2.0;
+    let slant = (height * height + radius * radius).sqrt();
+    let normal = [height / slant, radius / slant];
+    let profile: Vec<([f32; 2], [f32; 2])> = (0..=stacks)
+        .map(|stack| {
+            let t = stack as f32 / stacks as f32;
+            ([t * radius, half - t * height], normal)
+        })
+        .collect();
+    let mut builder = Builder::default();
+    builder.lathe(segments, &profile);
+    builder.disc(radius, -half, segments, 1, false);
+    builder.build()
+}
+
+/// `height` includes both hemispheres and is at least `2 * radius`, `rings` is per hemisphere
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> MeshData {
+    let rings = rings.max(1);
+    let half = (height / 2.0 - radius).max(0.0);
+    let mut profile = Vec::new();
+    for (center, from) in [(half, 0.0), (-half, PI / 2.0)] {
+        for ring in 0..=rings {
+            let angle = from + ring as f32 / rings as f32 * PI / 2.0;
+            let (r, y) = (angle.sin(), angle.cos());
+            profile.push(([r * radius, center + y * radius], [r, y]));
+        }
+    }
+    let mut builder = Builder::default();
+    builder.lathe(segments, &profile);
+    builder.build()
+}
+
+/// Lying on y, `segments` around the ring and `sides` around the tube
+pub fn torus(major_radius: f32, minor_radius: f32, segments: u32, sides: u32) -> MeshData {
+    let sides = sides.max(3);
+    // Starts and ends on the outer equator
+    let profile: Vec<([f32; 2], [f32; 2])> = (0..=sides)
+        .map(|side| {
+            let angle = side as f32 / sides as f32 * 2.0 * PI;
+            let (r, y) = (angle.cos(), angle.sin());
+            ([major_radius + r * minor_radius, y * minor_radius], [r, y])
+        })
+        .collect();
+    let mut builder = Builder::default();
+    builder.lathe(segments, &profile);
+    builder.build()
+}
+
+/// Facing up, `rings` from the center to the edge
+pub fn disc(radius: f32, segments: u32, rings: u32) -> MeshData {
+    let mut builder = Builder::default();
+    builder.disc(radius, 0.0, segments, rings, true);
+    builder.build()
+}
+
+struct Sample {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    tex_coord: [f32; 2],
+    /// Towards increasing u
+    tangent: Vector3<f32>,
+    /// Towards decreasing v, up in the texture, only its side of the tangent plane matters
+    up: Vector3<f32>,
+}
+
+#[derive(Default)]
+struct Builder {
+    vertices: Vec<Vertex>,
+    tangents: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl Builder {
+    fn vertex(&mut self, sample: Sample) -> u32 {
+        let normal = sample.normal.normalize();
+        // Orthogonal to the normal, w makes cross(normal, tangent) * w point up in the texture
+        let tangent = (sample.tangent - normal * normal.dot(sample.tangent)).normalize();
+        let w = if normal.cross(tangent).dot(sample.up) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        self.vertices.push(Vertex {
+            position: Position::new(sample.position.x, sample.position.y, sample.position.z),
+            normal,
+            tex_coord: TexCoord::from(sample.tex_coord),
+        });
+        self.tangents.push([tangent.x, tangent.y, tangent.z, w]);
+        self.vertices.len() as u32 - 1
+    }
+
+    /// Wound counter clockwise around the normals, triangles without area are dropped
+    fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        let [pa, pb, pc] = [a, b, c].map(|index| self.vertices[index as usize].position);
+        let (ab, ac) = (pb - pa, pc - pa);
+        let cross = ab.cross(ac);
+        if cross.magnitude2() <= 1e-10 * ab.magnitude2() * ac.magnitude2() {
+            return;
+        }
+        let normal: Vector3<f32> = [a, b, c]
+            .iter()
+            .map(|index| self.vertices[*index as usize].normal)
+            .sum();
+        if cross.dot(normal) < 0.0 {
+            self.indices.extend([a, c, b]);
+        } else {
+            self.indices.extend([a, b, c]);
+        }
+    }
+
+    /// `(columns + 1) * (rows + 1)` vertices, `sample` gets s and t from 0 to 1
+    fn grid(&mut self, columns: u32, rows: u32, sample: impl Fn(f32, f32) -> Sample) {
+        let first = self.vertices.len() as u32;
+        for row in 0..=rows {
+            for column in 0..=columns {
+                self.vertex(sample(
+                    column as f32 / columns as f32,
+                    row as f32 / rows as f32,
+                ));
+            }
+        }
+        let width = columns + 1;
+        for row in 0..rows {
+            for column in 0..columns {
+                let corner = first + row * width + column;
+                self.triangle(corner, corner + 1, corner + width + 1);
+                self.triangle(corner, corner + width + 1, corner + width);
+            }
+        }
+    }
+
+    /// Surface of revolution around y, the profile is a list of (radius, y) positions with their
+    /// (radius, y) normals. v follows the length of the profile
+    fn lathe(&mut self, segments: u32, profile: &[([f32; 2], [f32; 2])]) {
+        let segments = segments.max(3);
+        let mut lengths = vec![0.0];
+        for pair in profile.windows(2) {
+            let ([r0, y0], [r1, y1]) = (pair[0].0, pair[1].0);
+            lengths.push(lengths.last().unwrap() + ((r1 - r0).powi(2) + (y1 - y0).powi(2)).sqrt());
+        }
+        let total = *lengths.last().unwrap();
+        let last = profile.len() - 1;
+        self.grid(segments, last as u32, |s, t| {
+            let row = (t * last as f32).round() as usize;
+            let ([r, y], [normal_r, normal_y]) = profile[row];
+            // Along the profile, against increasing v
+            let ([r_before, y_before], [r_after, y_after]) = (
+                profile[row.saturating_sub(1)].0,
+                profile[(row + 1).min(last)].0,
+            );
+            let angle = s * 2.0 * PI;
+            let (cos, sin) = (angle.cos(), angle.sin());
+            let around = |r: f32, y: f32| vec3(r * cos, y, -r * sin);
+            Sample {
+                position: around(r, y),
+                normal: around(normal_r, normal_y),
+                tex_coord: [s, if total > 0.0 { lengths[row] / total } else { t }],
+                tangent: vec3(-sin, 0.0, -cos),
+                up: around(r_before - r_after, y_before - y_after),
+            }
+        });
+    }
+
+    /// Planar mapped like `plane`, seen from the side it faces
+    fn disc(&mut self, radius: f32, y: f32, segments: u32, rings: u32, facing_up: bool) {
+        let (segments, rings) = (segments.max(3), rings.max(1));
+        let side = if facing_up { 1.0 } else { -1.0 };
+        self.grid(segments, rings, |s, t| {
+            let angle = s * 2.0 * PI;
+            let (x, z) = (t * radius * angle.cos(), -t * radius * angle.sin());
+            Sample {
+                position: vec3(x, y, z),
+                normal: vec3(0.0, side, 0.0),
+                tex_coord: [0.5 + x / (2.0 * radius), 0.5 + side * z / (2.0 * radius)],
+                tangent: vec3(1.0, 0.0, 0.0),
+                up: vec3(0.0, 0.0, -side),
+            }
+        });
+    }
+
+    fn build(self) -> MeshData {
+        MeshData {
+            gl_mode: gl::TRIANGLES,
+            vertices: self.vertices,
+            indices: self.indices,
+            draw_type: MeshDrawType::DrawElements,
+            skin: Vec::new(),
+            morph_targets: Vec::new(),
+            attributes: VertexAttributes {
+                tangents: self.tangents,
+                ..VertexAttributes::default()
+            },
+            lods: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,466 @@
+// Quadric error metrics simplification (Garland and Heckbert 1997) with half edge collapses:
+// vertices only ever move onto a neighbour, so the simplified indices share the vertex buffer and
+// every attribute stays valid. Vertices sharing a position move together, borders and attribute
+// seams only collapse along themselves. Deterministic, hash maps are only used for lookups
+use super::Vertex;
+use std::collections::HashMap;
+
+/// Open edges count this much more than a face, keeps the outline of the mesh
+const BORDER_WEIGHT: f64 = 10.0;
+
+/// Collapses edges, cheapest first, until at most `target_index_count` indices are left or the
+/// next collapse would move the surface further than `max_error`. Returns the indices of the
+/// triangle list and the largest error reached, in the units of the positions
+pub fn simplify(
+    vertices: &[Vertex],
+    indices: &[u32],
+    target_index_count: usize,
+    max_error: f32,
+) -> (Vec<u32>, f32) {
+    // Vertices sharing a position form a class, classes are what collapses
+    let (class, positions) = classes(vertices);
+
+    // Triangles with two corners at the same position have no area to keep
+    let mut indices: Vec<u32> = indices
+        .chunks_exact(3)
+        .filter(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|corner| class[triangle[corner] as usize]);
+            a != b && b != c && c != a
+        })
+        .flatten()
+        .copied()
+        .collect();
+    if indices.len() <= target_index_count {
+        return (indices, 0.0);
+    }
+
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut edges = edge_counts(&indices, &class);
+    for triangle in indices.chunks_exact(3) {
+        let corners = [0, 1, 2].map(|corner| class[triangle[corner] as usize] as usize);
+        let [a, b, c] = corners.map(|corner| positions[corner]);
+        let normal = cross(sub(b, a), sub(c, a));
+        let area = length(normal) / 2.0;
+        if area <= 0.0 {
+            continue;
+        }
+        let normal = scale(normal, 1.0 / (2.0 * area));
+        let plane = Quadric::plane(normal, a, area);
+        for corner in corners {
+            quadrics[corner].add(&plane);
+        }
+        // Planes through the open edges, perpendicular to the face
+        for corner in 0..3 {
+            let (from, to) = (corners[corner], corners[(corner + 1) % 3]);
+            if edges.get(&edge(from as u32, to as u32)) != Some(&1) {
+                continue;
+            }
+            let along = sub(positions[to], positions[from]);
+            let edge_length = length(along);
+            let outwards = cross(along, normal);
+            if edge_length <= 0.0 || length(outwards) <= 0.0 {
+                continue;
+            }
+            let border = Quadric::plane(
+                scale(outwards, 1.0 / length(outwards)),
+                positions[from],
+                edge_length * edge_length * BORDER_WEIGHT,
+            );
+            quadrics[from].add(&border);
+            quadrics[to].add(&border);
+        }
+    }
+
+    let mut error: f64 = 0.0;
+    let max_cost = f64::from(max_error) * f64::from(max_error);
+    loop {
+        let kinds = kinds(&indices, &class, &edges, positions.len());
+        let around = triangles_around(&indices, &class, positions.len());
+
+        // Every allowed collapse of every edge, cheapest first
+        let mut candidates: Vec<(f64, u32, u32)> = Vec::new();
+        for triangle in indices.chunks_exact(3) {
+            for corner in 0..3 {
+                let a = class[triangle[corner] as usize];
+                let b = class[triangle[(corner + 1) % 3] as usize];
+                for (from, to) in [(a, b), (b, a)] {
+                    if allowed(
+                        kinds[from as usize],
+                        kinds[to as usize],
+                        edges[&edge(from, to)],
+                    ) {
+                        let mut quadric = quadrics[from as usize];
+                        quadric.add(&quadrics[to as usize]);
+                        candidates.push((quadric.error(positions[to as usize]), from, to));
+                    }
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+        candidates.dedup();
+
+        // Collapses in one pass never touch the same triangles, those around a collapsed class
+        // changed and wait for the next pass
+        let mut touched = vec![false; positions.len()];
+        let mut remap: Vec<u32> = (0..vertices.len() as u32).collect();
+        let mut index_count = indices.len();
+        let mut collapsed = false;
+        for (cost, from, to) in candidates {
+            if index_count <= target_index_count || cost > max_cost {
+                break;
+            }
+            if touched[from as usize] {
+                continue;
+            }
+            let Some(moves) = collapse(&indices, &class, &positions, &around, from, to) else {
+                continue;
+            };
+            for (vertex, target) in moves {
+                remap[vertex as usize] = target;
+            }
+            let removed = around[from as usize]
+                .iter()
+                .filter(|triangle| {
+                    indices[**triangle * 3..**triangle * 3 + 3]
+                        .iter()
+                        .any(|index| class[*index as usize] == to)
+                })
+                .count();
+            index_count -= removed * 3;
+            for triangle in &around[from as usize] {
+                for index in &indices[triangle * 3..triangle * 3 + 3] {
+                    touched[class[*index as usize] as usize] = true;
+                }
+            }
+            let quadric = quadrics[from as usize];
+            quadrics[to as usize].add(&quadric);
+            error = error.max(cost);
+            collapsed = true;
+        }
+        if !collapsed {
+            break;
+        }
+
+        // Triangles that lost an edge are gone
+        let mut simplified = Vec::with_capacity(index_count);
+        for triangle in indices.chunks_exact(3) {
+            let triangle = [0, 1, 2].map(|corner| remap[triangle[corner] as usize]);
+            let [a, b, c] = triangle.map(|index| class[index as usize]);
+            if a != b && b != c && c != a {
+                simplified.extend(triangle);
+            }
+        }
+        indices = simplified;
+        edges = edge_counts(&indices, &class);
+        if indices.len() <= target_index_count {
+            break;
+        }
+    }
+    (indices, error.sqrt() as f32)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Every edge around is shared by two triangles, one vertex at the position
+    Manifold,
+    /// Several vertices at the position, their attributes differ
+    Seam,
+    /// On an open edge
+    Border,
+    /// Non manifold, or both a border and a seam, never collapses
+    Locked,
+}
+
+/// Moving `from` onto `to` is allowed by their kinds, `count` triangles share the edge
+fn allowed(from: Kind, to: Kind, count: u32) -> bool {
+    match from {
+        Kind::Manifold => true,
+        Kind::Seam => matches!(to, Kind::Seam | Kind::Locked),
+        Kind::Border => count == 1 && matches!(to, Kind::Border | Kind::Locked),
+        Kind::Locked => false,
+    }
+}
+
+/// Vertex moves of the collapse, none if it would break the mesh: folded triangles, attribute
+/// seams crossed or edges shared by more than two triangles
+fn collapse(
+    indices: &[u32],
+    class: &[u32],
+    positions: &[[f64; 3]],
+    around: &[Vec<usize>],
+    from: u32,
+    to: u32,
+) -> Option<Vec<(u32, u32)>> {
+    let triangle = |index: usize| [0, 1, 2].map(|corner| indices[index * 3 + corner]);
+    let mut moves: Vec<(u32, u32)> = Vec::new();
+    let mut shared = 0;
+    let mut neighbours_from = Vec::new();
+    for &index in &around[from as usize] {
+        let corners = triangle(index);
+        let Some(vertex) = corners
+            .iter()
+            .copied()
+            .find(|index| class[*index as usize] == from)
+        else {
+            continue;
+        };
+        let target = corners
+            .iter()
+            .copied()
+            .find(|index| class[*index as usize] == to);
+        match target {
+            Some(target) => {
+                shared += 1;
+                // Every vertex of `from` has one partner at `to`, its own
+                match moves.iter().find(|(moved, _)| *moved == vertex) {
+                    Some((_, partner)) if *partner != target => return None,
+                    Some(_) => {}
+                    None if moves.iter().any(|(_, partner)| *partner == target) => return None,
+                    None => moves.push((vertex, target)),
+                }
+            }
+            None => {
+                // The triangle keeps its orientation
+                let before = corners.map(|index| positions[class[index as usize] as usize]);
+                let after = corners.map(|index| {
+                    if index == vertex {
+                        positions[to as usize]
+                    } else {
+                        positions[class[index as usize] as usize]
+                    }
+                });
+                let [a, b, c] = before;
+                let normal_before = cross(sub(b, a), sub(c, a));
+                let [a, b, c] = after;
+                let normal_after = cross(sub(b, a), sub(c, a));
+                if dot(normal_before, normal_after) <= 0.0 {
+                    return None;
+                }
+            }
+        }
+        neighbours_from.extend(
+            corners
+                .iter()
+                .map(|index| class[*index as usize])
+                .filter(|neighbour| *neighbour != from && *neighbour != to),
+        );
+    }
+    // Every vertex at `from` has to go somewhere
+    let mut vertices: Vec<u32> = around[from as usize]
+        .iter()
+        .flat_map(|index| triangle(*index))
+        .filter(|index| class[*index as usize] == from)
+        .collect();
+    vertices.sort_unstable();
+    vertices.dedup();
+    if shared == 0 || vertices.len() != moves.len() {
+        return None;
+    }
+
+    // Link condition: the only neighbours both share are across the collapsed edge, anything else
+    // would end up with an edge shared by more than two triangles
+    neighbours_from.sort_unstable();
+    neighbours_from.dedup();
+    let opposite: Vec<u32> = around[from as usize]
+        .iter()
+        .map(|index| triangle(*index))
+        .filter(|corners| corners.iter().any(|index| class[*index as usize] == to))
+        .flat_map(|corners| corners.map(|index| class[index as usize]))
+        .filter(|neighbour| *neighbour != from && *neighbour != to)
+        .collect();
+    for &index in &around[to as usize] {
+        let corners = triangle(index);
+        if corners.iter().any(|index| class[*index as usize] == from) {
+            continue;
+        }
+        for corner in corners {
+            let neighbour = class[corner as usize];
+            if neighbour != to
+                && neighbours_from.binary_search(&neighbour).is_ok()
+                && !opposite.contains(&neighbour)
+            {
+                return None;
+            }
+        }
+    }
+    Some(moves)
+}
+
+fn edge(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+/// Triangles per edge between classes
+fn edge_counts(indices: &[u32], class: &[u32]) -> HashMap<(u32, u32), u32> {
+    let mut counts = HashMap::new();
+    for triangle in indices.chunks_exact(3) {
+        for corner in 0..3 {
+            let a = class[triangle[corner] as usize];
+            let b = class[triangle[(corner + 1) % 3] as usize];
+            *counts.entry(edge(a, b)).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+fn kinds(
+    indices: &[u32],
+    class: &[u32],
+    edges: &HashMap<(u32, u32), u32>,
+    class_count: usize,
+) -> Vec<Kind> {
+    let mut border = vec![false; class_count];
+    let mut locked = vec![false; class_count];
+    let mut vertex = vec![u32::MAX; class_count];
+    let mut seam = vec![false; class_count];
+    for triangle in indices.chunks_exact(3) {
+        for corner in 0..3 {
+            let index = triangle[corner];
+            let a = class[index as usize] as usize;
+            let b = class[triangle[(corner + 1) % 3] as usize] as usize;
+            match edges[&edge(a as u32, b as u32)] {
+                1 => {
+                    border[a] = true;
+                    border[b] = true;
+                }
+                2 => {}
+                _ => {
+                    locked[a] = true;
+                    locked[b] = true;
+                }
+            }
+            if vertex[a] == u32::MAX {
+                vertex[a] = index;
+            } else if vertex[a] != index {
+                seam[a] = true;
+            }
+        }
+    }
+    (0..class_count)
+        .map(|class| match (locked[class], border[class], seam[class]) {
+            (true, _, _) | (false, true, true) => Kind::Locked,
+            (false, true, false) => Kind::Border,
+            (false, false, true) => Kind::Seam,
+            (false, false, false) => Kind::Manifold,
+        })
+        .collect()
+}
+
+fn triangles_around(indices: &[u32], class: &[u32], class_count: usize) -> Vec<Vec<usize>> {
+    let mut around = vec![Vec::new(); class_count];
+    for (triangle, corners) in indices.chunks_exact(3).enumerate() {
+        for index in corners {
+            around[class[*index as usize] as usize].push(triangle);
+        }
+    }
+    around
+}
+
+/// Sum of squared distances to planes, each weighted
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric {
+    /// xx, xy, xz, yy, yz, zz of the symmetric matrix
+    a: [f64; 6],
+    b: [f64; 3],
+    c: f64,
+    weight: f64,
+}
+
+impl Quadric {
+    fn plane(normal: [f64; 3], point: [f64; 3], weight: f64) -> Self {
+        let [x, y, z] = normal;
+        let d = -dot(normal, point);
+        Self {
+            a: [x * x, x * y, x * z, y * y, y * z, z * z].map(|value| value * weight),
+            b: [x * d, y * d, z * d].map(|value| value * weight),
+            c: d * d * weight,
+            weight,
+        }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (value, other) in self.a.iter_mut().zip(other.a) {
+            *value += other;
+        }
+        for (value, other) in self.b.iter_mut().zip(other.b) {
+            *value += other;
+        }
+        self.c += other.c;
+        self.weight += other.weight;
+    }
+
+    /// Weighted mean of the squared distances
+    fn error(&self, [x, y, z]: [f64; 3]) -> f64 {
+        if self.weight <= 0.0 {
+            return 0.0;
+        }
+        let [xx, xy, xz, yy, yz, zz] = self.a;
+        let quadratic =
+            x * x * xx + y * y * yy + z * z * zz + 2.0 * (x * y * xy + x * z * xz + y * z * yz);
+        let linear = 2.0 * (x * self.b[0] + y * self.b[1] + z * self.b[2]);
+        ((quadratic + linear + self.c) / self.weight).max(0.0)
+    }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f64; 3], factor: f64) -> [f64; 3] {
+    a.map(|value| value * factor)
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn length(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Positions closer than a millionth of the size of the mesh are one class, seams computed twice
+/// rarely land on the same bits. Returns the class of every vertex and the position of each class
+fn classes(vertices: &[Vertex]) -> (Vec<u32>, Vec<[f64; 3]>) {
+    let extent = vertices
+        .iter()
+        .flat_map(|vertex| [vertex.position.x, vertex.position.y, vertex.position.z])
+        .fold(0.0f64, |extent, value| extent.max(f64::from(value).abs()));
+    let tolerance = (extent * 1e-6).max(f64::MIN_POSITIVE);
+    // Cells as large as the tolerance, a match is in the cell of the vertex or a neighbouring one
+    let mut cells: HashMap<[i64; 3], Vec<u32>> = HashMap::new();
+    let mut positions: Vec<[f64; 3]> = Vec::new();
+    let class = vertices
+        .iter()
+        .map(|vertex| {
+            let position = [vertex.position.x, vertex.position.y, vertex.position.z].map(f64::from);
+            let cell = position.map(|value| (value / tolerance).floor() as i64);
+            // The first class within reach, whatever order the cells are visited in
+            let found = (0..27)
+                .filter_map(|offset| {
+                    let neighbour = [offset % 3, offset / 3 % 3, offset / 9].map(|step| step - 1);
+                    cells.get(&[0, 1, 2].map(|axis| cell[axis] + neighbour[axis]))
+                })
+                .flatten()
+                .copied()
+                .filter(|candidate| {
+                    length(sub(positions[*candidate as usize], position)) <= tolerance
+                })
+                .min();
+            found.unwrap_or_else(|| {
+                positions.push(position);
+                let class = positions.len() as u32 - 1;
+                cells.entry(cell).or_default().push(class);
+                class
+            })
+        })
+        .collect();
+    (class, positions)
+}
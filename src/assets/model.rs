@@ -1,173 +1,212 @@
+pub mod animation;
+pub mod export;
+pub mod node;
+pub mod obj;
+pub mod skin;
+
+use self::{
+    animation::{Animation, AnimationPlayer, Channel, Interpolation, Property, Sampler},
+    node::{Node, Scene},
+    skin::{Skin, MAX_JOINTS},
+};
 use super::{
+    camera::Camera,
+    error::AssetError,
     image::Image,
-    material::Material,
+    material::{AlphaMode, Material, MaterialData},
     // TODO remove Vertex and create a function inside mesh to load the mesh 🧠
-    mesh::{Mesh, MeshDrawType, Vertex},
+    mesh::{
+        layout::MAX_TEX_COORD_SETS,
+        lod::{self, LodSettings},
+        optimize::OptimizeStats,
+        Mesh, MeshData, MeshDrawType, MorphTarget, Vertex, VertexAttributes, VertexSkin,
+    },
     program::Program,
-    texture::Texture,
+    texture::TextureData,
+    uri,
 };
-use base64::{engine::general_purpose, Engine};
-use cgmath::{vec2, vec3};
+use cgmath::{vec2, vec3, EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Transform};
 use gltf::{
+    animation::util::ReadOutputs,
     image::Source,
     mesh::Mode,
     texture::{MagFilter, MinFilter, WrappingMode},
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf}; // TODO put in sub module?
+use std::path::{Path, PathBuf}; // TODO put in sub module?
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Debug)]
 pub struct Model {
     meshes: Vec<Mesh>,
     materials: Vec<Material>,
-    material_meshes_pairs: HashMap<usize, Vec<usize>>,
+    /// For every entry of `meshes`, its index into `materials`
+    pub primitive_materials: Vec<usize>,
+    /// For every glTF mesh, its primitives as indices into `meshes`
+    pub mesh_primitives: Vec<Vec<usize>>,
+    pub nodes: Vec<Node>,
+    pub scenes: Vec<Scene>,
+    /// Index into `scenes` of the one drawn
+    pub scene: usize,
+    pub skins: Vec<Skin>,
+    pub animations: Vec<Animation>,
+    /// See `LodSettings::screen_sizes`
+    pub lod_screen_sizes: Vec<f32>,
+    /// Level the meshes are drawn at, set by `select_lod`
+    pub lod: usize,
+    /// Model space, from the nodes at upload
+    bounding_sphere: (Point3<f32>, f32),
 }
 
-impl Model {
-    pub fn from_gltf(path: PathBuf) -> Self {
-        let (gltf, buffers, _) = gltf::import(&path).expect("Failed to import gltf file");
+/// CPU side of a `Model`, no GL involved until `upload`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModelData {
+    pub meshes: Vec<MeshData>,
+    pub materials: Vec<MaterialData>,
+    /// For every entry of `meshes`, its index into `materials`
+    pub primitive_materials: Vec<usize>,
+    pub mesh_primitives: Vec<Vec<usize>>,
+    pub nodes: Vec<Node>,
+    pub scenes: Vec<Scene>,
+    pub scene: usize,
+    pub skins: Vec<Skin>,
+    pub animations: Vec<Animation>,
+    pub lod_screen_sizes: Vec<f32>,
+}
 
-        let mut buffer_data = Vec::new();
-        for buffer in gltf.buffers() {
-            match buffer.source() {
-                gltf::buffer::Source::Bin => {
-                    buffer_data.push(buffers[buffer.index()].clone());
-                }
-                gltf::buffer::Source::Uri(uri) => {
-                    if path.extension().unwrap() != "glb" {
-                        let uri = uri.trim_start_matches("data:application/octet-stream;base64,");
-                        let data = uri.as_bytes();
-                        let bin = general_purpose::STANDARD
-                            .decode(data)
-                            .expect("Failed to decode buffer data");
-                        buffer_data.push(gltf::buffer::Data(bin));
-                    } else {
-                        let bin = std::fs::read(uri).expect("Failed to read buffer data");
-                        buffer_data.push(gltf::buffer::Data(bin));
-                    }
-                }
-            }
+impl ModelData {
+    /// Picks the importer from the extension of `path`
+    pub fn from_foreign(path: PathBuf) -> Result<Self, AssetError> {
+        match path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("glb" | "gltf") => Self::from_gltf(path),
+            Some("obj") => Self::from_obj(path),
+            extension => Err(AssetError::UnsupportedFormat(format!(
+                "model extension '{}'",
+                extension.unwrap_or_default()
+            ))),
         }
+    }
 
-        let mut materials = Vec::new();
-        let mut material_meshes_pairs = HashMap::new();
-        for gltf_material in gltf.materials() {
-            let pbr = gltf_material.pbr_metallic_roughness();
-            if let Some(gltf_texture) = &pbr.base_color_texture().map(|tex| tex.texture()) {
-                // println!(
-                //     "Loading PBR for model {} with texture {}",
-                //     path.display(),
-                //     gltf_texture.index()
-                // );
-
-                let wrap_s = match gltf_texture.sampler().wrap_s() {
-                    WrappingMode::ClampToEdge => gl::CLAMP_TO_EDGE,
-                    WrappingMode::MirroredRepeat => gl::MIRRORED_REPEAT,
-                    WrappingMode::Repeat => gl::REPEAT,
-                };
-                let wrap_t = match gltf_texture.sampler().wrap_t() {
-                    WrappingMode::ClampToEdge => gl::CLAMP_TO_EDGE,
-                    WrappingMode::MirroredRepeat => gl::MIRRORED_REPEAT,
-                    WrappingMode::Repeat => gl::REPEAT,
-                };
-                if let Some(filter_min) = gltf_texture.sampler().min_filter() {
-                    match filter_min {
-                        MinFilter::Nearest => gl::NEAREST,
-                        MinFilter::Linear => gl::LINEAR,
-                        MinFilter::NearestMipmapNearest => gl::NEAREST_MIPMAP_NEAREST,
-                        MinFilter::LinearMipmapNearest => gl::LINEAR_MIPMAP_NEAREST,
-                        MinFilter::NearestMipmapLinear => gl::NEAREST_MIPMAP_LINEAR,
-                        MinFilter::LinearMipmapLinear => gl::LINEAR_MIPMAP_LINEAR,
-                    };
-                }
-                if let Some(filter_mag) = gltf_texture.sampler().mag_filter() {
-                    match filter_mag {
-                        MagFilter::Nearest => gl::NEAREST,
-                        MagFilter::Linear => gl::LINEAR,
-                    };
-                }
-
-                let texture_source = gltf_texture.source().source();
+    /// Files besides `path` a foreign model is read from: glTF buffers and images, OBJ material
+    /// libraries and maps. Embedded data is not listed
+    pub fn dependencies(path: &Path) -> Result<Vec<PathBuf>, AssetError> {
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        if extension.is_some_and(|extension| extension.eq_ignore_ascii_case("obj")) {
+            return obj::dependencies(path);
+        }
+        let gltf = gltf::Gltf::open(path)?;
+        let folder = path.parent().unwrap_or(Path::new(""));
+        let buffers = gltf.buffers().filter_map(|buffer| match buffer.source() {
+            gltf::buffer::Source::Uri(buffer_uri) => Some(buffer_uri),
+            gltf::buffer::Source::Bin => None,
+        });
+        let images = gltf.images().filter_map(|image| match image.source() {
+            Source::Uri { uri, .. } => Some(uri),
+            Source::View { .. } => None,
+        });
+        let mut paths = Vec::new();
+        for reference in buffers.chain(images) {
+            if let uri::Resource::File(path) = uri::resolve(reference, folder)? {
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
 
-                let albedo_image = match texture_source {
-                    Source::Uri { uri, .. } => Image::from_uri(uri),
-                    Source::View { view, .. } => {
-                        let data = &buffer_data[view.buffer().index()][view.offset()..];
-                        Image::from_data(data)
-                    }
-                };
-                let mut albedo = Texture::new(albedo_image);
-                albedo.gl_s_wrapping = wrap_s;
-                albedo.gl_t_wrapping = wrap_t;
-                // TODO add min and mag filter
-                // TODO mipmaps? and all the other texture options
+    pub fn from_obj(path: PathBuf) -> Result<Self, AssetError> {
+        obj::import(&path)
+    }
 
-                materials.push(Material::Pbr { albedo });
+    pub fn from_gltf(path: PathBuf) -> Result<Self, AssetError> {
+        let gltf = gltf::Gltf::open(&path)?;
+        let folder = path.parent().unwrap_or(Path::new(""));
 
-                // TEMPORARY - ASSIGN EVERY MESH TO THE FIRST MATERIAL
-                material_meshes_pairs.insert(0, vec![0]);
+        let mut buffers = Vec::new();
+        for buffer in gltf.buffers() {
+            let data = match buffer.source() {
+                gltf::buffer::Source::Bin => gltf.blob.clone().ok_or_else(|| {
+                    AssetError::Decode("Buffer refers to a missing GLB chunk.".to_owned())
+                })?,
+                gltf::buffer::Source::Uri(buffer_uri) => uri::read(buffer_uri, folder)?,
+            };
+            if data.len() < buffer.length() {
+                return Err(AssetError::Decode(format!(
+                    "Buffer {} holds {} bytes but declares {}.",
+                    buffer.index(),
+                    data.len(),
+                    buffer.length()
+                )));
             }
+            buffers.push(gltf::buffer::Data(data));
+        }
 
-            // if let Some(normal_texture) = gltf_material.normal_texture() {
-            //     println!(
-            //         "Loading normal texture for model {} with texture {}",
-            //         path.display(),
-            //         normal_texture.texture().index()
-            //     );
-
-            //     let wrap_s = match normal_texture.texture().sampler().wrap_s() {
-            //         WrappingMode::ClampToEdge => gl::CLAMP_TO_EDGE,
-            //         WrappingMode::MirroredRepeat => gl::MIRRORED_REPEAT,
-            //         WrappingMode::Repeat => gl::REPEAT,
-            //     };
-
-            //     let wrap_t = match normal_texture.texture().sampler().wrap_t() {
-            //         WrappingMode::ClampToEdge => gl::CLAMP_TO_EDGE,
-            //         WrappingMode::MirroredRepeat => gl::MIRRORED_REPEAT,
-            //         WrappingMode::Repeat => gl::REPEAT,
-            //     };
-
-            //     if let Some(filter_min) = normal_texture.texture().sampler().min_filter() {
-            //         match filter_min {
-            //             MinFilter::Nearest => gl::NEAREST,
-            //             MinFilter::Linear => gl::LINEAR,
-            //             MinFilter::NearestMipmapNearest => gl::NEAREST_MIPMAP_NEAREST,
-            //             MinFilter::LinearMipmapNearest => gl::LINEAR_MIPMAP_NEAREST,
-            //             MinFilter::NearestMipmapLinear => gl::NEAREST_MIPMAP_LINEAR,
-            //             MinFilter::LinearMipmapLinear => gl::LINEAR_MIPMAP_LINEAR,
-            //         };
-            //     }
-
-            //     if let Some(filter_mag) = normal_texture.texture().sampler().mag_filter() {
-            //         match filter_mag {
-            //             MagFilter::Nearest => gl::NEAREST,
-            //             MagFilter::Linear => gl::LINEAR,
-            //         };
-            //     }
-
-            //     let texture_source = normal_texture.texture().source().source();
-
-            //     let normal_image = match texture_source {
-            //         Source::Uri { uri, .. } => Image::from_uri(uri),
-            //         Source::View { view, .. } => {
-            //             let data = &buffer_data[view.buffer().index()][view.offset()..];
-            //             Image::from_data(data)
-            //         }
-            //     };
-
-            //     let mut normal = Texture::new(normal_image);
-            //     normal.gl_s_wrapping = wrap_s;
-            //     normal.gl_t_wrapping = wrap_t;
-            //     // TODO add min and mag filter
-            //     // TODO mipmaps? and all the other texture options
-
-            //     materials.push(Material::Normal { normal });
-            // }
+        let mut materials = Vec::new();
+        for gltf_material in gltf.materials() {
+            let pbr = gltf_material.pbr_metallic_roughness();
+            // Missing textures are white so the factors are used as is
+            let white = || color_texture([1.0, 1.0, 1.0, 1.0]);
+            let albedo = match pbr.base_color_texture() {
+                Some(info) => texture_from_gltf(&info.texture(), &buffers, folder)?,
+                None => white(),
+            };
+            let metallic_roughness = match pbr.metallic_roughness_texture() {
+                Some(info) => texture_from_gltf(&info.texture(), &buffers, folder)?,
+                None => white(),
+            };
+            let (normal, normal_scale) = match gltf_material.normal_texture() {
+                Some(info) => (
+                    Some(texture_from_gltf(&info.texture(), &buffers, folder)?),
+                    info.scale(),
+                ),
+                None => (None, 1.0),
+            };
+            let (occlusion, occlusion_strength) = match gltf_material.occlusion_texture() {
+                Some(info) => (
+                    texture_from_gltf(&info.texture(), &buffers, folder)?,
+                    info.strength(),
+                ),
+                None => (white(), 1.0),
+            };
+            let emissive = match gltf_material.emissive_texture() {
+                Some(info) => texture_from_gltf(&info.texture(), &buffers, folder)?,
+                None => white(),
+            };
+            let alpha_mode = match gltf_material.alpha_mode() {
+                gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+                gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+                gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+            };
+            materials.push(MaterialData::Pbr {
+                albedo,
+                base_color_factor: pbr.base_color_factor(),
+                metallic_roughness,
+                metallic_factor: pbr.metallic_factor(),
+                roughness_factor: pbr.roughness_factor(),
+                normal,
+                normal_scale,
+                occlusion,
+                occlusion_strength,
+                emissive,
+                emissive_factor: gltf_material.emissive_factor(),
+                alpha_mode,
+                // The glTF default, only used by MASK
+                alpha_cutoff: gltf_material.alpha_cutoff().unwrap_or(0.5),
+                double_sided: gltf_material.double_sided(),
+            });
         }
 
         let mut meshes = Vec::new();
+        let mut primitive_materials = Vec::new();
+        let mut mesh_primitives = Vec::new();
         for mesh in gltf.meshes() {
+            let mut primitives = Vec::new();
             for primitive in mesh.primitives() {
+                primitives.push(meshes.len());
+                primitive_materials.push(primitive.material().index());
                 let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
 
                 // TODO Vertex color - Needs a new type called VertexGroup or something check on blender
@@ -181,19 +220,96 @@ impl Model {
                         })
                     });
                 }
-                if let Some(normal_attribute) = reader.read_normals() {
-                    let mut normal_index = 0;
-                    normal_attribute.for_each(|normal| {
-                        vertices[normal_index].normal = normal.into();
-                        normal_index += 1;
-                    });
+                let normal_attribute = reader.read_normals();
+                let has_normals = normal_attribute.is_some();
+                if let Some(normal_attribute) = normal_attribute {
+                    for (vertex, normal) in vertices.iter_mut().zip(normal_attribute) {
+                        vertex.normal = normal.into();
+                    }
                 }
                 if let Some(tex_coord_attribute) = reader.read_tex_coords(0).map(|v| v.into_f32()) {
-                    let mut tex_coord_index = 0;
-                    tex_coord_attribute.for_each(|tex_coord| {
-                        vertices[tex_coord_index].tex_coord = tex_coord.into();
-                        tex_coord_index += 1;
-                    });
+                    for (vertex, tex_coord) in vertices.iter_mut().zip(tex_coord_attribute) {
+                        vertex.tex_coord = tex_coord.into();
+                    }
+                }
+
+                let count = vertices.len();
+                let attributes = VertexAttributes {
+                    tangents: per_vertex(
+                        reader.read_tangents().map_or(Vec::new(), Iterator::collect),
+                        count,
+                    ),
+                    colors: per_vertex(
+                        reader
+                            .read_colors(0)
+                            .map_or(Vec::new(), |colors| colors.into_rgba_f32().collect()),
+                        count,
+                    ),
+                    tex_coords: (1..MAX_TEX_COORD_SETS as u32)
+                        .map_while(|set| reader.read_tex_coords(set))
+                        .map(|tex_coords| per_vertex(tex_coords.into_f32().collect(), count))
+                        .collect(),
+                };
+
+                let mut skin = Vec::new();
+                if let (Some(joints), Some(weights)) =
+                    (reader.read_joints(0), reader.read_weights(0))
+                {
+                    skin = joints
+                        .into_u16()
+                        .zip(weights.into_f32())
+                        .map(|(joints, weights)| VertexSkin { joints, weights })
+                        .collect();
+                    // The shaders hold `MAX_JOINTS` matrices, a larger index reads past them
+                    if let Some(joint) = skin
+                        .iter()
+                        .flat_map(|vertex| vertex.joints)
+                        .find(|joint| *joint as usize >= MAX_JOINTS)
+                    {
+                        return Err(AssetError::UnsupportedFormat(format!(
+                            "mesh {} uses joint {}, at most {} are supported",
+                            mesh.name().unwrap_or("unnamed"),
+                            joint,
+                            MAX_JOINTS
+                        )));
+                    }
+                    // Every vertex needs its joints or none does
+                    skin.resize(
+                        vertices.len(),
+                        VertexSkin {
+                            joints: [0; 4],
+                            weights: [0.0; 4],
+                        },
+                    );
+                }
+
+                let morph_targets: Vec<MorphTarget> = reader
+                    .read_morph_targets()
+                    .map(|(positions, normals, tangents)| MorphTarget {
+                        positions: positions.map(Iterator::collect).unwrap_or_default(),
+                        normals: normals.map(Iterator::collect).unwrap_or_default(),
+                        tangents: tangents.map(Iterator::collect).unwrap_or_default(),
+                    })
+                    .collect();
+                // Blending and welding index the targets by vertex
+                for (index, target) in morph_targets.iter().enumerate() {
+                    let lengths = [
+                        target.positions.len(),
+                        target.normals.len(),
+                        target.tangents.len(),
+                    ];
+                    if let Some(length) = lengths
+                        .into_iter()
+                        .find(|length| *length != 0 && *length != vertices.len())
+                    {
+                        return Err(AssetError::Decode(format!(
+                            "Morph target {} of mesh {} has {} entries for {} vertices.",
+                            index,
+                            mesh.name().unwrap_or("unnamed"),
+                            length,
+                            vertices.len()
+                        )));
+                    }
                 }
 
                 let gl_mode = match primitive.mode() {
@@ -211,39 +327,496 @@ impl Model {
                     indices.append(&mut indices_raw.into_u32().collect::<Vec<u32>>());
                 }
 
-                meshes.push(Mesh::new(
+                // Without indices the vertices are drawn in order
+                let draw_type = if indices.is_empty() {
+                    MeshDrawType::DrawArrays
+                } else {
+                    MeshDrawType::DrawElements
+                };
+                let mut mesh = MeshData {
                     gl_mode,
                     vertices,
                     indices,
-                    MeshDrawType::DrawElements,
-                ));
+                    draw_type,
+                    skin,
+                    morph_targets,
+                    attributes,
+                    lods: Vec::new(),
+                };
+                // Flat, as the specification asks
+                if !has_normals {
+                    mesh.generate_normals(0.0);
+                }
+                meshes.push(mesh);
+            }
+            mesh_primitives.push(primitives);
+        }
+
+        let mut nodes: Vec<Node> = gltf
+            .nodes()
+            .map(|node| Node::from_gltf(&node, None))
+            .collect();
+        for index in 0..nodes.len() {
+            for child in nodes[index].children.clone() {
+                nodes[child].parent = Some(index);
             }
         }
+        node::update_world_transforms(&mut nodes);
 
-        Self {
+        let mut scenes: Vec<Scene> = gltf
+            .scenes()
+            .map(|scene| Scene {
+                name: scene.name().map(str::to_owned),
+                roots: scene.nodes().map(|node| node.index()).collect(),
+            })
+            .collect();
+        // Without scenes every root node is part of the default one
+        if scenes.is_empty() {
+            scenes.push(Scene {
+                name: None,
+                roots: (0..nodes.len())
+                    .filter(|index| nodes[*index].parent.is_none())
+                    .collect(),
+            });
+        }
+        let scene = gltf.default_scene().map_or(0, |scene| scene.index());
+
+        let skins = gltf
+            .skins()
+            .map(|skin| {
+                let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
+                let inverse_bind_matrices = match reader.read_inverse_bind_matrices() {
+                    Some(matrices) => matrices.map(Matrix4::from).collect(),
+                    None => vec![Matrix4::identity(); skin.joints().count()],
+                };
+                Skin {
+                    name: skin.name().map(str::to_owned),
+                    joints: skin.joints().map(|joint| joint.index()).collect(),
+                    inverse_bind_matrices,
+                }
+            })
+            .collect();
+
+        let mut animations = Vec::new();
+        for animation in gltf.animations() {
+            let mut channels = Vec::new();
+            for channel in animation.channels() {
+                let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+                let (Some(inputs), Some(outputs)) = (reader.read_inputs(), reader.read_outputs())
+                else {
+                    continue;
+                };
+                let (property, values): (Property, Vec<[f32; 4]>) = match outputs {
+                    ReadOutputs::Translations(translations) => (
+                        Property::Translation,
+                        translations.map(|[x, y, z]| [x, y, z, 0.0]).collect(),
+                    ),
+                    ReadOutputs::Rotations(rotations) => {
+                        (Property::Rotation, rotations.into_f32().collect())
+                    }
+                    ReadOutputs::Scales(scales) => (
+                        Property::Scale,
+                        scales.map(|[x, y, z]| [x, y, z, 0.0]).collect(),
+                    ),
+                    ReadOutputs::MorphTargetWeights(weights) => (
+                        Property::Weights,
+                        weights
+                            .into_f32()
+                            .map(|weight| [weight, 0.0, 0.0, 0.0])
+                            .collect(),
+                    ),
+                };
+                let interpolation = match channel.sampler().interpolation() {
+                    gltf::animation::Interpolation::Linear => Interpolation::Linear,
+                    gltf::animation::Interpolation::Step => Interpolation::Step,
+                    gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+                };
+                let times: Vec<f32> = inputs.collect();
+                let per_key = match interpolation {
+                    Interpolation::CubicSpline => 3,
+                    _ => 1,
+                };
+                let keyframes = times.len() * per_key;
+                let valid = match property {
+                    // One value per morph target in every keyframe
+                    Property::Weights => !values.is_empty() && values.len() % keyframes == 0,
+                    _ => values.len() == keyframes,
+                };
+                if times.is_empty() || !valid {
+                    return Err(AssetError::Decode(format!(
+                        "Animation channel with {} keyframes but {} values.",
+                        times.len(),
+                        values.len()
+                    )));
+                }
+                channels.push(Channel {
+                    node: channel.target().node().index(),
+                    property,
+                    sampler: Sampler {
+                        interpolation,
+                        times,
+                        values,
+                    },
+                });
+            }
+            let duration = channels
+                .iter()
+                .filter_map(|channel| channel.sampler.times.last().copied())
+                .fold(0.0, f32::max);
+            animations.push(Animation {
+                name: animation.name().map(str::to_owned),
+                channels,
+                duration,
+            });
+        }
+
+        // Primitives without a material share the glTF default one, plain white
+        let default_material = materials.len();
+        let primitive_materials: Vec<usize> = primitive_materials
+            .into_iter()
+            .map(|material| material.unwrap_or(default_material))
+            .collect();
+        if primitive_materials.contains(&default_material) {
+            materials.push(MaterialData::Pbr {
+                albedo: color_texture([1.0, 1.0, 1.0, 1.0]),
+                base_color_factor: [1.0, 1.0, 1.0, 1.0],
+                metallic_roughness: color_texture([1.0, 1.0, 1.0, 1.0]),
+                metallic_factor: 1.0,
+                roughness_factor: 1.0,
+                normal: None,
+                normal_scale: 1.0,
+                occlusion: color_texture([1.0, 1.0, 1.0, 1.0]),
+                occlusion_strength: 1.0,
+                emissive: color_texture([1.0, 1.0, 1.0, 1.0]),
+                emissive_factor: [0.0, 0.0, 0.0],
+                alpha_mode: AlphaMode::Opaque,
+                alpha_cutoff: 0.5,
+                double_sided: false,
+            });
+        }
+
+        let mut model = Self {
             meshes,
             materials,
-            material_meshes_pairs,
+            primitive_materials,
+            mesh_primitives,
+            nodes,
+            scenes,
+            scene,
+            skins,
+            animations,
+            lod_screen_sizes: Vec::new(),
+        };
+        model.generate_missing_tangents();
+        Ok(model)
+    }
+
+    /// Every mesh, see `optimize::optimize`. The stats are summed over the meshes
+    pub fn optimize(&mut self) -> OptimizeStats {
+        self.meshes
+            .iter_mut()
+            .map(MeshData::optimize)
+            .fold(OptimizeStats::default(), |total, stats| total + stats)
+    }
+
+    /// Every mesh, see `lod::generate`. Run after `optimize`, which would undo the triangle order of
+    /// the levels
+    pub fn generate_lods(&mut self, settings: &LodSettings) {
+        for mesh in &mut self.meshes {
+            mesh.generate_lods(settings);
+        }
+        self.lod_screen_sizes = settings.screen_sizes.clone();
+    }
+
+    /// Center and radius of a sphere around every mesh instance, with the current world transforms
+    /// of the nodes. Skinned meshes are taken in their bind pose
+    pub fn bounding_sphere(&self) -> (Point3<f32>, f32) {
+        let (mut min, mut max) = (
+            Point3::new(f32::MAX, f32::MAX, f32::MAX),
+            Point3::new(f32::MIN, f32::MIN, f32::MIN),
+        );
+        for node in &self.nodes {
+            let Some(primitives) = node.mesh.and_then(|mesh| self.mesh_primitives.get(mesh)) else {
+                continue;
+            };
+            for mesh in primitives
+                .iter()
+                .filter_map(|primitive| self.meshes.get(*primitive))
+            {
+                for vertex in &mesh.vertices {
+                    let position = node.world.transform_point(vertex.position);
+                    for axis in 0..3 {
+                        min[axis] = min[axis].min(position[axis]);
+                        max[axis] = max[axis].max(position[axis]);
+                    }
+                }
+            }
+        }
+        if min.x > max.x {
+            return (Point3::new(0.0, 0.0, 0.0), 0.0);
+        }
+        (min.midpoint(max), (max - min).magnitude() / 2.0)
+    }
+
+    /// Normal mapped primitives get tangents when the file has none
+    fn generate_missing_tangents(&mut self) {
+        for (mesh, material) in self.meshes.iter_mut().zip(&self.primitive_materials) {
+            let normal_mapped = matches!(
+                self.materials.get(*material),
+                Some(MaterialData::Pbr {
+                    normal: Some(_),
+                    ..
+                })
+            );
+            if normal_mapped && mesh.attributes.tangents.is_empty() {
+                mesh.generate_tangents();
+            }
         }
     }
 
-    pub fn draw(&self, program: &Program) {
+    pub fn upload(self) -> Model {
+        let bounding_sphere = self.bounding_sphere();
+        Model {
+            meshes: self.meshes.into_iter().map(MeshData::upload).collect(),
+            materials: self
+                .materials
+                .into_iter()
+                .map(MaterialData::upload)
+                .collect(),
+            primitive_materials: self.primitive_materials,
+            mesh_primitives: self.mesh_primitives,
+            nodes: self.nodes,
+            scenes: self.scenes,
+            scene: self.scene,
+            skins: self.skins,
+            animations: self.animations,
+            lod_screen_sizes: self.lod_screen_sizes,
+            lod: 0,
+            bounding_sphere,
+        }
+    }
+
+    /// Writes a .gltf with embedded buffers or a .glb, depending on the extension
+    pub fn export(&self, path: &Path) -> Result<(), AssetError> {
+        export::export(self, path)
+    }
+}
+
+// Optimizing and generating the levels of detail takes seconds on large meshes, revenant-cook does
+// it once and native models load with them
+impl Model {
+    pub fn from_gltf(path: PathBuf) -> Result<Self, AssetError> {
+        Ok(ModelData::from_gltf(path)?.upload())
+    }
+
+    pub fn from_foreign(path: PathBuf) -> Result<Self, AssetError> {
+        Ok(ModelData::from_foreign(path)?.upload())
+    }
+
+    /// Copy of the CPU side, nodes keep their current pose
+    pub fn to_data(&self) -> ModelData {
+        ModelData {
+            meshes: self.meshes.iter().map(Mesh::to_data).collect(),
+            materials: self.materials.iter().map(Material::to_data).collect(),
+            primitive_materials: self.primitive_materials.clone(),
+            mesh_primitives: self.mesh_primitives.clone(),
+            nodes: self.nodes.clone(),
+            scenes: self.scenes.clone(),
+            scene: self.scene,
+            skins: self.skins.clone(),
+            animations: self.animations.clone(),
+            lod_screen_sizes: self.lod_screen_sizes.clone(),
+        }
+    }
+
+    pub fn export(&self, path: &Path) -> Result<(), AssetError> {
+        self.to_data().export(path)
+    }
+
+    /// Call after changing node transforms
+    pub fn update_world_transforms(&mut self) {
+        node::update_world_transforms(&mut self.nodes);
+    }
+
+    /// Poses the nodes with the current time of the player
+    pub fn animate(&mut self, player: &AnimationPlayer) {
+        player.apply(&self.animations, &mut self.nodes);
+    }
+
+    /// Picks the level of detail from how large the model looks through `camera`, `model` being
+    /// the matrix it is drawn with
+    pub fn select_lod(&mut self, camera: &Camera, model: &Matrix4<f32>) -> usize {
+        let (center, radius) = self.bounding_sphere;
+        let center = (camera.view * model).transform_point(center);
+        // The largest axis scale keeps the sphere around the model
+        let scale = [model.x, model.y, model.z]
+            .iter()
+            .map(|axis| axis.truncate().magnitude())
+            .fold(0.0, f32::max);
+        let size = lod::screen_size(center, radius * scale, &camera.projection);
+        self.lod = lod::select(size, &self.lod_screen_sizes);
+        self.lod
+    }
+
+    /// Nodes of the drawn scene, parents first
+    pub fn scene_nodes(&self) -> Vec<usize> {
+        let mut scene_nodes = Vec::new();
+        let mut stack = match self.scenes.get(self.scene) {
+            Some(scene) => scene.roots.clone(),
+            None => return scene_nodes,
+        };
+        stack.reverse();
+        let mut visited = vec![false; self.nodes.len()];
+        while let Some(index) = stack.pop() {
+            if std::mem::replace(&mut visited[index], true) {
+                continue;
+            }
+            scene_nodes.push(index);
+            stack.extend(self.nodes[index].children.iter().rev());
+        }
+        scene_nodes
+    }
+
+    /// Every mesh instance is drawn with `model * node world` as the `model` uniform, skinned ones
+    /// with `model` only and their joint matrices in `joints`
+    pub fn draw(&self, program: &Program, model: &Matrix4<f32>) {
         // TODO draw default objects with a hardcoded material program
-        for (mat_index, mesh_indexes) in &self.material_meshes_pairs {
-            let material = &self.materials[*mat_index as usize];
-            material.activate(&program);
-            for mesh_index in mesh_indexes {
-                let mesh = &self.meshes[*mesh_index as usize];
-                match mesh.gl_mode {
-                    gl::TRIANGLES => mesh.draw(),
-                    // OPTIMIZE quads should be deprecated in favor of gl::TRIANGLES
-                    gl::QUADS => panic!("QUADS are deprecated no longer supported!"),
-                    _ => panic!("Unsupported gl_mode yet!"),
+        // OPTIMIZE cache the instances of the scene instead of walking it every draw
+        let mut instances = Vec::new();
+        for node in self
+            .scene_nodes()
+            .into_iter()
+            .map(|index| &self.nodes[index])
+        {
+            let Some(primitives) = node.mesh.and_then(|mesh| self.mesh_primitives.get(mesh)) else {
+                continue;
+            };
+            for primitive in primitives {
+                instances.push((self.primitive_materials[*primitive], *primitive, node));
+            }
+        }
+        // OPTIMIZE only once per frame, and in a uniform buffer
+        let joint_matrices: Vec<Vec<Matrix4<f32>>> = self
+            .skins
+            .iter()
+            .map(|skin| skin.joint_matrices(&self.nodes))
+            .collect();
+        // Blended primitives go last so the opaque ones are behind them, then switch materials
+        // as little as possible
+        // TODO sort blended primitives back to front
+        instances
+            .sort_by_key(|(material, _, _)| (self.materials[*material].is_blended(), *material));
+
+        let mut active_material = None;
+        for (material_index, primitive, node) in instances {
+            let material = &self.materials[material_index];
+            if active_material != Some(material_index) {
+                #[cfg(feature = "pillow")]
+                if let Some(previous) = active_material {
+                    self.materials[previous].deactivate();
                 }
+                material.activate(program);
+                active_material = Some(material_index);
             }
 
-            #[cfg(feature = "pillow")]
-            material.deactivate();
+            let mesh = &self.meshes[primitive];
+            mesh.gl_apply_morph(&node.weights);
+            match node.skin.and_then(|skin| joint_matrices.get(skin)) {
+                Some(joints) if mesh.is_skinned() => {
+                    program.set_uniform_bool("skinned", true);
+                    // Import rejects vertices past `MAX_JOINTS`, the matrices after it are unused
+                    program
+                        .set_uniform_mat4_array("joints", &joints[..joints.len().min(MAX_JOINTS)]);
+                    program.set_uniform_mat4("model", model);
+                }
+                _ => {
+                    program.set_uniform_bool("skinned", false);
+                    program.set_uniform_mat4("model", &(model * node.world));
+                }
+            }
+            // Points, lines and strips draw with their own mode, only triangle lists have levels
+            mesh.draw_lod(self.lod);
+        }
+
+        #[cfg(feature = "pillow")]
+        if let Some(previous) = active_material {
+            self.materials[previous].deactivate();
+        }
+        // Back to the state set by `gl_init`
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::CULL_FACE);
         }
     }
 }
+
+fn texture_from_gltf(
+    texture: &gltf::Texture,
+    buffers: &[gltf::buffer::Data],
+    folder: &Path,
+) -> Result<TextureData, AssetError> {
+    let sampler = texture.sampler();
+    let wrapping = |mode| match mode {
+        WrappingMode::ClampToEdge => gl::CLAMP_TO_EDGE,
+        WrappingMode::MirroredRepeat => gl::MIRRORED_REPEAT,
+        WrappingMode::Repeat => gl::REPEAT,
+    };
+
+    let image = match texture.source().source() {
+        Source::Uri { uri, .. } => Image::from_uri(uri, folder)?,
+        Source::View { view, .. } => {
+            let data = buffers
+                .get(view.buffer().index())
+                .and_then(|buffer| buffer.get(view.offset()..view.offset() + view.length()))
+                .ok_or_else(|| {
+                    AssetError::Decode(format!("Image buffer view {} out of bounds.", view.index()))
+                })?;
+            Image::from_data(data)?
+        }
+    };
+
+    let mut texture_data = TextureData::new(image);
+    texture_data.gl_s_wrapping = wrapping(sampler.wrap_s());
+    texture_data.gl_t_wrapping = wrapping(sampler.wrap_t());
+    if let Some(filter_min) = sampler.min_filter() {
+        texture_data.gl_min_filtering = match filter_min {
+            MinFilter::Nearest => gl::NEAREST,
+            MinFilter::Linear => gl::LINEAR,
+            MinFilter::NearestMipmapNearest => gl::NEAREST_MIPMAP_NEAREST,
+            MinFilter::LinearMipmapNearest => gl::LINEAR_MIPMAP_NEAREST,
+            MinFilter::NearestMipmapLinear => gl::NEAREST_MIPMAP_LINEAR,
+            MinFilter::LinearMipmapLinear => gl::LINEAR_MIPMAP_LINEAR,
+        };
+    }
+    if let Some(filter_mag) = sampler.mag_filter() {
+        texture_data.gl_mag_filtering = match filter_mag {
+            MagFilter::Nearest => gl::NEAREST,
+            MagFilter::Linear => gl::LINEAR,
+        };
+    }
+    Ok(texture_data)
+}
+
+/// Empty unless there is one entry per vertex, shorter attributes would be read past their end
+fn per_vertex<T>(list: Vec<T>, count: usize) -> Vec<T> {
+    if list.len() == count {
+        list
+    } else {
+        Vec::new()
+    }
+}
+
+/// 1x1 texture, for materials defined by a factor only
+fn color_texture(color: [f32; 4]) -> TextureData {
+    let data = color
+        .iter()
+        .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect();
+    let mut texture_data = TextureData::new(Image::from_raw(1, 1, gl::RGBA, data));
+    texture_data.mipmapping = false;
+    texture_data.gl_min_filtering = gl::NEAREST;
+    texture_data.gl_mag_filtering = gl::NEAREST;
+    texture_data
+}
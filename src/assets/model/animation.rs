@@ -0,0 +1,239 @@
+use super::{node::Node, skin::Skin};
+use cgmath::{InnerSpace, Matrix4, Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Property {
+    Translation,
+    Rotation,
+    Scale,
+    /// Morph target weights, one value per target in every keyframe
+    Weights,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Linear,
+    Step,
+    /// Every keyframe holds an in tangent, a value and an out tangent, in that order
+    CubicSpline,
+}
+
+/// Keyframes of one property, times are in seconds and increasing
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Sampler {
+    pub interpolation: Interpolation,
+    pub times: Vec<f32>,
+    /// Translations and scales leave the last component at 0, rotations are [x, y, z, w], weights
+    /// only use the first component and take one entry per morph target in every keyframe
+    pub values: Vec<[f32; 4]>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Channel {
+    /// Index into `Model::nodes`
+    pub node: usize,
+    pub property: Property,
+    pub sampler: Sampler,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Animation {
+    pub name: Option<String>,
+    pub channels: Vec<Channel>,
+    /// Time of the last keyframe of every channel
+    pub duration: f32,
+}
+
+impl Sampler {
+    /// Values in every keyframe, more than one for morph target weights only
+    pub fn width(&self) -> usize {
+        let per_key = match self.interpolation {
+            Interpolation::CubicSpline => 3,
+            _ => 1,
+        };
+        (self.values.len() / (self.times.len() * per_key).max(1)).max(1)
+    }
+
+    /// Clamped to the first and last keyframes
+    pub fn sample(&self, time: f32, property: Property) -> [f32; 4] {
+        self.sample_element(time, property, 0)
+    }
+
+    pub fn sample_weights(&self, time: f32) -> Vec<f32> {
+        (0..self.width())
+            .map(|target| self.sample_element(time, Property::Weights, target)[0])
+            .collect()
+    }
+
+    fn sample_element(&self, time: f32, property: Property, element: usize) -> [f32; 4] {
+        let count = self.times.len();
+        if count == 0 {
+            return [0.0; 4];
+        }
+        let width = self.width();
+        let at = |index: usize| {
+            self.values
+                .get(index * width + element)
+                .copied()
+                .unwrap_or_default()
+        };
+        let value = |key: usize| match self.interpolation {
+            Interpolation::CubicSpline => at(key * 3 + 1),
+            _ => at(key),
+        };
+
+        let next = self.times.partition_point(|key_time| *key_time <= time);
+        if next == 0 {
+            return value(0);
+        }
+        if next == count {
+            return value(count - 1);
+        }
+        let previous = next - 1;
+        let delta = self.times[next] - self.times[previous];
+        let t = if delta > 0.0 {
+            (time - self.times[previous]) / delta
+        } else {
+            0.0
+        };
+
+        match self.interpolation {
+            Interpolation::Step => value(previous),
+            Interpolation::Linear => match property {
+                Property::Rotation => from_quaternion(
+                    to_quaternion(value(previous)).slerp(to_quaternion(value(next)), t),
+                ),
+                _ => lerp(value(previous), value(next), t),
+            },
+            Interpolation::CubicSpline => {
+                let p0 = value(previous);
+                let m0 = scale(at(previous * 3 + 2), delta);
+                let p1 = value(next);
+                let m1 = scale(at(next * 3), delta);
+                let (t2, t3) = (t * t, t * t * t);
+                let result = std::array::from_fn(|i| {
+                    (2.0 * t3 - 3.0 * t2 + 1.0) * p0[i]
+                        + (t3 - 2.0 * t2 + t) * m0[i]
+                        + (-2.0 * t3 + 3.0 * t2) * p1[i]
+                        + (t3 - t2) * m1[i]
+                });
+                match property {
+                    Property::Rotation => from_quaternion(to_quaternion(result).normalize()),
+                    _ => result,
+                }
+            }
+        }
+    }
+}
+
+impl Channel {
+    /// Writes the sampled value into the property of the target node
+    pub fn apply(&self, time: f32, nodes: &mut [Node]) {
+        let Some(node) = nodes.get_mut(self.node) else {
+            return;
+        };
+        let sample = || self.sampler.sample(time, self.property);
+        match self.property {
+            Property::Translation => {
+                let [x, y, z, _] = sample();
+                node.translation = Vector3::new(x, y, z);
+            }
+            Property::Rotation => node.rotation = to_quaternion(sample()),
+            Property::Scale => {
+                let [x, y, z, _] = sample();
+                node.scale = Vector3::new(x, y, z);
+            }
+            Property::Weights => node.weights = self.sampler.sample_weights(time),
+        }
+    }
+}
+
+impl Animation {
+    /// Poses the nodes at `time`, world transforms still need to be updated afterwards
+    pub fn apply(&self, time: f32, nodes: &mut [Node]) {
+        for channel in &self.channels {
+            channel.apply(time, nodes);
+        }
+    }
+}
+
+/// Plays one animation of a model, only CPU side so it can run without a context
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    /// Index into `Model::animations`
+    pub animation: usize,
+    pub time: f32,
+    pub speed: f32,
+    pub looping: bool,
+    pub playing: bool,
+}
+
+impl AnimationPlayer {
+    pub fn new(animation: usize) -> Self {
+        Self {
+            animation,
+            time: 0.0,
+            speed: 1.0,
+            looping: true,
+            playing: true,
+        }
+    }
+
+    /// Moves the time forward by `delta` seconds, wrapping or stopping at the end
+    pub fn advance(&mut self, delta: f32, animations: &[Animation]) {
+        let Some(animation) = animations.get(self.animation) else {
+            return;
+        };
+        if !self.playing {
+            return;
+        }
+        self.time += delta * self.speed;
+        if animation.duration <= 0.0 {
+            self.time = 0.0;
+        } else if self.looping {
+            self.time = self.time.rem_euclid(animation.duration);
+        } else if !(0.0..=animation.duration).contains(&self.time) {
+            self.time = self.time.clamp(0.0, animation.duration);
+            self.playing = false;
+        }
+    }
+
+    /// Poses the nodes and refreshes their world transforms
+    pub fn apply(&self, animations: &[Animation], nodes: &mut [Node]) {
+        if let Some(animation) = animations.get(self.animation) {
+            animation.apply(self.time, nodes);
+        }
+        super::node::update_world_transforms(nodes);
+    }
+
+    /// Poses the nodes, then evaluates the joint matrices of every skin
+    pub fn joint_matrices(
+        &self,
+        animations: &[Animation],
+        skins: &[Skin],
+        nodes: &mut [Node],
+    ) -> Vec<Vec<Matrix4<f32>>> {
+        self.apply(animations, nodes);
+        skins
+            .iter()
+            .map(|skin| skin.joint_matrices(nodes))
+            .collect()
+    }
+}
+
+fn lerp(from: [f32; 4], to: [f32; 4], t: f32) -> [f32; 4] {
+    std::array::from_fn(|i| from[i] + (to[i] - from[i]) * t)
+}
+
+fn scale(value: [f32; 4], factor: f32) -> [f32; 4] {
+    value.map(|component| component * factor)
+}
+
+fn to_quaternion(value: [f32; 4]) -> Quaternion<f32> {
+    Quaternion::new(value[3], value[0], value[1], value[2])
+}
+
+fn from_quaternion(quaternion: Quaternion<f32>) -> [f32; 4] {
+    [quaternion.v.x, quaternion.v.y, quaternion.v.z, quaternion.s]
+}
@@ -0,0 +1,556 @@
+// glTF 2.0 writer, everything goes into a single buffer: vertex data, indices, animation keyframes
+// and the textures as png. A .gltf embeds it as a base64 data URI, a .glb as its binary chunk
+use super::{
+    animation::{Interpolation, Property},
+    ModelData,
+};
+use crate::assets::{
+    error::AssetError,
+    material::{AlphaMode, MaterialData},
+    mesh::MeshData,
+    texture::TextureData,
+};
+use crate::types::Normal;
+use base64::{engine::general_purpose, Engine};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_VERSION: u32 = 2;
+const GLB_JSON: u32 = 0x4E4F534A;
+const GLB_BIN: u32 = 0x004E4942;
+
+/// Writes a .gltf or a .glb depending on the extension of `path`
+pub fn export(model: &ModelData, path: &Path) -> Result<(), AssetError> {
+    let bytes = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("gltf") => to_gltf(model)?.into_bytes(),
+        Some("glb") => to_glb(model)?,
+        extension => {
+            return Err(AssetError::UnsupportedFormat(format!(
+                "model export extension '{}'",
+                extension.unwrap_or_default()
+            )))
+        }
+    };
+    std::fs::write(path, bytes).map_err(|error| AssetError::Io(path.to_owned(), error))
+}
+
+/// Json document with the buffer embedded as a data URI
+pub fn to_gltf(model: &ModelData) -> Result<String, AssetError> {
+    let (mut root, bin) = Builder::default().build(model)?;
+    root["buffers"] = json!([{
+        "byteLength": bin.len(),
+        "uri": format!(
+            "data:application/octet-stream;base64,{}",
+            general_purpose::STANDARD.encode(&bin)
+        ),
+    }]);
+    Ok(serde_json::to_string_pretty(&root)?)
+}
+
+pub fn to_glb(model: &ModelData) -> Result<Vec<u8>, AssetError> {
+    let (mut root, mut bin) = Builder::default().build(model)?;
+    root["buffers"] = json!([{ "byteLength": bin.len() }]);
+    let mut json = serde_json::to_vec(&root)?;
+    // Chunks are 4 bytes aligned, json with spaces and the binary with zeros
+    pad(&mut json, b' ');
+    pad(&mut bin, 0);
+
+    let length = 12 + 8 + json.len() + 8 + bin.len();
+    let mut glb = Vec::with_capacity(length);
+    glb.extend_from_slice(GLB_MAGIC);
+    glb.extend_from_slice(&GLB_VERSION.to_le_bytes());
+    glb.extend_from_slice(&(length as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&GLB_JSON.to_le_bytes());
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&GLB_BIN.to_le_bytes());
+    glb.extend_from_slice(&bin);
+    Ok(glb)
+}
+
+#[derive(Default)]
+struct Builder {
+    bin: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    images: Vec<Value>,
+    samplers: Vec<Value>,
+    textures: Vec<Value>,
+}
+
+impl Builder {
+    fn build(mut self, model: &ModelData) -> Result<(Value, Vec<u8>), AssetError> {
+        let mut root = Map::new();
+        root.insert(
+            "asset".to_owned(),
+            json!({ "version": "2.0", "generator": "revenant" }),
+        );
+
+        let mut materials = Vec::new();
+        for material in &model.materials {
+            materials.push(self.material(material)?);
+        }
+
+        let meshes: Vec<Value> = model
+            .mesh_primitives
+            .iter()
+            .map(|primitives| {
+                let primitives: Vec<Value> = primitives
+                    .iter()
+                    .map(|primitive| {
+                        self.primitive(
+                            &model.meshes[*primitive],
+                            model.primitive_materials.get(*primitive).copied(),
+                        )
+                    })
+                    .collect();
+                json!({ "primitives": primitives })
+            })
+            .collect();
+
+        let nodes: Vec<Value> = model
+            .nodes
+            .iter()
+            .map(|node| {
+                let mut value = Map::new();
+                if let Some(name) = &node.name {
+                    value.insert("name".to_owned(), json!(name));
+                }
+                if !node.children.is_empty() {
+                    value.insert("children".to_owned(), json!(node.children));
+                }
+                if let Some(mesh) = node.mesh {
+                    value.insert("mesh".to_owned(), json!(mesh));
+                }
+                if let Some(skin) = node.skin {
+                    value.insert("skin".to_owned(), json!(skin));
+                }
+                if !node.weights.is_empty() {
+                    value.insert("weights".to_owned(), json!(node.weights));
+                }
+                let translation: [f32; 3] = node.translation.into();
+                let rotation = [
+                    node.rotation.v.x,
+                    node.rotation.v.y,
+                    node.rotation.v.z,
+                    node.rotation.s,
+                ];
+                let scale: [f32; 3] = node.scale.into();
+                value.insert("translation".to_owned(), json!(translation));
+                value.insert("rotation".to_owned(), json!(rotation));
+                value.insert("scale".to_owned(), json!(scale));
+                Value::Object(value)
+            })
+            .collect();
+
+        let scenes: Vec<Value> = model
+            .scenes
+            .iter()
+            .map(|scene| match &scene.name {
+                Some(name) => json!({ "name": name, "nodes": scene.roots }),
+                None => json!({ "nodes": scene.roots }),
+            })
+            .collect();
+
+        let skins: Vec<Value> = model
+            .skins
+            .iter()
+            .map(|skin| {
+                let matrices: Vec<f32> = skin
+                    .inverse_bind_matrices
+                    .iter()
+                    .flat_map(|matrix| *AsRef::<[f32; 16]>::as_ref(matrix))
+                    .collect();
+                let mut value = json!({
+                    "joints": skin.joints,
+                    "inverseBindMatrices": self.floats(&matrices, "MAT4", 16, None, false),
+                });
+                if let Some(name) = &skin.name {
+                    value["name"] = json!(name);
+                }
+                value
+            })
+            .collect();
+
+        let animations: Vec<Value> = model
+            .animations
+            .iter()
+            .filter(|animation| !animation.channels.is_empty())
+            .map(|animation| {
+                let mut samplers = Vec::new();
+                let mut channels = Vec::new();
+                for channel in &animation.channels {
+                    let sampler = &channel.sampler;
+                    let input = self.floats(&sampler.times, "SCALAR", 1, None, true);
+                    let (kind, components, path) = match channel.property {
+                        Property::Translation => ("VEC3", 3, "translation"),
+                        Property::Rotation => ("VEC4", 4, "rotation"),
+                        Property::Scale => ("VEC3", 3, "scale"),
+                        Property::Weights => ("SCALAR", 1, "weights"),
+                    };
+                    let values: Vec<f32> = sampler
+                        .values
+                        .iter()
+                        .flat_map(|value| value[..components].to_vec())
+                        .collect();
+                    let output = self.floats(&values, kind, components, None, false);
+                    let interpolation = match sampler.interpolation {
+                        Interpolation::Linear => "LINEAR",
+                        Interpolation::Step => "STEP",
+                        Interpolation::CubicSpline => "CUBICSPLINE",
+                    };
+                    channels.push(json!({
+                        "sampler": samplers.len(),
+                        "target": { "node": channel.node, "path": path },
+                    }));
+                    samplers.push(json!({
+                        "input": input,
+                        "output": output,
+                        "interpolation": interpolation,
+                    }));
+                }
+                let mut value = json!({ "samplers": samplers, "channels": channels });
+                if let Some(name) = &animation.name {
+                    value["name"] = json!(name);
+                }
+                value
+            })
+            .collect();
+
+        // Arrays must not be empty when present
+        for (key, values) in [
+            ("scenes", scenes),
+            ("nodes", nodes),
+            ("meshes", meshes),
+            ("materials", materials),
+            ("skins", skins),
+            ("animations", animations),
+            ("textures", self.textures),
+            ("images", self.images),
+            ("samplers", self.samplers),
+            ("accessors", self.accessors),
+            ("bufferViews", self.buffer_views),
+        ] {
+            if !values.is_empty() {
+                root.insert(key.to_owned(), Value::Array(values));
+            }
+        }
+        if !model.scenes.is_empty() {
+            root.insert("scene".to_owned(), json!(model.scene));
+        }
+
+        Ok((Value::Object(root), self.bin))
+    }
+
+    fn primitive(&mut self, mesh: &MeshData, material: Option<usize>) -> Value {
+        let mut attributes = Map::new();
+        let positions: Vec<f32> = mesh
+            .vertices
+            .iter()
+            .flat_map(|vertex| [vertex.position.x, vertex.position.y, vertex.position.z])
+            .collect();
+        attributes.insert(
+            "POSITION".to_owned(),
+            json!(self.floats(&positions, "VEC3", 3, Some(ARRAY_BUFFER), true)),
+        );
+        // Missing normals were imported as zeros, which are not valid glTF normals
+        if mesh
+            .vertices
+            .iter()
+            .any(|vertex| vertex.normal != Normal::new(0.0, 0.0, 0.0))
+        {
+            let normals: Vec<f32> = mesh
+                .vertices
+                .iter()
+                .flat_map(|vertex| [vertex.normal.x, vertex.normal.y, vertex.normal.z])
+                .collect();
+            attributes.insert(
+                "NORMAL".to_owned(),
+                json!(self.floats(&normals, "VEC3", 3, Some(ARRAY_BUFFER), false)),
+            );
+        }
+        let tex_coords: Vec<f32> = mesh
+            .vertices
+            .iter()
+            .flat_map(|vertex| [vertex.tex_coord.x, vertex.tex_coord.y])
+            .collect();
+        attributes.insert(
+            "TEXCOORD_0".to_owned(),
+            json!(self.floats(&tex_coords, "VEC2", 2, Some(ARRAY_BUFFER), false)),
+        );
+        for (set, tex_coords) in mesh.attributes.tex_coords.iter().enumerate() {
+            let tex_coords: Vec<f32> = tex_coords.iter().flatten().copied().collect();
+            attributes.insert(
+                format!("TEXCOORD_{}", set + 1),
+                json!(self.floats(&tex_coords, "VEC2", 2, Some(ARRAY_BUFFER), false)),
+            );
+        }
+        for (name, values) in [
+            ("TANGENT", &mesh.attributes.tangents),
+            ("COLOR_0", &mesh.attributes.colors),
+        ] {
+            if values.is_empty() {
+                continue;
+            }
+            let values: Vec<f32> = values.iter().flatten().copied().collect();
+            attributes.insert(
+                name.to_owned(),
+                json!(self.floats(&values, "VEC4", 4, Some(ARRAY_BUFFER), false)),
+            );
+        }
+        if !mesh.skin.is_empty() {
+            let joints: Vec<u16> = mesh.skin.iter().flat_map(|skin| skin.joints).collect();
+            let bytes: Vec<u8> = joints
+                .iter()
+                .flat_map(|joint| joint.to_le_bytes())
+                .collect();
+            let view = self.view(&bytes, Some(ARRAY_BUFFER));
+            attributes.insert(
+                "JOINTS_0".to_owned(),
+                json!(self.accessor(view, UNSIGNED_SHORT, mesh.skin.len(), "VEC4", None)),
+            );
+            let weights: Vec<f32> = mesh.skin.iter().flat_map(|skin| skin.weights).collect();
+            attributes.insert(
+                "WEIGHTS_0".to_owned(),
+                json!(self.floats(&weights, "VEC4", 4, Some(ARRAY_BUFFER), false)),
+            );
+        }
+
+        let mut primitive = json!({ "attributes": attributes, "mode": mesh.gl_mode });
+        if !mesh.indices.is_empty() {
+            let bytes: Vec<u8> = mesh
+                .indices
+                .iter()
+                .flat_map(|index| index.to_le_bytes())
+                .collect();
+            let view = self.view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+            primitive["indices"] =
+                json!(self.accessor(view, UNSIGNED_INT, mesh.indices.len(), "SCALAR", None));
+        }
+        if let Some(material) = material {
+            primitive["material"] = json!(material);
+        }
+
+        let targets: Vec<Value> = mesh
+            .morph_targets
+            .iter()
+            .map(|target| {
+                let mut attributes = Map::new();
+                for (name, deltas) in [
+                    ("POSITION", &target.positions),
+                    ("NORMAL", &target.normals),
+                    ("TANGENT", &target.tangents),
+                ] {
+                    if deltas.is_empty() {
+                        continue;
+                    }
+                    let deltas: Vec<f32> = deltas.iter().flatten().copied().collect();
+                    // Only positions need bounds
+                    let bounds = name == "POSITION";
+                    attributes.insert(
+                        name.to_owned(),
+                        json!(self.floats(&deltas, "VEC3", 3, Some(ARRAY_BUFFER), bounds)),
+                    );
+                }
+                Value::Object(attributes)
+            })
+            .collect();
+        if !targets.is_empty() {
+            primitive["targets"] = Value::Array(targets);
+        }
+        primitive
+    }
+
+    fn material(&mut self, material: &MaterialData) -> Result<Value, AssetError> {
+        let mut value = Map::new();
+        let mut pbr = Map::new();
+        match material {
+            MaterialData::Pbr {
+                albedo,
+                base_color_factor,
+                metallic_roughness,
+                metallic_factor,
+                roughness_factor,
+                normal,
+                normal_scale,
+                occlusion,
+                occlusion_strength,
+                emissive,
+                emissive_factor,
+                alpha_mode,
+                alpha_cutoff,
+                double_sided,
+            } => {
+                pbr.insert("baseColorFactor".to_owned(), json!(base_color_factor));
+                pbr.insert("metallicFactor".to_owned(), json!(metallic_factor));
+                pbr.insert("roughnessFactor".to_owned(), json!(roughness_factor));
+                if let Some(index) = self.texture(albedo)? {
+                    pbr.insert("baseColorTexture".to_owned(), json!({ "index": index }));
+                }
+                if let Some(index) = self.texture(metallic_roughness)? {
+                    pbr.insert(
+                        "metallicRoughnessTexture".to_owned(),
+                        json!({ "index": index }),
+                    );
+                }
+                if let Some(normal) = normal {
+                    let index = self.texture_always(normal)?;
+                    value.insert(
+                        "normalTexture".to_owned(),
+                        json!({ "index": index, "scale": normal_scale }),
+                    );
+                }
+                if let Some(index) = self.texture(occlusion)? {
+                    value.insert(
+                        "occlusionTexture".to_owned(),
+                        json!({ "index": index, "strength": occlusion_strength }),
+                    );
+                }
+                if let Some(index) = self.texture(emissive)? {
+                    value.insert("emissiveTexture".to_owned(), json!({ "index": index }));
+                }
+                value.insert("emissiveFactor".to_owned(), json!(emissive_factor));
+                let alpha_mode = match alpha_mode {
+                    AlphaMode::Opaque => "OPAQUE",
+                    AlphaMode::Mask => {
+                        value.insert("alphaCutoff".to_owned(), json!(alpha_cutoff));
+                        "MASK"
+                    }
+                    AlphaMode::Blend => "BLEND",
+                };
+                value.insert("alphaMode".to_owned(), json!(alpha_mode));
+                value.insert("doubleSided".to_owned(), json!(double_sided));
+            }
+            // Closest metallic-roughness look, the specular map has no equivalent
+            MaterialData::Phong {
+                diffuse, emissive, ..
+            } => {
+                pbr.insert("metallicFactor".to_owned(), json!(0.0));
+                if let Some(index) = self.texture(diffuse)? {
+                    pbr.insert("baseColorTexture".to_owned(), json!({ "index": index }));
+                }
+                if let Some(index) = self.texture(emissive)? {
+                    value.insert("emissiveTexture".to_owned(), json!({ "index": index }));
+                    value.insert("emissiveFactor".to_owned(), json!([1.0, 1.0, 1.0]));
+                }
+            }
+            MaterialData::Normal { normal } => {
+                let index = self.texture_always(normal)?;
+                value.insert("normalTexture".to_owned(), json!({ "index": index }));
+            }
+        }
+        value.insert("pbrMetallicRoughness".to_owned(), Value::Object(pbr));
+        Ok(Value::Object(value))
+    }
+
+    /// `None` for the 1x1 white textures standing in for missing ones on import
+    fn texture(&mut self, texture: &TextureData) -> Result<Option<usize>, AssetError> {
+        let image = &texture.image;
+        if image.width() == 1 && image.height() == 1 && image.data().iter().all(|byte| *byte == 255)
+        {
+            return Ok(None);
+        }
+        self.texture_always(texture).map(Some)
+    }
+
+    // OPTIMIZE share images and samplers used by several textures
+    fn texture_always(&mut self, texture: &TextureData) -> Result<usize, AssetError> {
+        let png = texture.image.to_png()?;
+        let view = self.view(&png, None);
+        self.images
+            .push(json!({ "bufferView": view, "mimeType": "image/png" }));
+        // glTF uses the GL enums for its samplers
+        self.samplers.push(json!({
+            "magFilter": texture.gl_mag_filtering,
+            "minFilter": texture.gl_min_filtering,
+            "wrapS": texture.gl_s_wrapping,
+            "wrapT": texture.gl_t_wrapping,
+        }));
+        self.textures.push(json!({
+            "sampler": self.samplers.len() - 1,
+            "source": self.images.len() - 1,
+        }));
+        Ok(self.textures.len() - 1)
+    }
+
+    /// Accessor over a new view of `values`, with per component bounds when `bounds` is set
+    fn floats(
+        &mut self,
+        values: &[f32],
+        kind: &str,
+        components: usize,
+        target: Option<u32>,
+        bounds: bool,
+    ) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let view = self.view(&bytes, target);
+        let count = values.len() / components;
+        let bounds = (bounds && count > 0).then(|| {
+            let mut min = vec![f32::MAX; components];
+            let mut max = vec![f32::MIN; components];
+            for element in values.chunks_exact(components) {
+                for (component, value) in element.iter().enumerate() {
+                    min[component] = min[component].min(*value);
+                    max[component] = max[component].max(*value);
+                }
+            }
+            (min, max)
+        });
+        self.accessor(view, FLOAT, count, kind, bounds)
+    }
+
+    fn accessor(
+        &mut self,
+        view: usize,
+        component_type: u32,
+        count: usize,
+        kind: &str,
+        bounds: Option<(Vec<f32>, Vec<f32>)>,
+    ) -> usize {
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": component_type,
+            "count": count,
+            "type": kind,
+        });
+        if let Some((min, max)) = bounds {
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    /// Views start 4 bytes aligned, enough for every component type
+    fn view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        pad(&mut self.bin, 0);
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.bin.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.bin.extend_from_slice(bytes);
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+}
+
+fn pad(bytes: &mut Vec<u8>, with: u8) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(with);
+    }
+}
@@ -0,0 +1,76 @@
+use cgmath::{Matrix4, Quaternion, SquareMatrix, Vector3};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Node {
+    pub name: Option<String>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    /// Index into the glTF meshes, see `Model::mesh_primitives`
+    pub mesh: Option<usize>,
+    /// Index into `Model::skins`, the mesh is then drawn with its joint matrices
+    pub skin: Option<usize>,
+    /// Morph target weights of the mesh, the ones of the mesh unless the node overrides them
+    pub weights: Vec<f32>,
+    // Matrices from the glTF are decomposed so the node can be animated
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+    /// Parent world matrix times the local one, refreshed by `update_world_transforms`
+    pub world: Matrix4<f32>,
+}
+
+impl Node {
+    pub fn from_gltf(node: &gltf::Node, parent: Option<usize>) -> Self {
+        let (translation, rotation, scale) = node.transform().decomposed();
+        Self {
+            name: node.name().map(str::to_owned),
+            parent,
+            children: node.children().map(|child| child.index()).collect(),
+            mesh: node.mesh().map(|mesh| mesh.index()),
+            skin: node.skin().map(|skin| skin.index()),
+            weights: node
+                .weights()
+                .or_else(|| node.mesh().and_then(|mesh| mesh.weights()))
+                .map(<[f32]>::to_vec)
+                .unwrap_or_default(),
+            translation: translation.into(),
+            // glTF stores quaternions as [x, y, z, w]
+            rotation: Quaternion::new(rotation[3], rotation[0], rotation[1], rotation[2]),
+            scale: scale.into(),
+            world: Matrix4::identity(),
+        }
+    }
+
+    pub fn local(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Scene {
+    pub name: Option<String>,
+    pub roots: Vec<usize>,
+}
+
+/// Walks every tree from its root, parents are always updated before their children
+pub fn update_world_transforms(nodes: &mut [Node]) {
+    let mut stack: Vec<(usize, Matrix4<f32>)> = nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| node.parent.is_none())
+        .map(|(index, _)| (index, Matrix4::identity()))
+        .collect();
+    // A malformed file could loop, or share a child between parents
+    let mut visited = vec![false; nodes.len()];
+    while let Some((index, parent_world)) = stack.pop() {
+        if std::mem::replace(&mut visited[index], true) {
+            continue;
+        }
+        let world = parent_world * nodes[index].local();
+        nodes[index].world = world;
+        stack.extend(nodes[index].children.iter().map(|child| (*child, world)));
+    }
+}
@@ -0,0 +1,687 @@
+// Wavefront OBJ and its MTL libraries. Every object or group becomes a root node with one mesh,
+// split in one primitive per material used by its faces
+use super::{
+    color_texture,
+    node::{self, Node, Scene},
+    ModelData,
+};
+use crate::assets::{
+    error::AssetError,
+    image::Image,
+    material::{AlphaMode, MaterialData},
+    mesh::{MeshData, MeshDrawType, Vertex, VertexAttributes},
+    texture::TextureData,
+};
+use cgmath::{vec3, InnerSpace, Matrix4, Quaternion, SquareMatrix, Vector3};
+use image::{DynamicImage, GenericImageView};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Indices into the position, texture coordinate and normal lists
+type Corner = (usize, Option<usize>, Option<usize>);
+
+#[derive(Default)]
+struct Group {
+    name: Option<String>,
+    /// Faces of every material, in the order they are first used
+    primitives: Vec<(Option<String>, Vec<Vec<Corner>>)>,
+}
+
+pub fn import(path: &Path) -> Result<ModelData, AssetError> {
+    let source = read(path)?;
+    let folder = path.parent().unwrap_or(Path::new(""));
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut tex_coords: Vec<[f32; 2]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut groups = vec![Group::default()];
+    let mut material: Option<String> = None;
+    let mut libraries = Vec::new();
+
+    for (number, line) in lines(&source) {
+        let error = |why: String| decode_error(path, number, why);
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        match keyword {
+            "v" => positions.push(floats(&mut tokens).ok_or_else(|| error(line.clone()))?),
+            "vt" => {
+                // Only u is mandatory, v goes from the bottom up
+                let u = tokens.next().and_then(|u| u.parse().ok());
+                let v = tokens.next().map_or(Some(0.0), |v| v.parse().ok());
+                let (Some(u), Some(v)) = (u, v) else {
+                    return Err(error(line.clone()));
+                };
+                tex_coords.push([u, 1.0 - v]);
+            }
+            "vn" => normals.push(floats(&mut tokens).ok_or_else(|| error(line.clone()))?),
+            "f" => {
+                let face = tokens
+                    .map(|token| corner(token, &positions, &tex_coords, &normals))
+                    .collect::<Option<Vec<Corner>>>()
+                    .ok_or_else(|| error(format!("invalid face '{}'", line)))?;
+                if face.len() < 3 {
+                    return Err(error(format!("face with {} corners", face.len())));
+                }
+                let group = groups.last_mut().unwrap();
+                match group
+                    .primitives
+                    .iter_mut()
+                    .find(|(name, _)| *name == material)
+                {
+                    Some((_, faces)) => faces.push(face),
+                    None => group.primitives.push((material.clone(), vec![face])),
+                }
+            }
+            "o" | "g" => {
+                let name = tokens.collect::<Vec<&str>>().join(" ");
+                let name = (!name.is_empty()).then_some(name);
+                let group = groups.last_mut().unwrap();
+                if group.primitives.is_empty() {
+                    group.name = name;
+                } else {
+                    groups.push(Group {
+                        name,
+                        primitives: Vec::new(),
+                    });
+                }
+            }
+            "usemtl" => material = Some(tokens.collect::<Vec<&str>>().join(" ")),
+            "mtllib" => libraries.extend(mtllib(&line, folder)),
+            // TODO lines, points and free-form geometry
+            _ => {}
+        }
+    }
+
+    let mut materials = Vec::new();
+    let mut material_indices = HashMap::new();
+    for library in libraries {
+        for (name, material) in parse_mtl(&library)? {
+            material_indices.insert(name, materials.len());
+            materials.push(material.to_data(library.parent().unwrap_or(Path::new("")))?);
+        }
+    }
+
+    let mut meshes = Vec::new();
+    let mut primitive_materials = Vec::new();
+    let mut mesh_primitives = Vec::new();
+    let mut nodes = Vec::new();
+    // Faces without a known material share a plain white one
+    let default_material = materials.len();
+    for group in groups {
+        if group.primitives.is_empty() {
+            continue;
+        }
+        let mut primitives = Vec::new();
+        for (material, faces) in &group.primitives {
+            primitives.push(meshes.len());
+            primitive_materials.push(
+                material
+                    .as_ref()
+                    .and_then(|material| material_indices.get(material))
+                    .copied()
+                    .unwrap_or(default_material),
+            );
+            meshes.push(primitive(faces, &positions, &tex_coords, &normals));
+        }
+        nodes.push(Node {
+            name: group.name,
+            parent: None,
+            children: Vec::new(),
+            mesh: Some(mesh_primitives.len()),
+            skin: None,
+            weights: Vec::new(),
+            translation: vec3(0.0, 0.0, 0.0),
+            rotation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+            scale: vec3(1.0, 1.0, 1.0),
+            world: Matrix4::identity(),
+        });
+        mesh_primitives.push(primitives);
+    }
+    if primitive_materials.contains(&default_material) {
+        materials.push(MtlMaterial::default().to_data(folder)?);
+    }
+    node::update_world_transforms(&mut nodes);
+
+    let mut model = ModelData {
+        meshes,
+        materials,
+        primitive_materials,
+        mesh_primitives,
+        scenes: vec![Scene {
+            name: None,
+            roots: (0..nodes.len()).collect(),
+        }],
+        nodes,
+        scene: 0,
+        skins: Vec::new(),
+        animations: Vec::new(),
+        lod_screen_sizes: Vec::new(),
+    };
+    model.generate_missing_tangents();
+    Ok(model)
+}
+
+/// Triangulated faces, corners sharing every index are merged
+fn primitive(
+    faces: &[Vec<Corner>],
+    positions: &[[f32; 3]],
+    tex_coords: &[[f32; 2]],
+    normals: &[[f32; 3]],
+) -> MeshData {
+    let mut corners = Vec::new();
+    for face in faces {
+        let polygon: Vec<[f32; 3]> = face.iter().map(|corner| positions[corner.0]).collect();
+        for triangle in triangulate(&polygon) {
+            corners.extend(triangle.map(|index| face[index]));
+        }
+    }
+
+    // Corners without a normal get the area weighted normal of the faces around their position
+    let mut generated: HashMap<usize, Vector3<f32>> = HashMap::new();
+    for triangle in corners.chunks_exact(3) {
+        if triangle.iter().all(|corner| corner.2.is_some()) {
+            continue;
+        }
+        let [a, b, c] = [0, 1, 2].map(|index| Vector3::from(positions[triangle[index].0]));
+        let normal = (b - a).cross(c - a);
+        for corner in triangle {
+            *generated.entry(corner.0).or_insert(vec3(0.0, 0.0, 0.0)) += normal;
+        }
+    }
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::with_capacity(corners.len());
+    let mut merged: HashMap<Corner, u32> = HashMap::new();
+    for corner in corners {
+        let index = *merged.entry(corner).or_insert_with(|| {
+            let normal = match corner.2 {
+                Some(normal) => Vector3::from(normals[normal]),
+                None => {
+                    let normal = generated[&corner.0];
+                    if normal.magnitude2() > 0.0 {
+                        normal.normalize()
+                    } else {
+                        normal
+                    }
+                }
+            };
+            vertices.push(Vertex {
+                position: positions[corner.0].into(),
+                normal,
+                tex_coord: corner.1.map_or([0.0, 0.0], |uv| tex_coords[uv]).into(),
+            });
+            vertices.len() as u32 - 1
+        });
+        indices.push(index);
+    }
+
+    MeshData {
+        gl_mode: gl::TRIANGLES,
+        vertices,
+        indices,
+        draw_type: MeshDrawType::DrawElements,
+        skin: Vec::new(),
+        morph_targets: Vec::new(),
+        attributes: VertexAttributes::default(),
+        lods: Vec::new(),
+    }
+}
+
+/// The material libraries of an .obj and the maps they reference
+pub fn dependencies(path: &Path) -> Result<Vec<PathBuf>, AssetError> {
+    let source = read(path)?;
+    let folder = path.parent().unwrap_or(Path::new(""));
+    let mut paths = Vec::new();
+    for (_, line) in lines(&source) {
+        if line.split_whitespace().next() != Some("mtllib") {
+            continue;
+        }
+        for library in mtllib(&line, folder) {
+            let library_folder = library.parent().unwrap_or(Path::new("")).to_owned();
+            // A library being saved still counts, its maps are found on the next reload
+            let materials = parse_mtl(&library).unwrap_or_default();
+            paths.push(library);
+            for (_, material) in materials {
+                let maps = [
+                    material.diffuse_map,
+                    material.specular_map,
+                    material.emissive_map,
+                    material.bump_map,
+                    material.roughness_map,
+                    material.metallic_map,
+                ];
+                paths.extend(
+                    maps.into_iter()
+                        .flatten()
+                        .map(|map| library_folder.join(map.path)),
+                );
+            }
+        }
+    }
+    Ok(paths)
+}
+
+/// Ear clipping in the plane of the polygon, concave ones included. Falls back to a fan for
+/// degenerate polygons
+pub fn triangulate(polygon: &[[f32; 3]]) -> Vec<[usize; 3]> {
+    if polygon.len() == 3 {
+        return vec![[0, 1, 2]];
+    }
+    // Newell normal, its largest axis is dropped to project the polygon
+    let mut normal = vec3(0.0, 0.0, 0.0);
+    for (index, current) in polygon.iter().enumerate() {
+        let next = polygon[(index + 1) % polygon.len()];
+        normal.x += (current[1] - next[1]) * (current[2] + next[2]);
+        normal.y += (current[2] - next[2]) * (current[0] + next[0]);
+        normal.z += (current[0] - next[0]) * (current[1] + next[1]);
+    }
+    let (u, v, sign) = if normal.x.abs() >= normal.y.abs() && normal.x.abs() >= normal.z.abs() {
+        (1, 2, normal.x.signum())
+    } else if normal.y.abs() >= normal.z.abs() {
+        (2, 0, normal.y.signum())
+    } else {
+        (0, 1, normal.z.signum())
+    };
+    let points: Vec<[f32; 2]> = polygon.iter().map(|point| [point[u], point[v]]).collect();
+    // Positive for counter clockwise corners once projected
+    let cross = |a: usize, b: usize, c: usize| {
+        let (a, b, c) = (points[a], points[b], points[c]);
+        sign * ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]))
+    };
+
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    let mut triangles = Vec::with_capacity(polygon.len() - 2);
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let ear = (0..count).find(|&index| {
+            let (a, b, c) = (
+                remaining[(index + count - 1) % count],
+                remaining[index],
+                remaining[(index + 1) % count],
+            );
+            cross(a, b, c) > 0.0
+                && remaining.iter().all(|&other| {
+                    other == a
+                        || other == b
+                        || other == c
+                        || cross(a, b, other) < 0.0
+                        || cross(b, c, other) < 0.0
+                        || cross(c, a, other) < 0.0
+                })
+        });
+        let Some(index) = ear else {
+            break;
+        };
+        triangles.push([
+            remaining[(index + count - 1) % count],
+            remaining[index],
+            remaining[(index + 1) % count],
+        ]);
+        remaining.remove(index);
+    }
+    for index in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[index], remaining[index + 1]]);
+    }
+    triangles
+}
+
+#[derive(Clone)]
+struct MapReference {
+    path: PathBuf,
+    clamp: bool,
+    /// Only used by bump maps
+    scale: f32,
+}
+
+/// Statements of one `newmtl`, the PBR ones come from the common extension (`Pr`, `Pm`, ...)
+#[derive(Clone)]
+struct MtlMaterial {
+    diffuse: [f32; 3],
+    specular: [f32; 3],
+    emissive: Option<[f32; 3]>,
+    shininess: f32,
+    dissolve: f32,
+    roughness: Option<f32>,
+    metallic: Option<f32>,
+    diffuse_map: Option<MapReference>,
+    specular_map: Option<MapReference>,
+    emissive_map: Option<MapReference>,
+    bump_map: Option<MapReference>,
+    roughness_map: Option<MapReference>,
+    metallic_map: Option<MapReference>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: [1.0, 1.0, 1.0],
+            specular: [0.0, 0.0, 0.0],
+            emissive: None,
+            shininess: 32.0,
+            dissolve: 1.0,
+            roughness: None,
+            metallic: None,
+            diffuse_map: None,
+            specular_map: None,
+            emissive_map: None,
+            bump_map: None,
+            roughness_map: None,
+            metallic_map: None,
+        }
+    }
+}
+
+impl MtlMaterial {
+    fn is_pbr(&self) -> bool {
+        self.roughness.is_some()
+            || self.metallic.is_some()
+            || self.roughness_map.is_some()
+            || self.metallic_map.is_some()
+            || self.bump_map.is_some()
+            || self.dissolve < 1.0
+    }
+
+    /// Phong unless the material needs what only `Pbr` has: normal maps, transparency or the
+    /// PBR statements
+    fn to_data(&self, folder: &Path) -> Result<MaterialData, AssetError> {
+        let texture = |map: &Option<MapReference>, color: [f32; 3]| match map {
+            Some(map) => load_texture(folder, map),
+            None => Ok(color_texture([color[0], color[1], color[2], 1.0])),
+        };
+        let emissive = self.emissive.unwrap_or(match self.emissive_map {
+            Some(_) => [1.0, 1.0, 1.0],
+            None => [0.0, 0.0, 0.0],
+        });
+        if !self.is_pbr() {
+            return Ok(MaterialData::Phong {
+                diffuse: texture(&self.diffuse_map, self.diffuse)?,
+                specular: texture(&self.specular_map, self.specular)?,
+                specular_strength: self.shininess.max(1.0),
+                emissive: texture(&self.emissive_map, emissive)?,
+            });
+        }
+
+        let [r, g, b] = self.diffuse;
+        Ok(MaterialData::Pbr {
+            albedo: texture(&self.diffuse_map, [1.0, 1.0, 1.0])?,
+            base_color_factor: [r, g, b, self.dissolve],
+            metallic_roughness: self.metallic_roughness(folder)?,
+            metallic_factor: self.metallic.unwrap_or(if self.metallic_map.is_some() {
+                1.0
+            } else {
+                0.0
+            }),
+            // Without a roughness, the usual conversion from the Phong exponent
+            roughness_factor: self.roughness.unwrap_or(if self.roughness_map.is_some() {
+                1.0
+            } else {
+                (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt()
+            }),
+            normal: self
+                .bump_map
+                .as_ref()
+                .map(|map| load_texture(folder, map))
+                .transpose()?,
+            normal_scale: self.bump_map.as_ref().map_or(1.0, |map| map.scale),
+            occlusion: color_texture([1.0, 1.0, 1.0, 1.0]),
+            occlusion_strength: 1.0,
+            emissive: texture(&self.emissive_map, [1.0, 1.0, 1.0])?,
+            emissive_factor: emissive,
+            alpha_mode: if self.dissolve < 1.0 {
+                AlphaMode::Blend
+            } else {
+                AlphaMode::Opaque
+            },
+            alpha_cutoff: 0.5,
+            double_sided: false,
+        })
+    }
+
+    /// Packs the separate roughness and metallic maps the way glTF expects, roughness in green
+    /// and metallic in blue
+    fn metallic_roughness(&self, folder: &Path) -> Result<TextureData, AssetError> {
+        let roughness = self
+            .roughness_map
+            .as_ref()
+            .map(|map| load_dynamic_image(&folder.join(&map.path)))
+            .transpose()?;
+        let metallic = self
+            .metallic_map
+            .as_ref()
+            .map(|map| load_dynamic_image(&folder.join(&map.path)))
+            .transpose()?;
+        let (width, height) = match (&roughness, &metallic) {
+            (Some(image), _) | (None, Some(image)) => image.dimensions(),
+            (None, None) => return Ok(color_texture([1.0, 1.0, 1.0, 1.0])),
+        };
+        let channel = |image: Option<DynamicImage>| {
+            image.map(|image| {
+                image
+                    .resize_exact(width, height, image::imageops::FilterType::Triangle)
+                    .to_luma8()
+            })
+        };
+        let (roughness, metallic) = (channel(roughness), channel(metallic));
+        let mut data = Vec::with_capacity((width * height * 3) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let value = |image: &Option<image::GrayImage>| {
+                    image.as_ref().map_or(255, |image| image.get_pixel(x, y)[0])
+                };
+                data.extend([255, value(&roughness), value(&metallic)]);
+            }
+        }
+        Ok(TextureData::new(Image::from_raw(
+            width,
+            height,
+            gl::RGB,
+            data,
+        )))
+    }
+}
+
+fn parse_mtl(path: &Path) -> Result<Vec<(String, MtlMaterial)>, AssetError> {
+    let source = read(path)?;
+    let mut materials: Vec<(String, MtlMaterial)> = Vec::new();
+    for (number, line) in lines(&source) {
+        let error = || decode_error(path, number, line.clone());
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        if keyword == "newmtl" {
+            materials.push((rest(&line, keyword), MtlMaterial::default()));
+            continue;
+        }
+        let Some((_, material)) = materials.last_mut() else {
+            continue;
+        };
+        let scalar = |tokens: &mut std::str::SplitWhitespace| {
+            tokens.next().and_then(|value| value.parse::<f32>().ok())
+        };
+        match keyword {
+            "Kd" => material.diffuse = color(&mut tokens).ok_or_else(error)?,
+            "Ks" => material.specular = color(&mut tokens).ok_or_else(error)?,
+            "Ke" => material.emissive = Some(color(&mut tokens).ok_or_else(error)?),
+            "Ns" => material.shininess = scalar(&mut tokens).ok_or_else(error)?,
+            "d" => material.dissolve = scalar(&mut tokens).ok_or_else(error)?,
+            "Tr" => material.dissolve = 1.0 - scalar(&mut tokens).ok_or_else(error)?,
+            "Pr" => material.roughness = Some(scalar(&mut tokens).ok_or_else(error)?),
+            "Pm" => material.metallic = Some(scalar(&mut tokens).ok_or_else(error)?),
+            "map_Kd" => material.diffuse_map = Some(map_reference(tokens).ok_or_else(error)?),
+            "map_Ks" => material.specular_map = Some(map_reference(tokens).ok_or_else(error)?),
+            "map_Ke" => material.emissive_map = Some(map_reference(tokens).ok_or_else(error)?),
+            "map_Bump" | "map_bump" | "bump" | "norm" => {
+                material.bump_map = Some(map_reference(tokens).ok_or_else(error)?)
+            }
+            "map_Pr" => material.roughness_map = Some(map_reference(tokens).ok_or_else(error)?),
+            "map_Pm" => material.metallic_map = Some(map_reference(tokens).ok_or_else(error)?),
+            // TODO ambient, alpha maps and illumination models
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+/// Options come before the file name, which may contain spaces
+fn map_reference(mut tokens: std::str::SplitWhitespace) -> Option<MapReference> {
+    let mut map = MapReference {
+        path: PathBuf::new(),
+        clamp: false,
+        scale: 1.0,
+    };
+    let mut name = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            "-bm" => map.scale = tokens.next()?.parse().ok()?,
+            "-clamp" => map.clamp = tokens.next()? == "on",
+            "-mm" => {
+                tokens.next()?;
+                tokens.next()?;
+            }
+            // Up to three values
+            "-o" | "-s" | "-t" => {
+                let mut lookahead = tokens.clone();
+                for _ in 0..3 {
+                    match lookahead.next() {
+                        Some(value) if value.parse::<f32>().is_ok() => {
+                            tokens.next();
+                        }
+                        _ => break,
+                    }
+                }
+            }
+            _ if token.starts_with('-') && name.is_empty() => {
+                tokens.next()?;
+            }
+            _ => name.push(token),
+        }
+    }
+    if name.is_empty() {
+        return None;
+    }
+    map.path = PathBuf::from(name.join(" ").replace('\\', "/"));
+    Some(map)
+}
+
+// OPTIMIZE cache the textures used by several materials
+fn load_texture(folder: &Path, map: &MapReference) -> Result<TextureData, AssetError> {
+    let image = load_dynamic_image(&folder.join(&map.path))?;
+    // Grayscale maps are common in MTL libraries
+    let image = match image {
+        DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => image,
+        image if image.color().has_alpha() => DynamicImage::ImageRgba8(image.to_rgba8()),
+        image => DynamicImage::ImageRgb8(image.to_rgb8()),
+    };
+    let mut texture_data = TextureData::new(Image::new(image));
+    if map.clamp {
+        texture_data.gl_s_wrapping = gl::CLAMP_TO_EDGE;
+        texture_data.gl_t_wrapping = gl::CLAMP_TO_EDGE;
+    }
+    Ok(texture_data)
+}
+
+fn load_dynamic_image(path: &Path) -> Result<DynamicImage, AssetError> {
+    let data = std::fs::read(path).map_err(|error| AssetError::Io(path.to_owned(), error))?;
+    Ok(image::load_from_memory(&data)?)
+}
+
+fn read(path: &Path) -> Result<String, AssetError> {
+    std::fs::read_to_string(path).map_err(|error| AssetError::Io(path.to_owned(), error))
+}
+
+/// Numbered lines without comments, joined when ending with a backslash
+fn lines(source: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (index, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let (number, mut joined) = pending.take().unwrap_or((index + 1, String::new()));
+        match line.trim_end().strip_suffix('\\') {
+            Some(line) => {
+                joined.push_str(line);
+                joined.push(' ');
+                pending = Some((number, joined));
+            }
+            None => {
+                joined.push_str(line);
+                lines.push((number, joined));
+            }
+        }
+    }
+    lines.extend(pending);
+    lines
+}
+
+/// The libraries of an `mtllib` line, one per name. The whole line is a single name with spaces
+/// only when the split names are not all found but it is
+fn mtllib(line: &str, folder: &Path) -> Vec<PathBuf> {
+    let whole = rest(line, "mtllib").replace('\\', "/");
+    let split: Vec<PathBuf> = whole
+        .split_whitespace()
+        .map(|name| folder.join(name))
+        .collect();
+    if !split.iter().all(|library| library.exists()) && folder.join(&whole).exists() {
+        return vec![folder.join(whole)];
+    }
+    split
+}
+
+/// Everything after the keyword, for names with spaces
+fn rest(line: &str, keyword: &str) -> String {
+    line.trim_start()[keyword.len()..].trim().to_owned()
+}
+
+fn floats(tokens: &mut std::str::SplitWhitespace) -> Option<[f32; 3]> {
+    let mut values = [0.0; 3];
+    for value in &mut values {
+        *value = tokens.next()?.parse().ok()?;
+    }
+    Some(values)
+}
+
+/// A single value is used for every channel
+fn color(tokens: &mut std::str::SplitWhitespace) -> Option<[f32; 3]> {
+    let r: f32 = tokens.next()?.parse().ok()?;
+    match (tokens.next(), tokens.next()) {
+        (Some(g), Some(b)) => Some([r, g.parse().ok()?, b.parse().ok()?]),
+        _ => Some([r, r, r]),
+    }
+}
+
+/// `v`, `v/vt`, `v//vn` or `v/vt/vn`, negative indices count back from the last element
+fn corner(
+    token: &str,
+    positions: &[[f32; 3]],
+    tex_coords: &[[f32; 2]],
+    normals: &[[f32; 3]],
+) -> Option<Corner> {
+    let resolve = |index: &str, count: usize| -> Option<usize> {
+        let index: isize = index.parse().ok()?;
+        let index = match index {
+            0 => return None,
+            index if index > 0 => index as usize - 1,
+            index => count.checked_sub(index.unsigned_abs())?,
+        };
+        (index < count).then_some(index)
+    };
+    let mut parts = token.split('/');
+    let position = resolve(parts.next()?, positions.len())?;
+    let tex_coord = match parts.next() {
+        None | Some("") => None,
+        Some(index) => Some(resolve(index, tex_coords.len())?),
+    };
+    let normal = match parts.next() {
+        None | Some("") => None,
+        Some(index) => Some(resolve(index, normals.len())?),
+    };
+    Some((position, tex_coord, normal))
+}
+
+fn decode_error(path: &Path, line: usize, why: String) -> AssetError {
+    AssetError::Decode(format!("{}:{}: {}", path.display(), line, why.trim()))
+}
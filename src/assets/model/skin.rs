@@ -0,0 +1,37 @@
+use super::node::Node;
+use cgmath::{Matrix4, SquareMatrix};
+use serde::{Deserialize, Serialize};
+
+/// Most joints a skinned mesh can be drawn with, see `MAX_JOINTS` in the vertex shaders
+pub const MAX_JOINTS: usize = 128;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Skin {
+    pub name: Option<String>,
+    /// Indices into `Model::nodes`, the `JOINTS_0` attribute indexes this list
+    pub joints: Vec<usize>,
+    /// One per joint, identity when the file has none
+    pub inverse_bind_matrices: Vec<Matrix4<f32>>,
+}
+
+impl Skin {
+    /// Joint world matrix times its inverse bind matrix, vertices skinned with them are in model space
+    /// so the world matrix of the skinned mesh node itself is ignored, as glTF requires
+    pub fn joint_matrices(&self, nodes: &[Node]) -> Vec<Matrix4<f32>> {
+        self.joints
+            .iter()
+            .enumerate()
+            .map(|(index, joint)| {
+                let world = nodes
+                    .get(*joint)
+                    .map_or(Matrix4::identity(), |node| node.world);
+                let inverse_bind = self
+                    .inverse_bind_matrices
+                    .get(index)
+                    .copied()
+                    .unwrap_or_else(Matrix4::identity);
+                world * inverse_bind
+            })
+            .collect()
+    }
+}
@@ -2,7 +2,8 @@ mod uniform;
 mod vertex_attribute;
 
 use self::{uniform::Uniform, vertex_attribute::VertexAttribute};
-use cgmath::Matrix4;
+use super::error::AssetError;
+use cgmath::{Matrix4, Vector3, Vector4};
 use gl::types::{GLchar, GLuint};
 use serde::{Deserialize, Serialize};
 
@@ -14,7 +15,7 @@ pub struct Program {
 }
 
 impl Program {
-    pub fn new(shaders_gl_ids: Vec<GLuint>) -> Self {
+    pub fn new(shaders_gl_ids: Vec<GLuint>) -> Result<Self, AssetError> {
         let gl_id = unsafe { gl::CreateProgram() };
 
         for shader_gl_id in shaders_gl_ids {
@@ -27,8 +28,10 @@ impl Program {
             gl::LinkProgram(gl_id);
         }
 
-        if gl_id <= 0 {
-            panic!("Failed to link shader program");
+        if gl_id == 0 {
+            return Err(AssetError::ProgramLink(
+                "The program id is invalid.".to_owned(),
+            ));
         }
 
         Self::verify_link(gl_id)
@@ -40,7 +43,7 @@ impl Program {
         }
     }
 
-    fn verify_link(gl_id: GLuint) -> Self {
+    fn verify_link(gl_id: GLuint) -> Result<Self, AssetError> {
         let mut success = 0;
         unsafe {
             gl::GetProgramiv(gl_id, gl::LINK_STATUS, &mut success);
@@ -60,17 +63,19 @@ impl Program {
                 );
                 log.set_len(log_length as usize);
             }
-            panic!(
-                "Failed to link shader program: {}",
-                String::from_utf8(log).expect("Shader program log is not valid UTF-8.")
-            );
+            unsafe {
+                gl::DeleteProgram(gl_id);
+            }
+            return Err(AssetError::ProgramLink(
+                String::from_utf8_lossy(&log).into_owned(),
+            ));
         }
 
-        Self {
+        Ok(Self {
             gl_id,
             uniforms: Uniform::get_all_uniforms(gl_id),
             vertex_attributes: vec![], // TODO VertexAttribute::get_all_vertex_attributes(gl_id),
-        }
+        })
     }
 
     pub fn set_uniform_int(&self, name: &str, value: i32) {
@@ -86,6 +91,43 @@ impl Program {
             uniform.set_mat4f32(value);
         }
     }
+
+    /// `name` is the array without its `[0]`, extra values past the array size are ignored by GL
+    pub fn set_uniform_mat4_array(&self, name: &str, values: &[Matrix4<f32>]) {
+        let name = format!("{}[0]", name);
+        // OPTIMIZE replace uniform ved by hashmap
+        if let Some(uniform) = self.uniforms.iter().find(|uniform| uniform.gl_name == name) {
+            uniform.set_mat4f32_array(values);
+        }
+    }
+
+    pub fn set_uniform_bool(&self, name: &str, value: bool) {
+        // OPTIMIZE replace uniform ved by hashmap
+        if let Some(uniform) = self.uniforms.iter().find(|uniform| uniform.gl_name == name) {
+            uniform.set_bool(value);
+        }
+    }
+
+    pub fn set_uniform_float(&self, name: &str, value: f32) {
+        // OPTIMIZE replace uniform ved by hashmap
+        if let Some(uniform) = self.uniforms.iter().find(|uniform| uniform.gl_name == name) {
+            uniform.set_float(value);
+        }
+    }
+
+    pub fn set_uniform_vec3(&self, name: &str, value: Vector3<f32>) {
+        // OPTIMIZE replace uniform ved by hashmap
+        if let Some(uniform) = self.uniforms.iter().find(|uniform| uniform.gl_name == name) {
+            uniform.set_vec3f32(value);
+        }
+    }
+
+    pub fn set_uniform_vec4(&self, name: &str, value: Vector4<f32>) {
+        // OPTIMIZE replace uniform ved by hashmap
+        if let Some(uniform) = self.uniforms.iter().find(|uniform| uniform.gl_name == name) {
+            uniform.set_uniform_vec4f32(value);
+        }
+    }
 }
 
 impl Drop for Program {
@@ -1,5 +1,5 @@
 use cgmath::{Matrix4, Point3, Vector3, Vector4};
-use gl::types::{GLchar, GLint, GLuint};
+use gl::types::{GLchar, GLint, GLsizei, GLuint};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -9,7 +9,7 @@ pub struct Uniform {
 }
 
 impl Uniform {
-    pub fn new(program_gl_id: GLuint, gl_location: GLuint) -> Self {
+    pub fn new(program_gl_id: GLuint, index: GLuint) -> Self {
         let mut name = [0 as GLchar; 256];
         let mut name_length = 0;
         let mut size = 0;
@@ -17,7 +17,7 @@ impl Uniform {
         unsafe {
             gl::GetActiveUniform(
                 program_gl_id,
-                gl_location,
+                index,
                 256,
                 &mut name_length,
                 &mut size,
@@ -26,12 +26,15 @@ impl Uniform {
             );
         }
 
+        // The index of an active uniform is not its location, struct members and arrays
+        // easily break that assumption
+        let location = unsafe { gl::GetUniformLocation(program_gl_id, name.as_ptr()) };
         Self {
             gl_name: unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) }
                 .to_str()
                 .expect("Failed to convert uniform name to str.")
                 .to_string(),
-            gl_location: gl_location as GLint,
+            gl_location: location,
         }
     }
 
@@ -95,4 +98,15 @@ impl Uniform {
             );
         }
     }
+
+    pub fn set_mat4f32_array(&self, values: &[Matrix4<f32>]) {
+        unsafe {
+            gl::UniformMatrix4fv(
+                self.gl_location,
+                values.len() as GLsizei,
+                gl::FALSE,
+                values.as_ptr() as *const f32,
+            );
+        }
+    }
 }
@@ -1,39 +1,49 @@
+use super::error::AssetError;
 use gl::types::{GLchar, GLuint};
-use std::{ffi::CString, fs::File, io::Read, path::PathBuf};
+use std::{ffi::CString, path::PathBuf};
 
 pub struct Shader {
     pub gl_id: GLuint,
 }
 
 impl Shader {
-    pub fn from_foreign(path: PathBuf, extension: &str) -> Self {
+    pub fn from_foreign(path: PathBuf, extension: &str) -> Result<Self, AssetError> {
         let shader_type = match extension.to_lowercase().as_str() {
             "vs" => gl::VERTEX_SHADER,
             "fs" => gl::FRAGMENT_SHADER,
-            _ => panic!("Unsupported shader extension: {}", extension),
+            _ => {
+                return Err(AssetError::UnsupportedFormat(format!(
+                    "shader extension '{}'",
+                    extension
+                )))
+            }
         };
 
-        let mut source = String::new();
-        let mut file = File::open(&path).expect("Failed to open shader file.");
-        file.read_to_string(&mut source)
-            .expect("Failed to read shader file.");
+        let source =
+            std::fs::read_to_string(&path).map_err(|error| AssetError::Io(path.clone(), error))?;
 
         let gl_id = unsafe { gl::CreateShader(shader_type) };
 
-        if gl_id <= 0 {
-            panic!("The shader id is invalid.");
+        if gl_id == 0 {
+            return Err(AssetError::ShaderCompile(
+                "The shader id is invalid.".to_owned(),
+            ));
         }
+        // From here on the shader is dropped, and deleted, on failure
+        let shader = Self { gl_id };
 
         // Compile shader
-        let source = CString::new(source).expect("Failed to convert source to CString.");
+        let source = CString::new(source).map_err(|_| {
+            AssetError::Decode(format!("Shader '{}' contains a nul byte.", path.display()))
+        })?;
         unsafe {
             gl::ShaderSource(gl_id, 1, &source.as_ptr(), std::ptr::null());
             gl::CompileShader(gl_id);
         }
 
-        verify_shader(gl_id, extension);
+        verify_shader(gl_id, &path)?;
 
-        Self { gl_id }
+        Ok(shader)
     }
 }
 
@@ -45,7 +55,7 @@ impl Drop for Shader {
     }
 }
 
-fn verify_shader(gl_id: GLuint, extension: &str) {
+fn verify_shader(gl_id: GLuint, path: &std::path::Path) -> Result<(), AssetError> {
     let mut success = 0;
     unsafe {
         gl::GetShaderiv(gl_id, gl::COMPILE_STATUS, &mut success);
@@ -65,7 +75,12 @@ fn verify_shader(gl_id: GLuint, extension: &str) {
             );
             log.set_len(log_length as usize);
         }
-        let why = String::from_utf8(log).expect("Failed to convert log to String.");
-        panic!("Failed to compile shader extension {}: {}", extension, why);
+        let why = String::from_utf8_lossy(&log);
+        return Err(AssetError::ShaderCompile(format!(
+            "{}: {}",
+            path.display(),
+            why
+        )));
     }
+    Ok(())
 }
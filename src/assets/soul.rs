@@ -0,0 +1,317 @@
+// .soul container, every file is a fixed header followed by the bincode payload
+//
+// offset  size  field
+//      0     4  magic "SOUL"
+//      4     2  container version
+//      6     2  flags, bit 0 set when the payload is deflate compressed
+//      8     4  asset type tag
+//     12     4  asset version
+//     16     8  payload length, as stored
+//     24     4  crc32 of the payload, as stored
+//     28     -  payload
+//
+// All integers are little endian
+use super::{
+    camera::Camera, error::AssetError, font::FontData, image::Image, mesh::MeshData,
+    model::ModelData, texture::TextureData,
+};
+use crate::cubemap::CubemapData;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{Read, Write};
+
+pub const MAGIC: [u8; 4] = *b"SOUL";
+pub const CONTAINER_VERSION: u16 = 1;
+pub const HEADER_LEN: usize = 28;
+
+const FLAG_DEFLATE: u16 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Deflate,
+}
+
+/// An asset that can be stored in a .soul file
+pub trait Soul: Serialize + DeserializeOwned {
+    /// Identifies the asset type, a file is only loaded as the type that wrote it
+    const TAG: [u8; 4];
+    /// Bump whenever the serialized layout changes, and handle the previous one in `migrate`
+    const VERSION: u32 = 1;
+
+    /// Upgrades a payload from `version` to `version + 1`, called once per step up to `VERSION`
+    fn migrate(version: u32, payload: Vec<u8>) -> Result<Vec<u8>, AssetError> {
+        let _ = payload;
+        Err(AssetError::Decode(format!(
+            "No migration for {} from version {}.",
+            tag_str(&Self::TAG),
+            version
+        )))
+    }
+}
+
+// Version 2 has the full pbr materials, skins and animations, version 3 morph targets and node
+// weights, version 4 the optional vertex attributes, version 5 the levels of detail, version 6
+// applies the tangent deltas of morph targets. Files before version 3 have to be cooked again
+impl Soul for ModelData {
+    const TAG: [u8; 4] = *b"MODL";
+    const VERSION: u32 = 6;
+
+    fn migrate(version: u32, payload: Vec<u8>) -> Result<Vec<u8>, AssetError> {
+        match version {
+            // Only the meshes changed, they lead the payload
+            3 => migrate_meshes(version, &payload),
+            // `lod_screen_sizes` was appended, after the `lods` of every mesh
+            4 => {
+                let mut payload = migrate_meshes(version, &payload)?;
+                payload.extend_from_slice(&0u64.to_le_bytes());
+                Ok(payload)
+            }
+            5 => migrate_meshes(version, &payload),
+            _ => Err(AssetError::Decode(format!(
+                "No migration for MODL from version {}, cook it again.",
+                version
+            ))),
+        }
+    }
+}
+impl Soul for MeshData {
+    const TAG: [u8; 4] = *b"MESH";
+    const VERSION: u32 = 6;
+
+    fn migrate(version: u32, mut payload: Vec<u8>) -> Result<Vec<u8>, AssetError> {
+        match version {
+            // `skin` then `morph_targets` were appended, an empty bincode vec is its u64 length only
+            1 | 2 => {
+                payload.extend_from_slice(&0u64.to_le_bytes());
+                Ok(payload)
+            }
+            // `attributes` was appended, three empty vecs
+            3 => {
+                payload.extend_from_slice(&[0; 24]);
+                Ok(payload)
+            }
+            // `lods` was appended
+            4 => {
+                payload.extend_from_slice(&0u64.to_le_bytes());
+                Ok(payload)
+            }
+            // The tangent deltas of morph targets were stored but ignored, they are applied now
+            5 => Ok(payload),
+            _ => Err(AssetError::Decode(format!(
+                "No migration for MESH from version {}.",
+                version
+            ))),
+        }
+    }
+}
+impl Soul for FontData {
+    const TAG: [u8; 4] = *b"FONT";
+}
+impl Soul for Image {
+    const TAG: [u8; 4] = *b"IMAG";
+}
+impl Soul for TextureData {
+    const TAG: [u8; 4] = *b"TXTR";
+}
+impl Soul for CubemapData {
+    const TAG: [u8; 4] = *b"CUBE";
+}
+impl Soul for Camera {
+    const TAG: [u8; 4] = *b"CAMR";
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub container_version: u16,
+    pub compression: Compression,
+    pub tag: [u8; 4],
+    pub version: u32,
+    pub payload_len: u64,
+    pub checksum: u32,
+}
+
+impl Header {
+    pub fn read(bytes: &[u8]) -> Result<Self, AssetError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(AssetError::Decode(format!(
+                "Soul file too short for a header, {} bytes.",
+                bytes.len()
+            )));
+        }
+        if bytes[0..4] != MAGIC {
+            return Err(AssetError::Decode("Not a soul file, bad magic.".to_owned()));
+        }
+        let container_version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if container_version > CONTAINER_VERSION {
+            return Err(AssetError::Decode(format!(
+                "Soul container version {} is newer than the supported {}.",
+                container_version, CONTAINER_VERSION
+            )));
+        }
+        let flags = u16::from_le_bytes([bytes[6], bytes[7]]);
+        let compression = if flags & FLAG_DEFLATE != 0 {
+            Compression::Deflate
+        } else {
+            Compression::None
+        };
+
+        Ok(Self {
+            container_version,
+            compression,
+            tag: [bytes[8], bytes[9], bytes[10], bytes[11]],
+            version: u32::from_le_bytes(bytes[12..16].try_into().unwrap()),
+            payload_len: u64::from_le_bytes(bytes[16..24].try_into().unwrap()),
+            checksum: u32::from_le_bytes(bytes[24..28].try_into().unwrap()),
+        })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        let flags = match self.compression {
+            Compression::None => 0,
+            Compression::Deflate => FLAG_DEFLATE,
+        };
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&self.container_version.to_le_bytes());
+        bytes.extend_from_slice(&flags.to_le_bytes());
+        bytes.extend_from_slice(&self.tag);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&self.payload_len.to_le_bytes());
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+    }
+}
+
+pub fn encode<T: Soul>(data: &T, compression: Compression) -> Result<Vec<u8>, AssetError> {
+    let mut payload = bincode::serialize(data)?;
+    if compression == Compression::Deflate {
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(&payload)
+            .and_then(|_| encoder.finish())
+            .map(|compressed| payload = compressed)
+            .map_err(|error| AssetError::Decode(format!("Deflate failed: {}", error)))?;
+    }
+
+    let header = Header {
+        container_version: CONTAINER_VERSION,
+        compression,
+        tag: T::TAG,
+        version: T::VERSION,
+        payload_len: payload.len() as u64,
+        checksum: crc32fast::hash(&payload),
+    };
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    header.write(&mut bytes);
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+pub fn decode<T: Soul>(bytes: &[u8]) -> Result<T, AssetError> {
+    let header = Header::read(bytes)?;
+    if header.tag != T::TAG {
+        return Err(AssetError::Decode(format!(
+            "Soul file holds a {}, expected a {}.",
+            tag_str(&header.tag),
+            tag_str(&T::TAG)
+        )));
+    }
+    if header.version > T::VERSION {
+        return Err(AssetError::Decode(format!(
+            "{} version {} is newer than the supported {}.",
+            tag_str(&T::TAG),
+            header.version,
+            T::VERSION
+        )));
+    }
+
+    let stored = &bytes[HEADER_LEN..];
+    if stored.len() as u64 != header.payload_len {
+        return Err(AssetError::Decode(format!(
+            "Soul payload is {} bytes, header says {}.",
+            stored.len(),
+            header.payload_len
+        )));
+    }
+    if crc32fast::hash(stored) != header.checksum {
+        return Err(AssetError::Decode(
+            "Soul payload checksum mismatch.".to_owned(),
+        ));
+    }
+
+    let mut payload = match header.compression {
+        Compression::None => stored.to_vec(),
+        Compression::Deflate => {
+            let mut payload = Vec::new();
+            DeflateDecoder::new(stored)
+                .read_to_end(&mut payload)
+                .map_err(|error| AssetError::Decode(format!("Inflate failed: {}", error)))?;
+            payload
+        }
+    };
+    for version in header.version..T::VERSION {
+        payload = T::migrate(version, payload)?;
+    }
+
+    Ok(bincode::deserialize(&payload)?)
+}
+
+/// Runs `MeshData::migrate` on every mesh of a model payload, the bytes after them are kept
+fn migrate_meshes(version: u32, payload: &[u8]) -> Result<Vec<u8>, AssetError> {
+    // Frozen copies of the old layouts, bincode writes a struct as its fields in order and an
+    // enum as its u32 variant index, so tuples of plain types read an old mesh whatever the
+    // current types become
+    type Vertex = ([f32; 3], [f32; 3], [f32; 2]);
+    type VertexSkin = ([u16; 4], [f32; 4]);
+    type MorphTarget = (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 3]>);
+    type VertexAttributes = (Vec<[f32; 4]>, Vec<[f32; 4]>, Vec<Vec<[f32; 2]>>);
+    type Lod = (Vec<u32>, f32);
+    // gl_mode, vertices, indices, draw_type, skin, morph_targets
+    type MeshV3 = (
+        u32,
+        Vec<Vertex>,
+        Vec<u32>,
+        u32,
+        Vec<VertexSkin>,
+        Vec<MorphTarget>,
+    );
+    type MeshV4 = (
+        u32,
+        Vec<Vertex>,
+        Vec<u32>,
+        u32,
+        Vec<VertexSkin>,
+        Vec<MorphTarget>,
+        VertexAttributes,
+    );
+    type MeshV5 = (
+        u32,
+        Vec<Vertex>,
+        Vec<u32>,
+        u32,
+        Vec<VertexSkin>,
+        Vec<MorphTarget>,
+        VertexAttributes,
+        Vec<Lod>,
+    );
+
+    let mut rest = payload;
+    let count: u64 = bincode::deserialize_from(&mut rest)?;
+    let mut migrated = count.to_le_bytes().to_vec();
+    for _ in 0..count {
+        let before = rest;
+        match version {
+            3 => bincode::deserialize_from::<_, MeshV3>(&mut rest).map(drop)?,
+            4 => bincode::deserialize_from::<_, MeshV4>(&mut rest).map(drop)?,
+            5 => bincode::deserialize_from::<_, MeshV5>(&mut rest).map(drop)?,
+            _ => unreachable!("Only called for the versions above."),
+        }
+        let mesh = before[..before.len() - rest.len()].to_vec();
+        migrated.extend(MeshData::migrate(version, mesh)?);
+    }
+    migrated.extend_from_slice(rest);
+    Ok(migrated)
+}
+
+fn tag_str(tag: &[u8; 4]) -> String {
+    String::from_utf8_lossy(tag).into_owned()
+}
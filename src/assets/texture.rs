@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 // TODO remove debug everywhere
 #[non_exhaustive]
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Debug)]
 pub struct Texture {
     gl_id: GLuint,
     image: Image,
@@ -16,6 +16,18 @@ pub struct Texture {
     mipmapping: bool,
 }
 
+/// CPU side of a `Texture`, no GL involved until `upload`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TextureData {
+    pub image: Image,
+    pub kind: TextureKind,
+    pub gl_s_wrapping: GLenum,
+    pub gl_t_wrapping: GLenum,
+    pub gl_min_filtering: GLenum,
+    pub gl_mag_filtering: GLenum,
+    pub mipmapping: bool,
+}
+
 // TODO remove debug everywhere
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum TextureKind {
@@ -27,11 +39,9 @@ pub enum TextureKind {
     Ambient,
 }
 
-impl Texture {
-    // TODO configurable tex options
+impl TextureData {
     pub fn new(image: Image) -> Self {
-        let mut texture = Self {
-            gl_id: 0,
+        Self {
             image,
             kind: TextureKind::Diffuse,
             gl_s_wrapping: gl::CLAMP_TO_EDGE,
@@ -39,10 +49,47 @@ impl Texture {
             gl_min_filtering: gl::LINEAR_MIPMAP_LINEAR,
             gl_mag_filtering: gl::LINEAR,
             mipmapping: true,
+        }
+    }
+
+    pub fn upload(self) -> Texture {
+        let mut texture = Texture {
+            gl_id: 0,
+            image: self.image,
+            kind: self.kind,
+            gl_s_wrapping: self.gl_s_wrapping,
+            gl_t_wrapping: self.gl_t_wrapping,
+            gl_min_filtering: self.gl_min_filtering,
+            gl_mag_filtering: self.gl_mag_filtering,
+            mipmapping: self.mipmapping,
         };
         texture.gl_register();
         texture
     }
+}
+
+impl Texture {
+    // TODO configurable tex options
+    pub fn new(image: Image) -> Self {
+        TextureData::new(image).upload()
+    }
+
+    pub fn kind(&self) -> &TextureKind {
+        &self.kind
+    }
+
+    /// Copy of the CPU side kept since the upload
+    pub fn to_data(&self) -> TextureData {
+        TextureData {
+            image: self.image.clone(),
+            kind: self.kind.clone(),
+            gl_s_wrapping: self.gl_s_wrapping,
+            gl_t_wrapping: self.gl_t_wrapping,
+            gl_min_filtering: self.gl_min_filtering,
+            gl_mag_filtering: self.gl_mag_filtering,
+            mipmapping: self.mipmapping,
+        }
+    }
 
     pub fn gl_unbind(&self) {
         unsafe {
@@ -56,8 +103,9 @@ impl Texture {
     // TODO deal with max amount of texture units
     pub fn gl_bind(&self, bind_position: GLuint) {
         unsafe {
-            gl::BindTexture(self.image.gl_target, self.gl_id);
+            // The unit must be active before binding, or the texture lands on the previous one
             gl::ActiveTexture(gl::TEXTURE0 + bind_position);
+            gl::BindTexture(self.image.gl_target, self.gl_id);
         }
     }
 
@@ -136,3 +184,11 @@ impl Texture {
         self.gl_unbind();
     }
 }
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.gl_id);
+        }
+    }
+}
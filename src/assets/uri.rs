@@ -0,0 +1,103 @@
+// URIs as glTF uses them (RFC 3986): data URIs, references relative to the file declaring them
+// and absolute file URLs
+use super::error::AssetError;
+use base64::{engine::general_purpose, Engine};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resource {
+    /// Content embedded in the URI, the media type is empty when not given
+    Data {
+        mime_type: String,
+        bytes: Vec<u8>,
+    },
+    File(PathBuf),
+}
+
+impl Resource {
+    pub fn read(self) -> Result<Vec<u8>, AssetError> {
+        match self {
+            Resource::Data { bytes, .. } => Ok(bytes),
+            Resource::File(path) => {
+                std::fs::read(&path).map_err(|error| AssetError::Io(path, error))
+            }
+        }
+    }
+}
+
+/// `folder` is the one of the file declaring the URI, relative references are resolved against it
+pub fn resolve(uri: &str, folder: &Path) -> Result<Resource, AssetError> {
+    let invalid =
+        |why: &dyn std::fmt::Display| AssetError::Decode(format!("URI '{}': {}", uri, why));
+    if let Some(data) = strip_scheme(uri, "data") {
+        return decode_data(data).ok_or_else(|| invalid(&"malformed data URI"));
+    }
+
+    let url = match Url::parse(uri) {
+        // Single letters are Windows drives, not schemes
+        Ok(url) if url.scheme().len() > 1 => url,
+        Ok(_) => return Ok(Resource::File(PathBuf::from(uri))),
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            let folder = std::path::absolute(folder)
+                .map_err(|error| AssetError::Io(folder.to_owned(), error))?;
+            let base = Url::from_directory_path(&folder)
+                .map_err(|_| invalid(&format!("no base URL for '{}'", folder.display())))?;
+            base.join(uri).map_err(|error| invalid(&error))?
+        }
+        Err(error) => return Err(invalid(&error)),
+    };
+    if url.scheme() != "file" {
+        return Err(AssetError::UnsupportedFormat(format!(
+            "URI scheme '{}'",
+            url.scheme()
+        )));
+    }
+    // Percent decoded on the way
+    url.to_file_path()
+        .map(Resource::File)
+        .map_err(|_| invalid(&"not a file path"))
+}
+
+pub fn read(uri: &str, folder: &Path) -> Result<Vec<u8>, AssetError> {
+    resolve(uri, folder)?.read()
+}
+
+fn strip_scheme<'a>(uri: &'a str, scheme: &str) -> Option<&'a str> {
+    let (prefix, rest) = uri.split_once(':')?;
+    prefix.eq_ignore_ascii_case(scheme).then_some(rest)
+}
+
+/// `[<media type>][;<parameter>]*[;base64],<data>`
+fn decode_data(data: &str) -> Option<Resource> {
+    let (header, payload) = data.split_once(',')?;
+    let mut parameters = header.split(';');
+    let mime_type = parameters.next().unwrap_or_default().trim().to_lowercase();
+    let base64 = parameters.any(|parameter| parameter.trim().eq_ignore_ascii_case("base64"));
+    let bytes = if base64 {
+        // Whitespace and escapes are allowed around the payload
+        let payload: Vec<u8> = percent_decode(payload)?
+            .into_iter()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .collect();
+        general_purpose::STANDARD.decode(payload).ok()?
+    } else {
+        percent_decode(payload)?
+    };
+    Some(Resource::Data { mime_type, bytes })
+}
+
+fn percent_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+    while let Some(byte) = iter.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+        let high = (iter.next()? as char).to_digit(16)?;
+        let low = (iter.next()? as char).to_digit(16)?;
+        bytes.push((high * 16 + low) as u8);
+    }
+    Some(bytes)
+}
@@ -0,0 +1,114 @@
+use super::{
+    handle::Handle, image::Image, model::Model, program::Program, shader::Shader, texture::Texture,
+};
+use crate::cubemap::Cubemap;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How to rebuild an asset when one of its files changes on disk
+#[derive(Clone, PartialEq)]
+pub(crate) enum Source {
+    Image(Handle<Image>, PathBuf, String),
+    Texture(Handle<Texture>, PathBuf, String),
+    Shader(Handle<Shader>, PathBuf, String),
+    Model(Handle<Model>, PathBuf),
+    Cubemap(Handle<Cubemap>, Vec<(PathBuf, String)>),
+}
+
+impl Source {
+    /// Files the asset was loaded from, without the ones a model references
+    pub(crate) fn paths(&self) -> Vec<PathBuf> {
+        match self {
+            Source::Image(_, path, _)
+            | Source::Texture(_, path, _)
+            | Source::Shader(_, path, _)
+            | Source::Model(_, path) => vec![path.clone()],
+            Source::Cubemap(_, faces) => faces.iter().map(|(path, _)| path.clone()).collect(),
+        }
+    }
+}
+
+struct Watched {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    source: Source,
+}
+
+/// Polls the modification time of every file an asset was loaded from
+pub(crate) struct Watcher {
+    watched: Vec<Watched>,
+    /// Shaders each program was linked from, to relink it when one of them changes
+    pub(crate) programs: Vec<(Handle<Program>, Vec<Handle<Shader>>)>,
+    pub(crate) interval: Duration,
+    last_poll: Instant,
+}
+
+impl Watcher {
+    pub(crate) fn new() -> Self {
+        Self {
+            watched: Vec::new(),
+            programs: Vec::new(),
+            interval: Duration::from_millis(500),
+            last_poll: Instant::now(),
+        }
+    }
+
+    pub(crate) fn watch(&mut self, path: &Path, source: Source) {
+        self.watched
+            .retain(|watched| watched.path != path || watched.source != source);
+        self.watched.push(Watched {
+            path: path.to_owned(),
+            modified: modified(path),
+            source,
+        });
+    }
+
+    /// Replaces every file watched for `source` by its own and `dependencies`, the files a model
+    /// references as listed when it was imported
+    pub(crate) fn watch_source(&mut self, source: &Source, dependencies: &[PathBuf]) {
+        self.unwatch(source);
+        for path in source.paths().iter().chain(dependencies) {
+            self.watch(path, source.clone());
+        }
+    }
+
+    pub(crate) fn unwatch(&mut self, source: &Source) {
+        self.watched.retain(|watched| watched.source != *source);
+    }
+
+    pub(crate) fn watch_program(&mut self, program: Handle<Program>, shaders: &[Handle<Shader>]) {
+        self.programs.retain(|(handle, _)| *handle != program);
+        self.programs.push((program, shaders.to_vec()));
+    }
+
+    /// Sources with at least one file modified since the last poll, at most once per interval
+    pub(crate) fn poll(&mut self) -> Vec<Source> {
+        let mut sources = Vec::new();
+        if self.last_poll.elapsed() < self.interval {
+            return sources;
+        }
+        self.last_poll = Instant::now();
+
+        for watched in &mut self.watched {
+            // A missing file is most likely being saved, keep the old time and retry next poll
+            let Some(modified) = modified(&watched.path) else {
+                continue;
+            };
+            if watched.modified != Some(modified) {
+                watched.modified = Some(modified);
+                if !sources.contains(&watched.source) {
+                    sources.push(watched.source.clone());
+                }
+            }
+        }
+        sources
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -0,0 +1,280 @@
+// Cooks every glTF, image and TTF of assets/foreign into .soul files under assets/soul
+// Inputs whose content hash matches the manifest are skipped, no GL context is created
+//
+// Usage: revenant-cook [assets folder] [--force] [--deflate]
+use revenant::assets::{
+    error::AssetError,
+    font::FontData,
+    image::Image,
+    mesh::lod::LodSettings,
+    model::ModelData,
+    soul::{self, Compression, Soul},
+    write_file,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    env,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+const FOREIGN_FOLDER: &str = "foreign";
+const NATIVE_FOLDER: &str = "soul";
+const NATIVE_EXT: &str = "soul";
+const MANIFEST: &str = "manifest.json";
+
+#[derive(Serialize, Deserialize, Default)]
+struct Manifest {
+    /// Keyed by the input path relative to the foreign folder
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone)]
+struct Entry {
+    /// Sha256 of the input file and of every file it references
+    hash: String,
+    tag: String,
+    version: u32,
+    compression: bool,
+    /// Relative to the soul folder, without extension
+    output: String,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Model,
+    Image,
+    Font,
+}
+
+impl Kind {
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "gltf" | "glb" | "obj" => Some(Kind::Model),
+            "png" | "jpg" | "jpeg" => Some(Kind::Image),
+            "ttf" => Some(Kind::Font),
+            _ => None,
+        }
+    }
+
+    fn folder(&self) -> &'static str {
+        match self {
+            Kind::Model => "models",
+            Kind::Image => "images",
+            Kind::Font => "fonts",
+        }
+    }
+
+    fn tag_and_version(&self) -> ([u8; 4], u32) {
+        match self {
+            Kind::Model => (ModelData::TAG, ModelData::VERSION),
+            Kind::Image => (Image::TAG, Image::VERSION),
+            Kind::Font => (FontData::TAG, FontData::VERSION),
+        }
+    }
+
+    fn cook(
+        &self,
+        path: &Path,
+        extension: &str,
+        compression: Compression,
+    ) -> Result<Vec<u8>, AssetError> {
+        match self {
+            Kind::Model => {
+                let mut model = ModelData::from_foreign(path.to_owned())?;
+                let stats = model.optimize();
+                println!(
+                    "Optimized {}: ACMR {:.3} -> {:.3}, ATVR {:.3} -> {:.3}, {} -> {} vertices",
+                    path.display(),
+                    stats.before.acmr(),
+                    stats.after.acmr(),
+                    stats.before.atvr(),
+                    stats.after.atvr(),
+                    stats.before.vertices,
+                    stats.after.vertices
+                );
+                model.generate_lods(&LodSettings::default());
+                let levels = model.meshes.iter().map(|mesh| mesh.lods.len()).max();
+                println!(
+                    "Simplified {}: {} levels of detail",
+                    path.display(),
+                    levels.unwrap_or(0)
+                );
+                soul::encode(&model, compression)
+            }
+            Kind::Image => {
+                soul::encode(&Image::from_file(path.to_owned(), extension)?, compression)
+            }
+            Kind::Font => soul::encode(&FontData::from_ttf(path.to_owned())?, compression),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut assets = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets");
+    let mut force = false;
+    let mut compression = Compression::None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--force" => force = true,
+            "--deflate" => compression = Compression::Deflate,
+            _ if arg.starts_with("--") => {
+                eprintln!("Unknown option {}", arg);
+                eprintln!("Usage: revenant-cook [assets folder] [--force] [--deflate]");
+                return ExitCode::FAILURE;
+            }
+            _ => assets = PathBuf::from(arg),
+        }
+    }
+
+    match cook_all(&assets, force, compression) {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Returns the amount of inputs that failed to cook
+fn cook_all(assets: &Path, force: bool, compression: Compression) -> Result<usize, AssetError> {
+    let foreign = assets.join(FOREIGN_FOLDER);
+    let native = assets.join(NATIVE_FOLDER);
+    let manifest_path = native.join(MANIFEST);
+
+    let mut manifest = match std::fs::read(&manifest_path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|error| {
+            eprintln!("Ignoring unreadable manifest: {}", error);
+            Manifest::default()
+        }),
+        Err(_) => Manifest::default(),
+    };
+
+    let mut inputs = Vec::new();
+    collect_files(&foreign, &mut inputs)?;
+    inputs.sort();
+
+    let (mut cooked, mut up_to_date, mut failed) = (0, 0, 0);
+    let mut entries = BTreeMap::new();
+    // Outputs drop the extension, `cube.gltf` and `cube.glb` would overwrite each other
+    let mut claimed: BTreeMap<String, String> = BTreeMap::new();
+    for path in inputs {
+        let Some(extension) = path.extension().and_then(|ext| ext.to_str()) else {
+            continue;
+        };
+        let Some(kind) = Kind::from_extension(extension) else {
+            continue;
+        };
+        let relative = path
+            .strip_prefix(&foreign)
+            .expect("Collected under the foreign folder.")
+            .with_extension("");
+        let key = path
+            .strip_prefix(&foreign)
+            .expect("Collected under the foreign folder.")
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        let (tag, version) = kind.tag_and_version();
+        let entry = Entry {
+            hash: hash(&path, kind)?,
+            tag: String::from_utf8_lossy(&tag).into_owned(),
+            version,
+            compression: compression == Compression::Deflate,
+            output: Path::new(kind.folder())
+                .join(relative)
+                .to_string_lossy()
+                .replace('\\', "/"),
+        };
+        let output = native.join(&entry.output).with_extension(NATIVE_EXT);
+        if let Some(other) = claimed.get(&entry.output) {
+            eprintln!(
+                "Failed to cook {}: {} already cooks to {}",
+                key,
+                other,
+                output.display()
+            );
+            failed += 1;
+            continue;
+        }
+        claimed.insert(entry.output.clone(), key.clone());
+
+        if !force && manifest.entries.get(&key) == Some(&entry) && output.exists() {
+            up_to_date += 1;
+            entries.insert(key, entry);
+            continue;
+        }
+
+        match kind.cook(&path, extension, compression) {
+            Ok(encoded) => {
+                write_file(output.clone(), &encoded)?;
+                println!("Cooked {} -> {}", key, output.display());
+                cooked += 1;
+                entries.insert(key, entry);
+            }
+            Err(error) => {
+                eprintln!("Failed to cook {}: {}", key, error);
+                failed += 1;
+            }
+        }
+    }
+
+    // Inputs that disappeared take their cooked file with them
+    for (key, entry) in &manifest.entries {
+        if !entries.contains_key(key) && !foreign.join(key).exists() {
+            let output = native.join(&entry.output).with_extension(NATIVE_EXT);
+            if std::fs::remove_file(&output).is_ok() {
+                println!("Removed {}", output.display());
+            }
+        }
+    }
+
+    manifest.entries = entries;
+    write_file(
+        manifest_path,
+        serde_json::to_string_pretty(&manifest)?.as_bytes(),
+    )?;
+    println!(
+        "{} cooked, {} up to date, {} failed",
+        cooked, up_to_date, failed
+    );
+
+    Ok(failed)
+}
+
+/// A model changes with its buffers, images and material libraries too
+fn hash(path: &Path, kind: Kind) -> Result<String, AssetError> {
+    let mut hasher = Sha256::new();
+    hasher.update(std::fs::read(path).map_err(|error| AssetError::Io(path.to_owned(), error))?);
+    if let Kind::Model = kind {
+        let folder = path.parent().unwrap_or(Path::new(""));
+        // A broken model fails to cook anyway, its dependencies do not matter
+        for dependency in ModelData::dependencies(path).unwrap_or_default() {
+            // Relative to the model, the same on every checkout
+            let relative = dependency.strip_prefix(folder).unwrap_or(&dependency);
+            hasher.update(relative.to_string_lossy().replace('\\', "/").as_bytes());
+            // Missing files hash as empty, cooking reports them
+            hasher.update(std::fs::read(&dependency).unwrap_or_default());
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn collect_files(folder: &Path, files: &mut Vec<PathBuf>) -> Result<(), AssetError> {
+    let read_dir =
+        std::fs::read_dir(folder).map_err(|error| AssetError::Io(folder.to_owned(), error))?;
+    for dir_entry in read_dir {
+        let path = dir_entry
+            .map_err(|error| AssetError::Io(folder.to_owned(), error))?
+            .path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
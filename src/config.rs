@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RevenantConfig {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+    pub window_mode: WindowMode,
+    /// Index in the connected monitors list, 0 being the primary monitor
+    pub monitor: usize,
+    pub vsync: VsyncMode,
+    pub msaa_samples: Option<u32>,
+    pub gl_version: (u32, u32),
+    pub gl_profile: GlProfile,
+    pub cursor_mode: CursorMode,
+    pub position: WindowPosition,
+    /// Creates an invisible window, rendering should target a `Framebuffer`
+    pub headless: bool,
+    pub context_api: ContextApi,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowMode {
+    Windowed,
+    Fullscreen,
+    Borderless,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VsyncMode {
+    Off,
+    On,
+    Adaptive,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlProfile {
+    Core,
+    Compat,
+    Any,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMode {
+    Normal,
+    Hidden,
+    Disabled,
+}
+
+/// `Egl` and `OsMesa` allow software rendering through Mesa (llvmpipe). GLFW still opens its
+/// window on an X or Wayland display, GPU-less machines need one such as Xvfb
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextApi {
+    Native,
+    Egl,
+    OsMesa,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowPosition {
+    /// Centered on the selected monitor current video mode
+    Centered,
+    /// Offset from the selected monitor top left corner
+    At(i32, i32),
+}
+
+impl RevenantConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_owned();
+        self
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn min_size(mut self, width: u32, height: u32) -> Self {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    pub fn max_size(mut self, width: u32, height: u32) -> Self {
+        self.max_size = Some((width, height));
+        self
+    }
+
+    pub fn window_mode(mut self, window_mode: WindowMode) -> Self {
+        self.window_mode = window_mode;
+        self
+    }
+
+    pub fn monitor(mut self, monitor: usize) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    pub fn vsync(mut self, vsync: VsyncMode) -> Self {
+        self.vsync = vsync;
+        self
+    }
+
+    pub fn msaa_samples(mut self, samples: Option<u32>) -> Self {
+        self.msaa_samples = samples;
+        self
+    }
+
+    pub fn gl_version(mut self, major: u32, minor: u32) -> Self {
+        self.gl_version = (major, minor);
+        self
+    }
+
+    pub fn gl_profile(mut self, gl_profile: GlProfile) -> Self {
+        self.gl_profile = gl_profile;
+        self
+    }
+
+    pub fn cursor_mode(mut self, cursor_mode: CursorMode) -> Self {
+        self.cursor_mode = cursor_mode;
+        self
+    }
+
+    pub fn position(mut self, position: WindowPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.headless = headless;
+        self
+    }
+
+    pub fn context_api(mut self, context_api: ContextApi) -> Self {
+        self.context_api = context_api;
+        self
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let serialized = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&serialized)?)
+    }
+
+    /// Falls back to the default settings if the file is missing or invalid
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Self {
+        Self::load(path).unwrap_or_default()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, serialized)?;
+        Ok(())
+    }
+}
+
+impl Default for RevenantConfig {
+    fn default() -> Self {
+        Self {
+            title: env!("CARGO_PKG_NAME").to_owned(),
+            width: 1600,
+            height: 900,
+            min_size: None,
+            max_size: None,
+            window_mode: WindowMode::Windowed,
+            monitor: 0,
+            vsync: VsyncMode::Off,
+            msaa_samples: None,
+            gl_version: (4, 6),
+            gl_profile: GlProfile::Core,
+            cursor_mode: CursorMode::Disabled,
+            position: WindowPosition::Centered,
+            headless: false,
+            context_api: ContextApi::Native,
+        }
+    }
+}
+
+impl From<VsyncMode> for glfw::SwapInterval {
+    fn from(vsync: VsyncMode) -> Self {
+        match vsync {
+            VsyncMode::Off => glfw::SwapInterval::None,
+            VsyncMode::On => glfw::SwapInterval::Sync(1),
+            VsyncMode::Adaptive => glfw::SwapInterval::Adaptive,
+        }
+    }
+}
+
+impl From<GlProfile> for glfw::OpenGlProfileHint {
+    fn from(gl_profile: GlProfile) -> Self {
+        match gl_profile {
+            GlProfile::Core => glfw::OpenGlProfileHint::Core,
+            GlProfile::Compat => glfw::OpenGlProfileHint::Compat,
+            GlProfile::Any => glfw::OpenGlProfileHint::Any,
+        }
+    }
+}
+
+impl From<ContextApi> for glfw::ContextCreationApi {
+    fn from(context_api: ContextApi) -> Self {
+        match context_api {
+            ContextApi::Native => glfw::ContextCreationApi::Native,
+            ContextApi::Egl => glfw::ContextCreationApi::Egl,
+            ContextApi::OsMesa => glfw::ContextCreationApi::OsMesa,
+        }
+    }
+}
+
+impl From<CursorMode> for glfw::CursorMode {
+    fn from(cursor_mode: CursorMode) -> Self {
+        match cursor_mode {
+            CursorMode::Normal => glfw::CursorMode::Normal,
+            CursorMode::Hidden => glfw::CursorMode::Hidden,
+            CursorMode::Disabled => glfw::CursorMode::Disabled,
+        }
+    }
+}
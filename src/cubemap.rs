@@ -1,6 +1,70 @@
 use gl::types::{GLenum, GLint, GLuint, GLvoid};
 
 use crate::assets::image::Image;
+use serde::{Deserialize, Serialize};
+
+/// CPU side of a `Cubemap`, no GL involved until `upload`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CubemapData {
+    pub images_pos_x: Image,
+    pub images_neg_x: Image,
+    pub images_pos_y: Image,
+    pub images_neg_y: Image,
+    pub images_pos_z: Image,
+    pub images_neg_z: Image,
+    pub gl_s_wrapping: GLenum,
+    pub gl_t_wrapping: GLenum,
+    pub gl_r_wrapping: GLenum,
+    pub gl_min_filtering: GLenum,
+    pub gl_mag_filtering: GLenum,
+    pub mipmapping: bool,
+}
+
+impl CubemapData {
+    pub fn from_images(
+        images_pos_x: Image,
+        images_neg_x: Image,
+        images_pos_y: Image,
+        images_neg_y: Image,
+        images_pos_z: Image,
+        images_neg_z: Image,
+    ) -> Self {
+        Self {
+            images_pos_x,
+            images_neg_x,
+            images_pos_y,
+            images_neg_y,
+            images_pos_z,
+            images_neg_z,
+            gl_s_wrapping: gl::CLAMP_TO_EDGE,
+            gl_t_wrapping: gl::CLAMP_TO_EDGE,
+            gl_r_wrapping: gl::CLAMP_TO_EDGE,
+            gl_min_filtering: gl::LINEAR_MIPMAP_LINEAR,
+            gl_mag_filtering: gl::LINEAR,
+            mipmapping: true,
+        }
+    }
+
+    pub fn upload(self) -> Cubemap {
+        let mut cubemap = Cubemap {
+            gl_id: 0,
+            images_pos_x: self.images_pos_x,
+            images_neg_x: self.images_neg_x,
+            images_pos_y: self.images_pos_y,
+            images_neg_y: self.images_neg_y,
+            images_pos_z: self.images_pos_z,
+            images_neg_z: self.images_neg_z,
+            gl_s_wrapping: self.gl_s_wrapping,
+            gl_t_wrapping: self.gl_t_wrapping,
+            gl_r_wrapping: self.gl_r_wrapping,
+            gl_min_filtering: self.gl_min_filtering,
+            gl_mag_filtering: self.gl_mag_filtering,
+            mipmapping: self.mipmapping,
+        };
+        cubemap.gl_register();
+        cubemap
+    }
+}
 
 pub struct Cubemap {
     gl_id: GLuint,
@@ -27,23 +91,15 @@ impl Cubemap {
         images_pos_z: Image,
         images_neg_z: Image,
     ) -> Self {
-        let mut texture = Self {
-            gl_id: 0,
+        CubemapData::from_images(
             images_pos_x,
             images_neg_x,
             images_pos_y,
             images_neg_y,
             images_pos_z,
             images_neg_z,
-            gl_s_wrapping: gl::CLAMP_TO_EDGE,
-            gl_t_wrapping: gl::CLAMP_TO_EDGE,
-            gl_r_wrapping: gl::CLAMP_TO_EDGE,
-            gl_min_filtering: gl::LINEAR_MIPMAP_LINEAR,
-            gl_mag_filtering: gl::LINEAR,
-            mipmapping: true,
-        };
-        texture.gl_register();
-        texture
+        )
+        .upload()
     }
 
     pub fn gl_unbind(&self) {
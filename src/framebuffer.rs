@@ -1,12 +1,16 @@
 pub(crate) mod renderbuffer;
 pub(crate) mod texturebuffer;
 use self::{renderbuffer::Renderbuffer, texturebuffer::TextureFramebuffer};
-use gl::types::GLuint;
+use crate::assets::{error::AssetError, image::Image};
+use gl::types::{GLenum, GLsizei, GLuint, GLvoid};
 
 pub struct Framebuffer {
     pub(crate) gl_id: GLuint,
     pub gl_texturebuffer_id: GLuint,
     pub gl_renderbuffer_id: GLuint,
+    pub(crate) gl_texturebuffer_format: GLenum,
+    pub(crate) width: GLsizei,
+    pub(crate) height: GLsizei,
 }
 
 impl Framebuffer {
@@ -15,6 +19,9 @@ impl Framebuffer {
             gl_id: 0,
             gl_texturebuffer_id: 0,
             gl_renderbuffer_id: 0,
+            gl_texturebuffer_format: gl::RGBA,
+            width: 0,
+            height: 0,
         };
 
         unsafe {
@@ -42,6 +49,9 @@ impl Framebuffer {
         self.integrity_check();
 
         self.gl_texturebuffer_id = texture.gl_id;
+        self.gl_texturebuffer_format = texture.gl_format;
+        self.width = texture.width;
+        self.height = texture.height;
     }
 
     pub fn gl_attach_renderbuffer(&mut self, renderbuffer: Renderbuffer) {
@@ -74,6 +84,19 @@ impl Framebuffer {
         }
     }
 
+    /// Reads back the color attachment, rows are flipped to a top-left origin
+    pub fn read_pixels(&self) -> Result<Image, AssetError> {
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.gl_id);
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+        }
+        let image = gl_read_pixels(self.width, self.height, self.gl_texturebuffer_format);
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+        image
+    }
+
     #[inline]
     #[cfg(feature = "pillow")]
     pub fn integrity_check(&self) {
@@ -106,6 +129,51 @@ impl Framebuffer {
     }
 }
 
+/// Reads the currently bound read framebuffer, OpenGL rows start at the bottom-left
+pub(crate) fn gl_read_pixels(
+    width: GLsizei,
+    height: GLsizei,
+    gl_format: GLenum,
+) -> Result<Image, AssetError> {
+    let channels = match gl_format {
+        gl::RGB => 3,
+        gl::RGBA => 4,
+        gl_format => {
+            return Err(AssetError::UnsupportedFormat(format!(
+                "framebuffer gl format {}",
+                gl_format
+            )))
+        }
+    };
+    let stride = width as usize * channels;
+    let mut data = vec![0u8; stride * height as usize];
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            width,
+            height,
+            gl_format,
+            gl::UNSIGNED_BYTE,
+            data.as_mut_ptr() as *mut GLvoid,
+        );
+    }
+    let data = data.chunks_exact(stride).rev().flatten().copied().collect();
+    Ok(Image::from_raw(
+        width as u32,
+        height as u32,
+        gl_format,
+        data,
+    ))
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Drop for Framebuffer {
     fn drop(&mut self) {
         unsafe {
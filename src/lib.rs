@@ -1,27 +1,27 @@
 pub mod assets;
 #[cfg(feature = "pillow")]
 mod benchmark;
+pub mod config;
 mod cubemap;
 mod framebuffer;
 mod inputs;
 mod types;
+use assets::{error::AssetError, image::Image};
+use config::{RevenantConfig, WindowMode, WindowPosition};
 use glfw::{Context, Glfw, PixelImage, Window, WindowEvent};
 use inputs::Inputs;
-use std::{env, sync::mpsc::Receiver};
+use std::{cell::RefCell, fmt, rc::Rc, sync::mpsc::Receiver};
 
 #[cfg(feature = "pillow")]
 use gl::types::{GLenum, GLuint};
 #[cfg(feature = "pillow")]
 use std::ffi::{c_void, CStr};
 
-// TODO flexible window size
-const WIN_DIM_X: u32 = 1600;
-const WIN_DIM_Y: u32 = 900;
-
 pub struct Revenant {
     pub(crate) glfw: Glfw,
     pub(crate) window: Window,
     pub(crate) events: Receiver<(f64, WindowEvent)>,
+    pub(crate) config: RevenantConfig,
     pub(crate) frame_time: f64,
     pub(crate) frame_time_last: f64,
     pub frame_time_delta: f64,
@@ -29,6 +29,23 @@ pub struct Revenant {
     pub inputs: Inputs,
 }
 
+/// No window or GL context could be created, most likely for lack of a display or driver
+#[derive(Debug)]
+pub struct ContextError(pub String);
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to create a GL context: {}", self.0)
+    }
+}
+
+impl std::error::Error for ContextError {}
+
+/// Keeps the last GLFW error until the context exists, GLFW reports why it failed only there
+fn record_error(_: glfw::Error, description: String, last_error: &Rc<RefCell<String>>) {
+    *last_error.borrow_mut() = description;
+}
+
 #[cfg(feature = "pillow")]
 extern "system" fn debug_callback(
     source: GLenum,
@@ -75,43 +92,99 @@ extern "system" fn debug_callback(
 
 impl Revenant {
     pub fn new() -> Self {
-        let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).expect("Failed to initialize GLFW.");
+        Self::with_config(RevenantConfig::default())
+    }
 
-        // requiest core profile 4.6
-        glfw.window_hint(glfw::WindowHint::ContextVersionMajor(4));
-        glfw.window_hint(glfw::WindowHint::ContextVersionMinor(6));
-        glfw.window_hint(glfw::WindowHint::OpenGlProfile(
-            glfw::OpenGlProfileHint::Core,
+    pub fn with_config(config: RevenantConfig) -> Self {
+        Self::try_with_config(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `with_config`, but fails instead of panicking when no context can be created
+    pub fn try_with_config(config: RevenantConfig) -> Result<Self, ContextError> {
+        let last_error = Rc::new(RefCell::new(String::new()));
+        let context_error = |what: &str| ContextError(format!("{}, {}", what, last_error.borrow()));
+        let mut glfw = glfw::init(Some(glfw::Callback {
+            f: record_error as fn(glfw::Error, String, &Rc<RefCell<String>>),
+            data: last_error.clone(),
+        }))
+        .map_err(|_| context_error("GLFW failed to initialize"))?;
+
+        glfw.window_hint(glfw::WindowHint::ContextVersionMajor(config.gl_version.0));
+        glfw.window_hint(glfw::WindowHint::ContextVersionMinor(config.gl_version.1));
+        glfw.window_hint(glfw::WindowHint::OpenGlProfile(config.gl_profile.into()));
+        glfw.window_hint(glfw::WindowHint::Samples(config.msaa_samples));
+        glfw.window_hint(glfw::WindowHint::Decorated(
+            config.window_mode != WindowMode::Borderless,
         ));
+        glfw.window_hint(glfw::WindowHint::ContextCreationApi(
+            config.context_api.into(),
+        ));
+        glfw.window_hint(glfw::WindowHint::Visible(!config.headless));
 
-        // TODO custom window name
-        // TODO custom window size, start position, fullscreen / borderless, etc
-        let (mut window, events) = glfw
-            .create_window(
-                WIN_DIM_X,
-                WIN_DIM_Y,
-                env!("CARGO_PKG_NAME"),
+        // glfw panics when listing monitors while none are connected
+        let has_monitor = glfw.with_primary_monitor(|_, monitor| monitor.is_some());
+        let created = if config.headless || !has_monitor {
+            glfw.create_window(
+                config.width,
+                config.height,
+                &config.title,
                 glfw::WindowMode::Windowed,
             )
-            .expect("Failed to create GLFW window.");
+            .map(|(window, events)| (window, events, None))
+        } else {
+            glfw.with_connected_monitors(|glfw, monitors| {
+                let monitor = monitors.get(config.monitor).or_else(|| monitors.first());
+                // x, y, width, height of the monitor current video mode
+                let monitor_area = monitor.and_then(|monitor| {
+                    let (x, y) = monitor.get_pos();
+                    monitor
+                        .get_video_mode()
+                        .map(|video_mode| (x, y, video_mode.width, video_mode.height))
+                });
+                let (width, height, mode) = match (config.window_mode, monitor, monitor_area) {
+                    (WindowMode::Fullscreen, Some(monitor), _) => (
+                        config.width,
+                        config.height,
+                        glfw::WindowMode::FullScreen(monitor),
+                    ),
+                    (WindowMode::Borderless, _, Some((_, _, width, height))) => {
+                        (width, height, glfw::WindowMode::Windowed)
+                    }
+                    _ => (config.width, config.height, glfw::WindowMode::Windowed),
+                };
+                glfw.create_window(width, height, &config.title, mode)
+                    .map(|(window, events)| (window, events, monitor_area))
+            })
+        };
+        let (mut window, events, monitor_area) =
+            created.ok_or_else(|| context_error("GLFW failed to create the window"))?;
+        // Errors past this point are bugs, not a missing display or driver
+        glfw.set_error_callback(glfw::FAIL_ON_ERRORS);
 
         gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
 
-        // Unlock framerate
-        // TODO make this a setting
-        glfw.set_swap_interval(glfw::SwapInterval::None);
+        glfw.set_swap_interval(config.vsync.into());
 
         window.set_framebuffer_size_polling(true);
         window.set_key_polling(true);
         window.set_scroll_polling(true);
         window.set_cursor_pos_polling(true);
-        window.set_cursor_mode(glfw::CursorMode::Disabled);
+        if !config.headless {
+            window.set_cursor_mode(config.cursor_mode.into());
+        }
+        window.set_size_limits(
+            config.min_size.map(|(width, _)| width),
+            config.min_size.map(|(_, height)| height),
+            config.max_size.map(|(width, _)| width),
+            config.max_size.map(|(_, height)| height),
+        );
         window.make_current();
 
         let mut revenant = Self {
             glfw,
             window,
             events,
+            config,
             // OPTIMIZE custom allocator? May not necessary
             frame_time: 0.0,
             frame_time_last: 0.0,
@@ -121,28 +194,69 @@ impl Revenant {
         };
 
         revenant.gl_init();
-        // TODO make this a setting or function
-        revenant.set_position_center();
-        // TODO make this configurable
+        revenant.set_position(monitor_area);
+
+        Ok(revenant)
+    }
 
-        revenant
+    /// Invisible window of the given size, see `RevenantConfig::headless`
+    pub fn headless(width: u32, height: u32) -> Self {
+        Self::with_config(RevenantConfig::default().size(width, height).headless(true))
+    }
+
+    /// Like `headless`, but fails instead of panicking when no context can be created
+    pub fn try_headless(width: u32, height: u32) -> Result<Self, ContextError> {
+        Self::try_with_config(RevenantConfig::default().size(width, height).headless(true))
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.config.headless
     }
 
     pub fn set_window_icon(&mut self, images: Vec<PixelImage>) {
         self.window.set_icon_from_pixels(images);
     }
 
-    fn set_position_center(&mut self) {
-        const WIN_DIM_X: u32 = 1600;
-        const WIN_DIM_Y: u32 = 900;
-        // TODO get this on the fly
-        const SCREEN_DIM_X: u32 = 1920;
-        const SCREEN_DIM_Y: u32 = 1080;
-        // Center window
-        self.window.set_pos(
-            (SCREEN_DIM_X - WIN_DIM_X) as i32 / 2,
-            (SCREEN_DIM_Y - WIN_DIM_Y) as i32 / 2,
-        );
+    pub fn config(&self) -> &RevenantConfig {
+        &self.config
+    }
+
+    pub fn framebuffer_size(&self) -> (u32, u32) {
+        let (width, height) = self.window.get_framebuffer_size();
+        (width as u32, height as u32)
+    }
+
+    /// Reads the default framebuffer back buffer, call before `should_close` swaps it
+    pub fn screenshot(&self) -> Result<Image, AssetError> {
+        let (width, height) = self.window.get_framebuffer_size();
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+            gl::ReadBuffer(gl::BACK);
+        }
+        framebuffer::gl_read_pixels(width, height, gl::RGBA)
+    }
+
+    fn set_position(&mut self, monitor_area: Option<(i32, i32, u32, u32)>) {
+        if self.config.headless {
+            return;
+        }
+        let Some((monitor_x, monitor_y, monitor_width, monitor_height)) = monitor_area else {
+            return;
+        };
+        match (self.config.window_mode, self.config.position) {
+            (WindowMode::Fullscreen, _) => {}
+            (WindowMode::Borderless, _) => self.window.set_pos(monitor_x, monitor_y),
+            (WindowMode::Windowed, WindowPosition::Centered) => {
+                let (width, height) = self.window.get_size();
+                self.window.set_pos(
+                    monitor_x + (monitor_width as i32 - width) / 2,
+                    monitor_y + (monitor_height as i32 - height) / 2,
+                );
+            }
+            (WindowMode::Windowed, WindowPosition::At(x, y)) => {
+                self.window.set_pos(monitor_x + x, monitor_y + y)
+            }
+        }
     }
 
     pub fn should_close(&mut self) -> bool {
@@ -193,6 +307,12 @@ impl Revenant {
             gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
         }
 
+        if self.config.msaa_samples.is_some() {
+            unsafe {
+                gl::Enable(gl::MULTISAMPLE);
+            }
+        }
+
         #[cfg(feature = "pillow")]
         unsafe {
             gl::Enable(gl::DEBUG_OUTPUT);
@@ -8,4 +8,4 @@ pub type Normal = Vector3<Uniaxial>;
 pub type TexCoord = Vector2<Uniaxial>; // OPTIMIZE use u16 if possible or even u8
 pub type ColorChannel = f32;
 pub type Rgb = Vector3<ColorChannel>;
-pub type Indice = u32; // OPTIMIZE use u16 if possible
+pub type Indice = u32; // Uploaded as u16 when the vertex count allows
@@ -0,0 +1,304 @@
+use cgmath::{vec3, Deg, InnerSpace, Matrix4, Quaternion, Rotation3, SquareMatrix};
+use revenant::assets::{
+    error::AssetError,
+    model::{
+        animation::{Animation, AnimationPlayer, Channel, Interpolation, Property, Sampler},
+        node::{self, Node},
+        skin::{Skin, MAX_JOINTS},
+        ModelData,
+    },
+};
+
+mod common;
+use common::EPSILON;
+
+fn assert_close(actual: [f32; 4], expected: [f32; 4]) {
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < EPSILON, "{:?} != {:?}", actual, expected);
+    }
+}
+
+fn sampler(interpolation: Interpolation, times: &[f32], values: &[[f32; 4]]) -> Sampler {
+    Sampler {
+        interpolation,
+        times: times.to_vec(),
+        values: values.to_vec(),
+    }
+}
+
+fn node(parent: Option<usize>, children: Vec<usize>) -> Node {
+    Node {
+        name: None,
+        parent,
+        children,
+        mesh: None,
+        skin: None,
+        weights: Vec::new(),
+        translation: vec3(0.0, 0.0, 0.0),
+        rotation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+        scale: vec3(1.0, 1.0, 1.0),
+        world: Matrix4::identity(),
+    }
+}
+
+#[test]
+fn linear_interpolates_between_keyframes() {
+    let sampler = sampler(
+        Interpolation::Linear,
+        &[0.0, 2.0],
+        &[[0.0, 0.0, 0.0, 0.0], [4.0, -2.0, 1.0, 0.0]],
+    );
+    assert_close(
+        sampler.sample(0.5, Property::Translation),
+        [1.0, -0.5, 0.25, 0.0],
+    );
+}
+
+#[test]
+fn sampling_clamps_outside_keyframes() {
+    let sampler = sampler(
+        Interpolation::Linear,
+        &[1.0, 2.0],
+        &[[1.0, 1.0, 1.0, 0.0], [2.0, 2.0, 2.0, 0.0]],
+    );
+    assert_close(sampler.sample(0.0, Property::Scale), [1.0, 1.0, 1.0, 0.0]);
+    assert_close(sampler.sample(5.0, Property::Scale), [2.0, 2.0, 2.0, 0.0]);
+}
+
+#[test]
+fn step_holds_the_previous_keyframe() {
+    let sampler = sampler(
+        Interpolation::Step,
+        &[0.0, 1.0],
+        &[[0.0, 0.0, 0.0, 0.0], [1.0, 1.0, 1.0, 0.0]],
+    );
+    assert_close(
+        sampler.sample(0.99, Property::Translation),
+        [0.0, 0.0, 0.0, 0.0],
+    );
+    assert_close(
+        sampler.sample(1.0, Property::Translation),
+        [1.0, 1.0, 1.0, 0.0],
+    );
+}
+
+#[test]
+fn linear_rotation_is_slerped() {
+    let half = (45.0f32.to_radians() / 2.0).sin();
+    let quarter_turn = Quaternion::from_angle_y(Deg(90.0));
+    let sampler = sampler(
+        Interpolation::Linear,
+        &[0.0, 1.0],
+        &[
+            [0.0, 0.0, 0.0, 1.0],
+            [
+                quarter_turn.v.x,
+                quarter_turn.v.y,
+                quarter_turn.v.z,
+                quarter_turn.s,
+            ],
+        ],
+    );
+    let sampled = sampler.sample(0.5, Property::Rotation);
+    assert_close(
+        sampled,
+        [0.0, half, 0.0, (45.0f32.to_radians() / 2.0).cos()],
+    );
+}
+
+#[test]
+fn cubic_spline_uses_tangents() {
+    // flat tangents give a smoothstep, the midpoint stays halfway
+    let flat = sampler(
+        Interpolation::CubicSpline,
+        &[0.0, 1.0],
+        &[
+            [0.0; 4],
+            [0.0, 0.0, 0.0, 0.0],
+            [0.0; 4],
+            [0.0; 4],
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0; 4],
+        ],
+    );
+    assert_close(
+        flat.sample(0.5, Property::Translation),
+        [1.0, 0.0, 0.0, 0.0],
+    );
+    assert_close(
+        flat.sample(0.25, Property::Translation),
+        [0.3125, 0.0, 0.0, 0.0],
+    );
+
+    // tangents are scaled by the keyframe delta
+    let sloped = sampler(
+        Interpolation::CubicSpline,
+        &[0.0, 2.0],
+        &[
+            [0.0; 4],
+            [0.0, 0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0; 4],
+        ],
+    );
+    assert_close(
+        sloped.sample(1.0, Property::Translation),
+        [1.0, 0.0, 0.0, 0.0],
+    );
+}
+
+#[test]
+fn cubic_spline_rotation_stays_normalized() {
+    let quarter_turn = Quaternion::from_angle_z(Deg(90.0));
+    let rotation = [
+        quarter_turn.v.x,
+        quarter_turn.v.y,
+        quarter_turn.v.z,
+        quarter_turn.s,
+    ];
+    let sampler = sampler(
+        Interpolation::CubicSpline,
+        &[0.0, 1.0],
+        &[
+            [0.0; 4],
+            [0.0, 0.0, 0.0, 1.0],
+            [0.0; 4],
+            [0.0; 4],
+            rotation,
+            [0.0; 4],
+        ],
+    );
+    let [x, y, z, w] = sampler.sample(0.5, Property::Rotation);
+    assert!((Quaternion::new(w, x, y, z).magnitude() - 1.0).abs() < EPSILON);
+}
+
+#[test]
+fn player_loops_and_stops() {
+    let animations = [Animation {
+        name: None,
+        channels: Vec::new(),
+        duration: 2.0,
+    }];
+
+    let mut player = AnimationPlayer::new(0);
+    player.advance(2.5, &animations);
+    assert!((player.time - 0.5).abs() < EPSILON);
+    assert!(player.playing);
+
+    player.looping = false;
+    player.advance(3.0, &animations);
+    assert!((player.time - 2.0).abs() < EPSILON);
+    assert!(!player.playing);
+    player.advance(1.0, &animations);
+    assert!((player.time - 2.0).abs() < EPSILON);
+}
+
+#[test]
+fn joint_matrices_follow_the_pose() {
+    // root -> arm, bound with the arm one unit up
+    let mut nodes = vec![node(None, vec![1]), node(Some(0), Vec::new())];
+    nodes[1].translation = vec3(0.0, 1.0, 0.0);
+    let skins = [Skin {
+        name: None,
+        joints: vec![0, 1],
+        inverse_bind_matrices: vec![
+            Matrix4::identity(),
+            Matrix4::from_translation(vec3(0.0, -1.0, 0.0)),
+        ],
+    }];
+    let animations = [Animation {
+        name: None,
+        channels: vec![Channel {
+            node: 0,
+            property: Property::Translation,
+            sampler: sampler(
+                Interpolation::Linear,
+                &[0.0, 1.0],
+                &[[0.0, 0.0, 0.0, 0.0], [2.0, 0.0, 0.0, 0.0]],
+            ),
+        }],
+        duration: 1.0,
+    }];
+
+    // bind pose skins to identity
+    let player = AnimationPlayer::new(0);
+    let joints = player.joint_matrices(&animations, &skins, &mut nodes);
+    assert_eq!(joints[0], vec![Matrix4::identity(); 2]);
+
+    let mut player = AnimationPlayer::new(0);
+    player.advance(0.5, &animations);
+    let joints = player.joint_matrices(&animations, &skins, &mut nodes);
+    let moved = Matrix4::from_translation(vec3(1.0, 0.0, 0.0));
+    assert_eq!(joints[0], vec![moved; 2]);
+}
+
+#[test]
+fn world_transforms_survive_cycles() {
+    let mut nodes = vec![node(None, vec![1]), node(Some(0), vec![1])];
+    nodes[1].translation = vec3(1.0, 0.0, 0.0);
+    node::update_world_transforms(&mut nodes);
+    assert_eq!(
+        nodes[1].world,
+        Matrix4::from_translation(vec3(1.0, 0.0, 0.0))
+    );
+}
+
+#[test]
+fn imports_skins_and_animations() {
+    let path = common::path("tests/fixtures/skinned.gltf");
+    let model = ModelData::from_gltf(path).expect("Failed to import the skinned fixture.");
+
+    assert_eq!(model.skins.len(), 1);
+    assert_eq!(model.skins[0].joints, vec![1, 2]);
+    assert_eq!(
+        model.skins[0].inverse_bind_matrices[1],
+        Matrix4::from_translation(vec3(0.0, -1.0, 0.0))
+    );
+    assert_eq!(model.nodes[0].skin, Some(0));
+
+    let skin = &model.meshes[0].skin;
+    assert_eq!(skin.len(), model.meshes[0].vertices.len());
+    assert_eq!(skin[2].joints, [0, 1, 0, 0]);
+    assert_eq!(skin[2].weights, [0.5, 0.5, 0.0, 0.0]);
+
+    let animation = &model.animations[0];
+    assert_eq!(animation.name.as_deref(), Some("wave"));
+    assert_eq!(animation.duration, 1.0);
+    let interpolations: Vec<Interpolation> = animation
+        .channels
+        .iter()
+        .map(|channel| channel.sampler.interpolation)
+        .collect();
+    assert_eq!(
+        interpolations,
+        [
+            Interpolation::Linear,
+            Interpolation::Step,
+            Interpolation::CubicSpline
+        ]
+    );
+
+    let mut nodes = model.nodes.clone();
+    let mut player = AnimationPlayer::new(0);
+    player.advance(0.5, &model.animations);
+    let joints = player.joint_matrices(&model.animations, &model.skins, &mut nodes);
+    assert_eq!(joints[0][0], Matrix4::from_translation(vec3(1.0, 0.0, 0.0)));
+    // step keeps the arm unrotated, the spline scales it halfway to 3
+    assert_close(nodes[2].rotation.into(), [0.0, 0.0, 0.0, 1.0]);
+    assert!((nodes[2].scale.x - 2.0).abs() < EPSILON);
+}
+
+#[test]
+fn joints_past_the_shader_limit_are_rejected() {
+    // The skinned fixture with one vertex bound to joint 200
+    let path = common::path("tests/fixtures/too_many_joints.gltf");
+    let error = ModelData::from_gltf(path).unwrap_err();
+    assert!(
+        matches!(error, AssetError::UnsupportedFormat(_)),
+        "{}",
+        error
+    );
+    assert!(error.to_string().contains(&MAX_JOINTS.to_string()));
+}
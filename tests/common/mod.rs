@@ -0,0 +1,40 @@
+// Fixtures shared by the mesh tests, every test crate uses only some of them
+#![allow(dead_code)]
+
+use cgmath::{vec2, vec3, Point3};
+use revenant::assets::{
+    mesh::{MeshData, Vertex},
+    model::ModelData,
+};
+use std::path::PathBuf;
+
+pub const EPSILON: f32 = 1e-5;
+
+/// `relative` to the root of the crate
+pub fn path(relative: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(relative)
+}
+
+/// The glTF models of `assets/foreign`, imported
+pub fn bundled() -> Vec<(&'static str, ModelData)> {
+    let assets = path("assets/foreign");
+    ["cube.gltf", "grass.gltf", "sphere.gltf", "window.gltf"]
+        .into_iter()
+        .map(|name| (name, ModelData::from_gltf(assets.join(name)).unwrap()))
+        .collect()
+}
+
+/// No normal and no texture coordinate
+pub fn vertex(position: [f32; 3]) -> Vertex {
+    Vertex {
+        position: Point3::from(position),
+        normal: vec3(0.0, 0.0, 0.0),
+        tex_coord: vec2(0.0, 0.0),
+    }
+}
+
+pub fn triangles(mesh: &MeshData) -> impl Iterator<Item = [usize; 3]> + '_ {
+    mesh.indices
+        .chunks(3)
+        .map(|triangle| [0, 1, 2].map(|corner| triangle[corner] as usize))
+}
@@ -0,0 +1,142 @@
+// Exported files go through the importer again, which validates them with the gltf crate
+use revenant::assets::{
+    material::MaterialData,
+    model::{export, ModelData},
+};
+use std::path::Path;
+
+mod common;
+use common::path;
+
+fn round_trip(model: &ModelData, name: &str) -> ModelData {
+    let directory = std::env::temp_dir().join(format!("revenant-export-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    model.export(&path).expect("Failed to export the model.");
+    ModelData::from_gltf(path).expect("Failed to import the exported model.")
+}
+
+fn assert_same(original: &ModelData, exported: &ModelData) {
+    assert_eq!(original.meshes.len(), exported.meshes.len());
+    for (original, exported) in original.meshes.iter().zip(&exported.meshes) {
+        assert_eq!(original.gl_mode, exported.gl_mode);
+        assert_eq!(original.vertices, exported.vertices);
+        assert_eq!(original.indices, exported.indices);
+        assert_eq!(original.skin, exported.skin);
+        assert_eq!(original.morph_targets, exported.morph_targets);
+    }
+    assert_eq!(original.primitive_materials, exported.primitive_materials);
+    assert_eq!(original.mesh_primitives, exported.mesh_primitives);
+
+    assert_eq!(original.nodes.len(), exported.nodes.len());
+    for (original, exported) in original.nodes.iter().zip(&exported.nodes) {
+        assert_eq!(original.name, exported.name);
+        assert_eq!(original.parent, exported.parent);
+        assert_eq!(original.children, exported.children);
+        assert_eq!(original.mesh, exported.mesh);
+        assert_eq!(original.skin, exported.skin);
+        assert_eq!(original.weights, exported.weights);
+        assert_eq!(original.local(), exported.local());
+    }
+    assert_eq!(original.scene, exported.scene);
+    assert_eq!(original.scenes.len(), exported.scenes.len());
+
+    assert_eq!(original.skins.len(), exported.skins.len());
+    for (original, exported) in original.skins.iter().zip(&exported.skins) {
+        assert_eq!(original.joints, exported.joints);
+        assert_eq!(
+            original.inverse_bind_matrices,
+            exported.inverse_bind_matrices
+        );
+    }
+    assert_eq!(original.animations.len(), exported.animations.len());
+    for (original, exported) in original.animations.iter().zip(&exported.animations) {
+        assert_eq!(original.name, exported.name);
+        assert_eq!(original.duration, exported.duration);
+        assert_eq!(original.channels.len(), exported.channels.len());
+        for (original, exported) in original.channels.iter().zip(&exported.channels) {
+            assert_eq!(original.node, exported.node);
+            assert_eq!(original.property, exported.property);
+            assert_eq!(
+                original.sampler.interpolation,
+                exported.sampler.interpolation
+            );
+            assert_eq!(original.sampler.times, exported.sampler.times);
+            assert_eq!(original.sampler.values, exported.sampler.values);
+        }
+    }
+}
+
+#[test]
+fn textured_model_round_trips() {
+    let original = ModelData::from_gltf(path("assets/foreign/cube.gltf")).unwrap();
+    for name in ["cube.glb", "cube.gltf"] {
+        let exported = round_trip(&original, name);
+        assert_same(&original, &exported);
+
+        assert_eq!(original.materials.len(), exported.materials.len());
+        for (original, exported) in original.materials.iter().zip(&exported.materials) {
+            let (
+                MaterialData::Pbr {
+                    albedo: original_albedo,
+                    normal: original_normal,
+                    base_color_factor: original_factor,
+                    alpha_mode: original_alpha_mode,
+                    ..
+                },
+                MaterialData::Pbr {
+                    albedo,
+                    normal,
+                    base_color_factor,
+                    alpha_mode,
+                    ..
+                },
+            ) = (original, exported)
+            else {
+                panic!("Expected metallic-roughness materials.");
+            };
+            assert_eq!(original_factor, base_color_factor);
+            assert_eq!(original_alpha_mode, alpha_mode);
+            assert_eq!(original_normal.is_some(), normal.is_some());
+            assert_eq!(original_albedo.image.width(), albedo.image.width());
+            assert_eq!(original_albedo.image.height(), albedo.image.height());
+            assert_eq!(original_albedo.image.data(), albedo.image.data());
+        }
+    }
+}
+
+#[test]
+fn skins_and_animations_round_trip() {
+    let original = ModelData::from_gltf(path("tests/fixtures/skinned.gltf")).unwrap();
+    assert_same(&original, &round_trip(&original, "skinned.glb"));
+}
+
+#[test]
+fn morph_targets_round_trip() {
+    let original = ModelData::from_gltf(path("tests/fixtures/morph.gltf")).unwrap();
+    assert_same(&original, &round_trip(&original, "morph.gltf"));
+}
+
+#[test]
+fn glb_chunks_are_aligned() {
+    let glb = export::to_glb(&ModelData::from_gltf(path("tests/fixtures/skinned.gltf")).unwrap())
+        .unwrap();
+    let word = |offset: usize| u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap());
+
+    assert_eq!(&glb[..4], b"glTF");
+    assert_eq!(word(4), 2);
+    assert_eq!(word(8) as usize, glb.len());
+    let json_length = word(12) as usize;
+    assert_eq!(word(16), 0x4E4F534A);
+    assert_eq!(json_length % 4, 0);
+    let bin = 20 + json_length;
+    assert_eq!(word(bin + 4), 0x004E4942);
+    assert_eq!(word(bin) as usize % 4, 0);
+    assert_eq!(bin + 8 + word(bin) as usize, glb.len());
+}
+
+#[test]
+fn unknown_extension_is_rejected() {
+    let model = ModelData::from_gltf(path("tests/fixtures/morph.gltf")).unwrap();
+    assert!(model.export(Path::new("morph.obj")).is_err());
+}
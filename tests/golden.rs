@@ -0,0 +1,256 @@
+// Golden image regression tests, renders every scene headless and compares it against tests/golden/<scene>.png
+// Run with REVENANT_BLESS=1 to (re)generate the references, skipped when no GL context can be created
+use cgmath::{point3, vec3, EuclideanSpace, Matrix4};
+use revenant::{
+    assets::{
+        camera::Camera, error::AssetError, handle::Handle, image::Image, light::DirLight,
+        mesh::Mesh, model::Model, program::Program, Assets, Cubemap, Framebuffer,
+    },
+    Revenant,
+};
+use std::{env, path::PathBuf, process::ExitCode};
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 180;
+// the retouching pass dithers by up to one step per channel
+const TOLERANCE: u8 = 2;
+
+const SCENES: [(&str, fn(&Assets, &Handles) -> Result<(), AssetError>); 3] = [
+    ("skybox", render_skybox),
+    ("skybox_cube", render_skybox_cube),
+    ("cube_retouching", render_cube_retouching),
+];
+
+fn main() -> Result<ExitCode, AssetError> {
+    let _revenant = match Revenant::try_headless(WIDTH, HEIGHT) {
+        Ok(revenant) => revenant,
+        Err(error) => {
+            println!("golden: skipped, {}", error);
+            return Ok(ExitCode::SUCCESS);
+        }
+    };
+    let mut assets = Assets::new();
+    let handles = init_assets(&mut assets)?;
+
+    let bless = env::var_os("REVENANT_BLESS").is_some();
+    let mut failures = 0;
+    for (name, render) in SCENES {
+        render(&assets, &handles)?;
+        let actual = assets.get_framebuffer(handles.output)?.read_pixels()?;
+        if !check(name, &actual, bless)? {
+            failures += 1;
+        }
+    }
+
+    if failures > 0 {
+        println!("golden: {} of {} scenes failed", failures, SCENES.len());
+        return Ok(ExitCode::FAILURE);
+    }
+    println!("golden: {} scenes passed", SCENES.len());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn golden_path(name: &str, suffix: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}{}.png", name, suffix))
+}
+
+fn check(name: &str, actual: &Image, bless: bool) -> Result<bool, AssetError> {
+    let reference_path = golden_path(name, "");
+    if bless {
+        if let Some(folder) = reference_path.parent() {
+            std::fs::create_dir_all(folder)
+                .map_err(|error| AssetError::Io(folder.to_owned(), error))?;
+        }
+        actual.to_file(reference_path.clone())?;
+        println!("golden: {} reference written to {:?}", name, reference_path);
+        return Ok(true);
+    }
+    if !reference_path.exists() {
+        actual.to_file(golden_path(name, ".actual"))?;
+        println!(
+            "golden: {} FAILED, no reference at {:?}, bless it with REVENANT_BLESS=1",
+            name, reference_path
+        );
+        return Ok(false);
+    }
+
+    let reference = Image::from_file(reference_path, "png")?;
+    let diff = match actual.compare(&reference, TOLERANCE) {
+        Some(diff) if diff.mismatched_pixels == 0 => {
+            println!("golden: {} ok", name);
+            return Ok(true);
+        }
+        Some(diff) => diff,
+        None => {
+            println!(
+                "golden: {} dimensions or format differ from reference",
+                name
+            );
+            return Ok(false);
+        }
+    };
+
+    actual.to_file(golden_path(name, ".actual"))?;
+    diff.image.to_file(golden_path(name, ".diff"))?;
+    println!(
+        "golden: {} FAILED, {} pixels differ (max channel delta {}), see {:?}",
+        name,
+        diff.mismatched_pixels,
+        diff.max_channel_delta,
+        golden_path(name, ".diff")
+    );
+    Ok(false)
+}
+
+struct Handles {
+    quad: Handle<Mesh>,
+    mesh_skybox: Handle<Mesh>,
+    cubemap_skybox: Handle<Cubemap>,
+    cube: Handle<Model>,
+    camera: Handle<Camera>,
+    program_pbr: Handle<Program>,
+    program_skybox: Handle<Program>,
+    program_retouching: Handle<Program>,
+    scene: Handle<Framebuffer>,
+    output: Handle<Framebuffer>,
+}
+
+fn init_assets(assets: &mut Assets) -> Result<Handles, AssetError> {
+    let quad = assets.new_mesh("quad", Mesh::quad());
+    let mesh_skybox = assets.new_mesh("skybox", Mesh::skybox());
+    let cubemap_skybox = assets.new_cubemap_foreign(
+        "skybox",
+        ("right", "jpg"),
+        ("left", "jpg"),
+        ("top", "jpg"),
+        ("bottom", "jpg"),
+        ("front", "jpg"),
+        ("back", "jpg"),
+    )?;
+    let cube = assets.new_model_foreign("cube", "gltf")?;
+    // The view matrices are only computed by `update`
+    let mut camera = Camera::perspective(point3(1.5, 1.0, 5.0));
+    camera.update();
+    let camera = assets.new_camera("main", camera);
+    assets.new_dirlight(
+        "sun",
+        DirLight {
+            dir: vec3(-0.3, -1.0, -0.5),
+            ambient: vec3(0.2, 0.2, 0.2),
+            diffuse: vec3(3.0, 3.0, 3.0),
+            specular: vec3(1.0, 1.0, 1.0),
+        },
+    );
+
+    let mut new_program = |name: &str| -> Result<Handle<Program>, AssetError> {
+        let vs = assets.new_shader_foreign(name, "vs")?;
+        let fs = assets.new_shader_foreign(name, "fs")?;
+        assets.new_program(name, &[vs, fs])
+    };
+    let program_pbr = new_program("pbr")?;
+    let program_skybox = new_program("skybox")?;
+    let program_retouching = new_program("retouching")?;
+
+    Ok(Handles {
+        quad,
+        mesh_skybox,
+        cubemap_skybox,
+        cube,
+        camera,
+        program_pbr,
+        program_skybox,
+        program_retouching,
+        scene: assets.new_framebuffer("scene", WIDTH, HEIGHT),
+        output: assets.new_framebuffer("output", WIDTH, HEIGHT),
+    })
+}
+
+fn begin_scene(assets: &Assets, handles: &Handles) -> Result<(), AssetError> {
+    assets.get_framebuffer(handles.scene)?.gl_bind();
+    unsafe {
+        gl::Enable(gl::DEPTH_TEST);
+        gl::ClearColor(0.1, 0.0, 0.1, 1.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+    }
+
+    Ok(())
+}
+
+fn draw_skybox(assets: &Assets, handles: &Handles) -> Result<(), AssetError> {
+    let camera = assets.get_camera(handles.camera)?;
+    let program = assets.get_program(handles.program_skybox)?;
+    unsafe {
+        gl::DepthMask(gl::FALSE);
+    }
+    program.use_program();
+    program.set_uniform_mat4("view", &camera.view_skybox);
+    program.set_uniform_mat4("projection", &camera.projection);
+    assets.get_cubemap(handles.cubemap_skybox)?.gl_bind();
+    program.set_uniform_int("skybox", 0);
+    assets.get_mesh(handles.mesh_skybox)?.draw();
+    unsafe {
+        gl::DepthMask(gl::TRUE);
+    }
+
+    Ok(())
+}
+
+fn draw_cube(assets: &Assets, handles: &Handles) -> Result<(), AssetError> {
+    let camera = assets.get_camera(handles.camera)?;
+    let program = assets.get_program(handles.program_pbr)?;
+    assets.upload_lights(handles.program_pbr)?;
+    program.set_uniform_mat4("view", &camera.view);
+    program.set_uniform_mat4("projection", &camera.projection);
+    program.set_uniform_vec3("camera_pos", camera.pos.to_vec());
+    assets
+        .get_model(handles.cube)?
+        .draw(program, &Matrix4::from_translation(vec3(0.0, 0.0, 0.0)));
+
+    Ok(())
+}
+
+fn retouch(assets: &Assets, handles: &Handles) -> Result<(), AssetError> {
+    let output = assets.get_framebuffer(handles.output)?;
+    output.gl_bind();
+    unsafe {
+        gl::Disable(gl::DEPTH_TEST);
+        gl::ClearColor(1.0, 1.0, 1.0, 1.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+    }
+    assets
+        .get_program(handles.program_retouching)?
+        .use_program();
+    let quad = assets.get_mesh(handles.quad)?;
+    quad.gl_bind_vao();
+    unsafe {
+        gl::BindTexture(
+            gl::TEXTURE_2D,
+            assets.get_framebuffer(handles.scene)?.gl_texturebuffer_id,
+        );
+    }
+    quad.draw();
+    output.gl_unbind();
+
+    Ok(())
+}
+
+fn render_skybox(assets: &Assets, handles: &Handles) -> Result<(), AssetError> {
+    begin_scene(assets, handles)?;
+    draw_skybox(assets, handles)?;
+    retouch(assets, handles)
+}
+
+fn render_skybox_cube(assets: &Assets, handles: &Handles) -> Result<(), AssetError> {
+    begin_scene(assets, handles)?;
+    draw_skybox(assets, handles)?;
+    draw_cube(assets, handles)?;
+    retouch(assets, handles)
+}
+
+fn render_cube_retouching(assets: &Assets, handles: &Handles) -> Result<(), AssetError> {
+    begin_scene(assets, handles)?;
+    draw_cube(assets, handles)?;
+    retouch(assets, handles)
+}
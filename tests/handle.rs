@@ -0,0 +1,62 @@
+// Cameras need no GL, their storage is the one every asset kind uses
+use cgmath::point3;
+use revenant::assets::{camera::Camera, error::AssetError, Assets};
+
+fn camera_at(x: f32) -> Camera {
+    Camera {
+        pos: point3(x, 0.0, 0.0),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn removed_handles_are_stale() {
+    let mut assets = Assets::new();
+    let handle = assets.new_camera("main", camera_at(1.0));
+    assert_eq!(assets.get_camera(handle).unwrap().pos.x, 1.0);
+
+    assert_eq!(assets.remove_camera(handle).unwrap().pos.x, 1.0);
+    assert!(matches!(
+        assets.get_camera(handle),
+        Err(AssetError::Missing { .. })
+    ));
+    assert!(assets.get_mut_camera(handle).is_err());
+    // A second remove finds nothing
+    assert!(assets.remove_camera(handle).is_none());
+}
+
+#[test]
+fn reused_slots_bump_the_generation() {
+    let mut assets = Assets::new();
+    let first = assets.new_camera("first", camera_at(1.0));
+    assets.remove_camera(first);
+
+    let second = assets.new_camera("second", camera_at(2.0));
+    assert_eq!(second.index(), first.index());
+    assert_ne!(second.generation(), first.generation());
+    assert_ne!(second, first);
+    // The stale handle does not reach the new camera
+    assert!(assets.get_camera(first).is_err());
+    assert!(assets.remove_camera(first).is_none());
+    assert_eq!(assets.get_camera(second).unwrap().pos.x, 2.0);
+}
+
+#[test]
+fn removing_frees_the_name() {
+    let mut assets = Assets::new();
+    let handle = assets.new_camera("main", camera_at(1.0));
+    // Inserting under the same name replaces in place
+    assert_eq!(assets.new_camera("main", camera_at(2.0)), handle);
+    assert_eq!(assets.camera_handle("main").unwrap(), handle);
+
+    assets.remove_camera(handle);
+    assert!(matches!(
+        assets.camera_handle("main"),
+        Err(AssetError::Missing { .. })
+    ));
+
+    // The name is free for a new camera, under a new handle
+    let other = assets.new_camera("main", camera_at(3.0));
+    assert_ne!(other, handle);
+    assert_eq!(assets.camera_handle("main").unwrap(), other);
+}
@@ -0,0 +1,111 @@
+use image::{DynamicImage, ImageBuffer, ImageFormat, Luma, LumaA, Rgb, Rgba};
+use revenant::assets::{
+    error::AssetError,
+    image::Image,
+    soul::{self, Compression, HEADER_LEN},
+};
+use std::path::PathBuf;
+
+fn png(image: DynamicImage) -> Vec<u8> {
+    let mut png = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageFormat::Png).unwrap();
+    png.into_inner()
+}
+
+#[test]
+fn grey_images_become_rgb() {
+    let grey = ImageBuffer::from_fn(2, 1, |x, _| Luma([x as u8 * 200]));
+    let image = Image::from_data(&png(DynamicImage::ImageLuma8(grey))).unwrap();
+    assert_eq!((image.width(), image.height()), (2, 1));
+    assert_eq!(image.data(), [0, 0, 0, 200, 200, 200]);
+
+    let grey_alpha = ImageBuffer::from_pixel(1, 1, LumaA([50, 100]));
+    let image = Image::from_data(&png(DynamicImage::ImageLumaA8(grey_alpha))).unwrap();
+    assert_eq!(image.data(), [50, 50, 50, 100]);
+}
+
+#[test]
+fn sixteen_bit_images_become_eight_bit() {
+    let rgb = ImageBuffer::from_pixel(1, 1, Rgb([0u16, 0x8080, u16::MAX]));
+    let image = Image::from_data(&png(DynamicImage::ImageRgb16(rgb))).unwrap();
+    assert_eq!(image.data(), [0, 128, 255]);
+
+    let rgba = ImageBuffer::from_pixel(1, 1, Rgba([u16::MAX, 0, 0, u16::MAX]));
+    let image = Image::from_data(&png(DynamicImage::ImageRgba16(rgba))).unwrap();
+    assert_eq!(image.data(), [255, 0, 0, 255]);
+
+    let grey = ImageBuffer::from_pixel(1, 1, Luma([u16::MAX]));
+    let image = Image::from_data(&png(DynamicImage::ImageLuma16(grey))).unwrap();
+    assert_eq!(image.data(), [255, 255, 255]);
+}
+
+#[test]
+fn missing_files_are_io_errors() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/foreign/missing.png");
+    let error = Image::from_file(path.clone(), "png").unwrap_err();
+    assert!(
+        matches!(&error, AssetError::Io(at, _) if *at == path),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn jpeg_is_a_jpg_extension() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/foreign/back.jpg");
+    let jpg = Image::from_file(path.clone(), "jpg").unwrap();
+    let jpeg = Image::from_file(path, "JPEG").unwrap();
+    assert_eq!(jpeg.data(), jpg.data());
+}
+
+#[test]
+fn unknown_extensions_are_unsupported() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/foreign/icon.png");
+    let error = Image::from_file(path, "tga").unwrap_err();
+    assert!(
+        matches!(error, AssetError::UnsupportedFormat(_)),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn corrupt_images_fail_to_decode() {
+    let error = Image::from_data(b"definitely not an image").unwrap_err();
+    assert!(matches!(error, AssetError::Decode(_)), "{}", error);
+
+    // A valid header cut short
+    let mut truncated = png(DynamicImage::ImageRgb8(ImageBuffer::from_pixel(
+        16,
+        16,
+        Rgb([1, 2, 3]),
+    )));
+    truncated.truncate(truncated.len() / 2);
+    let error = Image::from_data(&truncated).unwrap_err();
+    assert!(matches!(error, AssetError::Decode(_)), "{}", error);
+}
+
+#[test]
+fn images_compare_pixel_by_pixel() {
+    let reference = Image::from_raw(2, 1, gl::RGB, vec![10, 10, 10, 10, 10, 10]);
+    let actual = Image::from_raw(2, 1, gl::RGB, vec![12, 10, 10, 10, 20, 10]);
+    let diff = actual.compare(&reference, 2).unwrap();
+    assert_eq!(diff.mismatched_pixels, 1);
+    assert_eq!(diff.max_channel_delta, 10);
+
+    let other_size = Image::from_raw(1, 1, gl::RGB, vec![10, 10, 10]);
+    assert!(actual.compare(&other_size, 2).is_none());
+}
+
+#[test]
+fn other_formats_cannot_be_compared() {
+    // Only a decoded .soul can hold another format, `from_raw` checks it
+    let image = Image::from_raw(1, 1, gl::RGB, vec![0, 0, 0]);
+    let mut bytes = soul::encode(&image, Compression::None).unwrap();
+    // The format is the first field of the payload, the checksum follows the new payload
+    bytes[HEADER_LEN..HEADER_LEN + 4].copy_from_slice(&gl::RED.to_le_bytes());
+    let checksum = crc32fast::hash(&bytes[HEADER_LEN..]);
+    bytes[24..28].copy_from_slice(&checksum.to_le_bytes());
+    let red: Image = soul::decode(&bytes).unwrap();
+    assert!(red.compare(&red, 0).is_none());
+}
@@ -0,0 +1,92 @@
+use revenant::assets::{
+    mesh::{
+        layout::{Semantic, VertexLayout},
+        Vertex, VertexAttributes, VertexSkin,
+    },
+    model::{export, ModelData},
+};
+
+mod common;
+
+#[test]
+fn vertex_layout_matches_the_struct() {
+    let layout = Vertex::layout();
+    assert_eq!(layout.stride(), std::mem::size_of::<Vertex>());
+    let placement: Vec<(Semantic, u32, usize)> = layout
+        .attributes()
+        .iter()
+        .map(|attribute| (attribute.semantic, attribute.location, attribute.offset))
+        .collect();
+    assert_eq!(
+        placement,
+        [
+            (Semantic::Position, 0, 0),
+            (Semantic::Normal, 1, 12),
+            (Semantic::TexCoord(0), 2, 24),
+        ]
+    );
+}
+
+#[test]
+fn skin_layout_keeps_integer_joints() {
+    let layout = VertexSkin::layout();
+    assert_eq!(layout.stride(), std::mem::size_of::<VertexSkin>());
+    let joints = layout.get(Semantic::Joints).unwrap();
+    assert_eq!((joints.location, joints.gl_type), (3, gl::UNSIGNED_SHORT));
+    assert!(!joints.normalized);
+    assert_eq!(layout.get(Semantic::Weights).unwrap().offset, 8);
+}
+
+#[test]
+fn custom_layouts_pack_attributes() {
+    let layout = VertexLayout::new()
+        .with(Semantic::Position, 3, gl::FLOAT, false)
+        .with(Semantic::Color, 4, gl::UNSIGNED_BYTE, true)
+        .with(Semantic::TexCoord(1), 2, gl::UNSIGNED_SHORT, true);
+    assert_eq!(layout.stride(), 12 + 4 + 4);
+    assert_eq!(layout.get(Semantic::Color).unwrap().location, 6);
+    assert_eq!(layout.get(Semantic::TexCoord(1)).unwrap().offset, 16);
+    assert_eq!(layout.get(Semantic::TexCoord(1)).unwrap().location, 7);
+    assert!(layout.get(Semantic::Normal).is_none());
+}
+
+#[test]
+fn optional_attributes_are_interleaved() {
+    let attributes = VertexAttributes {
+        tangents: vec![[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, -1.0]],
+        colors: Vec::new(),
+        tex_coords: vec![vec![[0.25, 0.5], [0.75, 1.0]]],
+    };
+    let semantics: Vec<Semantic> = attributes
+        .layout()
+        .attributes()
+        .iter()
+        .map(|attribute| attribute.semantic)
+        .collect();
+    assert_eq!(semantics, [Semantic::Tangent, Semantic::TexCoord(1)]);
+    assert_eq!(attributes.layout().stride(), 24);
+    assert_eq!(
+        attributes.interleave(2),
+        [1.0, 0.0, 0.0, 1.0, 0.25, 0.5, 0.0, 1.0, 0.0, -1.0, 0.75, 1.0]
+    );
+    assert!(VertexAttributes::default().layout().attributes().is_empty());
+}
+
+#[test]
+fn optional_attributes_round_trip_through_gltf() {
+    let path = common::path("tests/fixtures/morph.gltf");
+    let mut model = ModelData::from_gltf(path).unwrap();
+    let count = model.meshes[0].vertices.len();
+    model.meshes[0].attributes = VertexAttributes {
+        tangents: vec![[1.0, 0.0, 0.0, 1.0]; count],
+        colors: vec![[1.0, 0.5, 0.25, 1.0]; count],
+        tex_coords: vec![vec![[0.5, 0.5]; count], vec![[0.0, 1.0]; count]],
+    };
+
+    let directory = std::env::temp_dir().join(format!("revenant-layout-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("attributes.glb");
+    std::fs::write(&path, export::to_glb(&model).unwrap()).unwrap();
+    let imported = ModelData::from_gltf(path).unwrap();
+    assert_eq!(imported.meshes[0].attributes, model.meshes[0].attributes);
+}
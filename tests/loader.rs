@@ -0,0 +1,63 @@
+// Images decode and "upload" without GL, enough to drive the async lifecycle
+use revenant::assets::{loader::LoadStatus, Assets};
+use std::time::{Duration, Instant};
+
+/// Processes uploads until nothing is loading
+fn settle(assets: &mut Assets) {
+    let start = Instant::now();
+    while assets.loading_count() > 0 {
+        assert!(start.elapsed() < Duration::from_secs(10), "loader stuck");
+        assets.process_uploads(Duration::from_millis(1));
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assets.process_uploads(Duration::from_millis(1));
+}
+
+#[test]
+fn reserved_slots_fill_once_decoded() {
+    let mut assets = Assets::new();
+    let handle = assets.new_image_foreign_async("icon", "png").unwrap();
+    assert!(assets.get_image(handle).is_err());
+    assert_eq!(assets.image_handle("icon").unwrap(), handle);
+    settle(&mut assets);
+    assert_eq!(assets.load_status(handle), LoadStatus::Ready);
+    assert!(assets.get_image(handle).is_ok());
+
+    assert!(assets.remove_image(handle).is_some());
+    assert!(assets.get_image(handle).is_err());
+    assert!(assets.image_handle("icon").is_err());
+}
+
+#[test]
+fn inserting_under_a_reserved_name_fills_its_slot() {
+    let mut assets = Assets::new();
+    let reserved = assets.new_image_foreign_async("icon", "png").unwrap();
+    let inserted = assets.new_image_foreign("icon", "png").unwrap();
+    assert_eq!(inserted, reserved);
+    assert!(assets.get_image(reserved).is_ok());
+
+    // The pending load lands in the same slot instead of an orphan one
+    settle(&mut assets);
+    assert_eq!(assets.load_status(reserved), LoadStatus::Ready);
+    assert_eq!(assets.image_handle("icon").unwrap(), reserved);
+    assert!(assets.get_image(reserved).is_ok());
+}
+
+#[test]
+fn removing_a_reserved_slot_frees_it() {
+    let mut assets = Assets::new();
+    let handle = assets.new_image_foreign_async("icon", "png").unwrap();
+    assert!(assets.remove_image(handle).is_none());
+    assert!(assets.image_handle("icon").is_err());
+    assert_eq!(assets.loading_count(), 0);
+
+    // The decode finishing later fills nothing, not even the reused slot
+    let other = assets.new_image_foreign("icon", "png").unwrap();
+    assert_eq!(other.index(), handle.index());
+    assert_ne!(other.generation(), handle.generation());
+    settle(&mut assets);
+    std::thread::sleep(Duration::from_millis(100));
+    assets.process_uploads(Duration::from_millis(1));
+    assert!(assets.get_image(handle).is_err());
+    assert!(assets.get_image(other).is_ok());
+}
@@ -0,0 +1,227 @@
+use cgmath::{ortho, perspective, Deg, InnerSpace, Point3};
+use revenant::assets::mesh::{
+    lod::{self, LodSettings},
+    primitives,
+    simplify::simplify,
+    MeshData,
+};
+
+mod common;
+use common::bundled;
+
+/// Every index in range and no triangle without area
+fn assert_valid(mesh: &MeshData, indices: &[u32]) {
+    assert_eq!(indices.len() % 3, 0);
+    for triangle in indices.chunks(3) {
+        let [a, b, c] = [0, 1, 2].map(|corner| {
+            let index = triangle[corner] as usize;
+            assert!(index < mesh.vertices.len());
+            mesh.vertices[index].position
+        });
+        assert!((b - a).cross(c - a).magnitude2() > 0.0, "{:?}", triangle);
+    }
+}
+
+#[test]
+fn simplifying_reaches_the_target() {
+    for mesh in [
+        primitives::uv_sphere(1.0, 32, 16),
+        primitives::torus(1.0, 0.25, 32, 16),
+        primitives::plane(1.0, 1.0, 16, 16),
+    ] {
+        let target = mesh.indices.len() / 4 / 3 * 3;
+        let (indices, error) = simplify(&mesh.vertices, &mesh.indices, target, f32::MAX);
+        assert!(indices.len() <= target, "{} > {}", indices.len(), target);
+        assert!(!indices.is_empty());
+        assert!(error >= 0.0);
+        assert_valid(&mesh, &indices);
+    }
+}
+
+#[test]
+fn flat_regions_collapse_for_free() {
+    let mesh = primitives::plane(1.0, 1.0, 16, 16);
+    let (indices, error) = simplify(&mesh.vertices, &mesh.indices, 0, 1e-4);
+    assert!(indices.len() < mesh.indices.len() / 4, "{}", indices.len());
+    assert!(error <= 1e-4, "{}", error);
+}
+
+#[test]
+fn the_error_bounds_the_collapses() {
+    let mesh = primitives::uv_sphere(1.0, 32, 16);
+    let (loose, loose_error) = simplify(&mesh.vertices, &mesh.indices, 0, 0.1);
+    let (tight, tight_error) = simplify(&mesh.vertices, &mesh.indices, 0, 0.01);
+    assert!(loose_error <= 0.1 && tight_error <= 0.01);
+    assert!(tight.len() > loose.len());
+    let (all, _) = simplify(&mesh.vertices, &mesh.indices, 0, 0.0);
+    assert_eq!(all.len(), mesh.indices.len());
+}
+
+#[test]
+fn borders_keep_their_corners() {
+    let mesh = primitives::plane(2.0, 2.0, 8, 8);
+    let (indices, _) = simplify(&mesh.vertices, &mesh.indices, 0, 0.01);
+    assert_valid(&mesh, &indices);
+    assert!(indices.len() < mesh.indices.len() / 4, "{}", indices.len());
+    for corner in [[-1.0, -1.0], [-1.0, 1.0], [1.0, -1.0], [1.0, 1.0]] {
+        assert!(
+            indices.iter().any(|index| {
+                let position = mesh.vertices[*index as usize].position;
+                position.x == corner[0] && position.z == corner[1]
+            }),
+            "{:?}",
+            corner
+        );
+    }
+}
+
+#[test]
+fn seams_stay_closed() {
+    // Every position of the sphere seam is split in two vertices, both halves must move alike
+    let mesh = primitives::uv_sphere(1.0, 32, 16);
+    let (indices, _) = simplify(&mesh.vertices, &mesh.indices, mesh.indices.len() / 8, 0.2);
+    let mut edges = std::collections::HashMap::new();
+    for triangle in indices.chunks(3) {
+        for corner in 0..3 {
+            // Seam positions only match up to rounding
+            let [a, b] = [triangle[corner], triangle[(corner + 1) % 3]].map(|index| {
+                let position = mesh.vertices[index as usize].position;
+                [position.x, position.y, position.z].map(|value| (value * 1e4).round() as i32)
+            });
+            let key = if a < b { (a, b) } else { (b, a) };
+            *edges.entry(key).or_insert(0) += 1;
+        }
+    }
+    // Closed surface, every edge between two triangles
+    let open: Vec<_> = edges.iter().filter(|(_, count)| **count != 2).collect();
+    assert!(open.is_empty(), "{:?}", open);
+}
+
+#[test]
+fn simplifying_is_deterministic() {
+    let mesh = primitives::torus(1.0, 0.25, 48, 24);
+    let first = simplify(&mesh.vertices, &mesh.indices, 300, f32::MAX);
+    let second = simplify(&mesh.vertices, &mesh.indices, 300, f32::MAX);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn chains_shrink_with_growing_errors() {
+    let mut mesh = primitives::uv_sphere(1.0, 48, 24);
+    mesh.generate_lods(&LodSettings::default());
+    assert_eq!(mesh.lods.len(), 3);
+    let mut previous = (mesh.indices.len(), 0.0);
+    for level in &mesh.lods {
+        assert!(level.indices.len() < previous.0);
+        assert!(level.error >= previous.1);
+        assert!(level.error <= 0.05 * 1.0f32.hypot(1.0).hypot(1.0));
+        assert_valid(&mesh, &level.indices);
+        previous = (level.indices.len(), level.error);
+    }
+}
+
+#[test]
+fn optimizing_keeps_the_levels_in_step() {
+    let mut mesh = primitives::torus(1.0, 0.25, 32, 16);
+    mesh.generate_lods(&LodSettings::default());
+    let positions = |mesh: &MeshData, indices: &[u32]| -> Vec<[f32; 3]> {
+        indices
+            .iter()
+            .map(|index| mesh.vertices[*index as usize].position.into())
+            .collect()
+    };
+    let before: Vec<_> = mesh
+        .lods
+        .iter()
+        .map(|level| positions(&mesh, &level.indices))
+        .collect();
+    mesh.optimize();
+    for (level, before) in mesh.lods.iter().zip(before) {
+        assert_eq!(positions(&mesh, &level.indices), before);
+    }
+}
+
+#[test]
+fn the_bundled_sphere_gets_its_levels() {
+    let (_, mut model) = bundled()
+        .into_iter()
+        .find(|(name, _)| *name == "sphere.gltf")
+        .unwrap();
+    model.optimize();
+    let settings = LodSettings::default();
+    model.generate_lods(&settings);
+    assert_eq!(model.lod_screen_sizes, settings.screen_sizes);
+
+    let mesh = &model.meshes[0];
+    let (_, radius) = model.bounding_sphere();
+    // 48 segments and 24 rings, every level halves the previous one
+    assert_eq!(mesh.indices.len() / 3, 2208);
+    let counts: Vec<_> = mesh
+        .lods
+        .iter()
+        .map(|level| level.indices.len() / 3)
+        .collect();
+    assert_eq!(counts, [1104, 552, 276]);
+    let mut previous = (mesh.indices.len(), 0.0);
+    for level in &mesh.lods {
+        assert!(level.indices.len() as f32 <= previous.0 as f32 * settings.ratio);
+        assert!(level.error >= previous.1);
+        assert!(
+            level.error <= settings.max_error * radius,
+            "{}",
+            level.error
+        );
+        assert_valid(mesh, &level.indices);
+        previous = (level.indices.len(), level.error);
+    }
+}
+
+#[test]
+fn dense_models_get_their_levels() {
+    let (_, mut model) = bundled().remove(0);
+    model.meshes[0] = primitives::uv_sphere(2.0, 64, 32);
+    model.optimize();
+    let mut again = model.clone();
+    model.generate_lods(&LodSettings::default());
+    again.generate_lods(&LodSettings::default());
+    let mesh = &model.meshes[0];
+    assert_eq!(mesh.lods.len(), 3);
+    assert_eq!(mesh.lods, again.meshes[0].lods);
+    for level in &mesh.lods {
+        assert_valid(mesh, &level.indices);
+    }
+    let (_, radius) = model.bounding_sphere();
+    assert!(radius >= 2.0, "{}", radius);
+}
+
+#[test]
+fn screen_size_shrinks_with_distance() {
+    let projection = perspective(Deg(90.0), 1.0, 0.1, 100.0);
+    let near = lod::screen_size(Point3::new(0.0, 0.0, -2.0), 1.0, &projection);
+    let far = lod::screen_size(Point3::new(0.0, 0.0, -8.0), 1.0, &projection);
+    assert!((near - 0.5).abs() < 1e-5, "{}", near);
+    assert!((far - 0.125).abs() < 1e-5, "{}", far);
+    // Inside or behind the camera
+    let inside = lod::screen_size(Point3::new(0.0, 0.0, -0.5), 1.0, &projection);
+    let behind = lod::screen_size(Point3::new(0.0, 0.0, 5.0), 1.0, &projection);
+    assert_eq!(inside, f32::INFINITY);
+    assert_eq!(behind, f32::INFINITY);
+
+    // Orthographic, the distance does not matter
+    let projection = ortho(-4.0, 4.0, -4.0, 4.0, 0.1, 100.0);
+    for z in [-2.0, -50.0] {
+        let size = lod::screen_size(Point3::new(0.0, 0.0, z), 1.0, &projection);
+        assert!((size - 0.25).abs() < 1e-5, "{}", size);
+    }
+}
+
+#[test]
+fn selection_follows_the_thresholds() {
+    let thresholds = [0.5, 0.25, 0.125];
+    assert_eq!(lod::select(f32::INFINITY, &thresholds), 0);
+    assert_eq!(lod::select(0.5, &thresholds), 0);
+    assert_eq!(lod::select(0.3, &thresholds), 1);
+    assert_eq!(lod::select(0.2, &thresholds), 2);
+    assert_eq!(lod::select(0.01, &thresholds), 3);
+    assert_eq!(lod::select(0.01, &[]), 0);
+}
@@ -0,0 +1,125 @@
+use cgmath::{point3, vec3};
+use revenant::assets::{
+    error::AssetError,
+    mesh::{blend_morph_tangents, blend_morph_targets, MorphTarget, Vertex},
+    model::{
+        animation::{AnimationPlayer, Interpolation, Property, Sampler},
+        ModelData,
+    },
+};
+
+mod common;
+use common::EPSILON;
+
+fn vertex(x: f32, y: f32) -> Vertex {
+    Vertex {
+        normal: vec3(0.0, 0.0, 1.0),
+        ..common::vertex([x, y, 0.0])
+    }
+}
+
+#[test]
+fn targets_are_weighted_and_summed() {
+    let vertices = [vertex(0.0, 0.0), vertex(1.0, 0.0)];
+    let targets = [
+        MorphTarget {
+            positions: vec![[0.0, 2.0, 0.0], [0.0, 2.0, 0.0]],
+            ..Default::default()
+        },
+        MorphTarget {
+            positions: vec![[1.0, 0.0, 0.0], [1.0, 0.0, 0.0]],
+            normals: vec![[1.0, 0.0, -1.0], [0.0, 0.0, 0.0]],
+            ..Default::default()
+        },
+    ];
+
+    let blended = blend_morph_targets(&vertices, &targets, &[0.5, 1.0]);
+    assert_eq!(blended[0].position, point3(1.0, 1.0, 0.0));
+    assert_eq!(blended[1].position, point3(2.0, 1.0, 0.0));
+    // normals are renormalized after blending
+    assert!((blended[0].normal.x - 1.0).abs() < EPSILON);
+    assert!(blended[0].normal.z.abs() < EPSILON);
+    assert_eq!(blended[1].normal, vec3(0.0, 0.0, 1.0));
+
+    // missing weights leave the mesh alone
+    let rest = blend_morph_targets(&vertices, &targets, &[]);
+    assert_eq!(rest[1].position, vertices[1].position);
+}
+
+#[test]
+fn tangents_are_blended_and_keep_their_handedness() {
+    let tangents = [[1.0, 0.0, 0.0, 1.0], [1.0, 0.0, 0.0, -1.0]];
+    let targets = [
+        MorphTarget {
+            positions: vec![[0.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+            ..Default::default()
+        },
+        MorphTarget {
+            tangents: vec![[-1.0, 1.0, 0.0], [0.0, 0.0, 0.0]],
+            ..Default::default()
+        },
+    ];
+
+    let blended = blend_morph_tangents(&tangents, &targets, &[1.0, 1.0]);
+    // the first target moves no tangent, the second turns the first one a quarter
+    assert!(blended[0][0].abs() < EPSILON);
+    assert!((blended[0][1] - 1.0).abs() < EPSILON);
+    assert_eq!(blended[0][3], 1.0);
+    assert_eq!(blended[1], tangents[1]);
+
+    // halfway is renormalized
+    let half = blend_morph_tangents(&tangents, &targets, &[0.0, 0.5]);
+    let length = (half[0][0] * half[0][0] + half[0][1] * half[0][1]).sqrt();
+    assert!((length - 1.0).abs() < EPSILON);
+    assert!((half[0][0] - half[0][1]).abs() < EPSILON);
+
+    assert_eq!(blend_morph_tangents(&tangents, &targets, &[]), tangents);
+}
+
+#[test]
+fn weights_sample_one_value_per_target() {
+    let sampler = Sampler {
+        interpolation: Interpolation::Linear,
+        times: vec![0.0, 1.0],
+        values: [0.0, 1.0, 1.0, 0.0]
+            .iter()
+            .map(|weight| [*weight, 0.0, 0.0, 0.0])
+            .collect(),
+    };
+    assert_eq!(sampler.width(), 2);
+    let weights = sampler.sample_weights(0.25);
+    assert!((weights[0] - 0.25).abs() < EPSILON);
+    assert!((weights[1] - 0.75).abs() < EPSILON);
+    assert_eq!(sampler.sample(0.25, Property::Weights)[0], weights[0]);
+}
+
+#[test]
+fn imports_and_animates_morph_targets() {
+    let path = common::path("tests/fixtures/morph.gltf");
+    let model = ModelData::from_gltf(path).expect("Failed to import the morph fixture.");
+
+    let mesh = &model.meshes[0];
+    assert_eq!(mesh.morph_targets.len(), 2);
+    assert_eq!(mesh.morph_targets[0].positions[2], [0.0, 1.0, 0.0]);
+    assert!(mesh.morph_targets[0].normals.is_empty());
+    // the node takes the default weights of its mesh
+    assert_eq!(model.nodes[0].weights, vec![0.25, 0.0]);
+
+    let mut nodes = model.nodes.clone();
+    let mut player = AnimationPlayer::new(0);
+    player.advance(1.0, &model.animations);
+    player.apply(&model.animations, &mut nodes);
+    assert_eq!(nodes[0].weights, vec![0.5, 0.25]);
+
+    let blended = blend_morph_targets(&mesh.vertices, &mesh.morph_targets, &nodes[0].weights);
+    assert_eq!(blended[2].position, point3(0.25, 1.5, 0.0));
+}
+
+#[test]
+fn short_targets_are_rejected() {
+    // The morph fixture with its second target one vertex short
+    let path = common::path("tests/fixtures/short_morph_target.gltf");
+    let error = ModelData::from_gltf(path).unwrap_err();
+    assert!(matches!(error, AssetError::Decode(_)), "{}", error);
+    assert!(error.to_string().contains("short"), "{}", error);
+}
@@ -0,0 +1,205 @@
+use cgmath::{vec2, vec3, InnerSpace, Vector3};
+use revenant::assets::{
+    mesh::{
+        normals::{self, flat_normals, smooth_normals},
+        primitives, MeshData, Vertex, VertexSkin,
+    },
+    model::ModelData,
+};
+use std::f32::consts::PI;
+
+mod common;
+use common::vertex;
+
+/// 8 shared corners, no normals
+fn cube() -> (Vec<Vertex>, Vec<u32>) {
+    let vertices = (0..8)
+        .map(|corner| {
+            vertex([0, 1, 2].map(|axis| if corner >> axis & 1 == 1 { 1.0 } else { -1.0 }))
+        })
+        .collect();
+    #[rustfmt::skip]
+    let indices = vec![
+        0, 2, 1, 1, 2, 3, // -z
+        4, 5, 6, 5, 7, 6, // +z
+        0, 1, 4, 1, 5, 4, // -y
+        2, 6, 3, 3, 6, 7, // +y
+        0, 4, 2, 2, 4, 6, // -x
+        1, 3, 5, 3, 7, 5, // +x
+    ];
+    (vertices, indices)
+}
+
+fn position(vertex: &Vertex) -> Vector3<f32> {
+    vec3(vertex.position.x, vertex.position.y, vertex.position.z)
+}
+
+#[test]
+fn flat_normals_split_every_face() {
+    let (vertices, indices) = cube();
+    let split = flat_normals(&vertices, &indices);
+    assert_eq!(split.vertices.len(), 24);
+    assert_eq!(split.indices.len(), indices.len());
+    for (vertex, origin) in split.vertices.iter().zip(&split.origins) {
+        assert_eq!(vertex.position, vertices[*origin as usize].position);
+        // Axis aligned and pointing out of the cube
+        let normal = vertex.normal;
+        assert!((normal.magnitude() - 1.0).abs() < 1e-6);
+        assert!((normal.x.abs() + normal.y.abs() + normal.z.abs() - 1.0).abs() < 1e-6);
+        assert!(normal.dot(position(vertex)) > 0.0);
+    }
+    for triangle in split.indices.chunks(3) {
+        let normals: Vec<_> = triangle
+            .iter()
+            .map(|index| split.vertices[*index as usize].normal)
+            .collect();
+        assert!(normals.iter().all(|normal| *normal == normals[0]));
+    }
+}
+
+#[test]
+fn angle_threshold_decides_what_is_smooth() {
+    let (vertices, indices) = cube();
+    // The faces meet at 90 degrees
+    assert_eq!(
+        smooth_normals(&vertices, &indices, 80f32.to_radians())
+            .vertices
+            .len(),
+        24
+    );
+    let smooth = smooth_normals(&vertices, &indices, 100f32.to_radians());
+    assert_eq!(smooth.vertices.len(), 8);
+    for vertex in &smooth.vertices {
+        assert!((vertex.normal - position(vertex).normalize()).magnitude() < 1e-5);
+    }
+}
+
+#[test]
+fn smooth_normals_follow_curved_surfaces() {
+    let mut sphere = primitives::icosphere(1.0, 3);
+    let expected: Vec<Vector3<f32>> = sphere.vertices.iter().map(|vertex| vertex.normal).collect();
+    for vertex in &mut sphere.vertices {
+        vertex.normal = vec3(0.0, 0.0, 0.0);
+    }
+    let split = smooth_normals(&sphere.vertices, &sphere.indices, PI);
+    // Vertices split along the texture seam still get the same normal
+    assert_eq!(split.vertices.len(), sphere.vertices.len());
+    for (vertex, origin) in split.vertices.iter().zip(&split.origins) {
+        assert!(vertex.normal.dot(expected[*origin as usize]) > 0.999);
+    }
+}
+
+#[test]
+fn unindexed_vertices_are_triangles() {
+    let vertices = vec![
+        vertex([0.0, 0.0, 0.0]),
+        vertex([1.0, 0.0, 0.0]),
+        vertex([0.0, 1.0, 0.0]),
+    ];
+    let split = flat_normals(&vertices, &[]);
+    assert_eq!(split.indices, [0, 1, 2]);
+    assert!(split
+        .vertices
+        .iter()
+        .all(|vertex| vertex.normal == vec3(0.0, 0.0, 1.0)));
+}
+
+#[test]
+fn generated_tangents_match_the_primitives() {
+    for mesh in [
+        primitives::plane(1.0, 2.0, 3, 3),
+        primitives::cube(1.0, 2),
+        primitives::cylinder(1.0, 1.0, 32, 2),
+        primitives::torus(2.0, 0.5, 48, 24),
+    ] {
+        let generated = normals::tangents(&mesh.vertices, &mesh.indices);
+        for (generated, expected) in generated.iter().zip(&mesh.attributes.tangents) {
+            let dot: f32 = (0..3).map(|axis| generated[axis] * expected[axis]).sum();
+            assert!(dot > 0.99, "{:?} instead of {:?}", generated, expected);
+            assert_eq!(generated[3], expected[3]);
+        }
+    }
+}
+
+#[test]
+fn mirrored_texture_flips_the_handedness() {
+    let mut plane = primitives::plane(1.0, 1.0, 1, 1);
+    let unmirrored = normals::tangents(&plane.vertices, &plane.indices);
+    for vertex in &mut plane.vertices {
+        vertex.tex_coord.y = 1.0 - vertex.tex_coord.y;
+    }
+    let mirrored = normals::tangents(&plane.vertices, &plane.indices);
+    for (unmirrored, mirrored) in unmirrored.iter().zip(&mirrored) {
+        assert_eq!(unmirrored[..3], mirrored[..3]);
+        assert_eq!(unmirrored[3], -mirrored[3]);
+    }
+}
+
+#[test]
+fn tangents_without_texture_coordinates_are_orthogonal() {
+    let mut sphere = primitives::uv_sphere(1.0, 16, 8);
+    for vertex in &mut sphere.vertices {
+        vertex.tex_coord = vec2(0.0, 0.0);
+    }
+    for (vertex, tangent) in sphere
+        .vertices
+        .iter()
+        .zip(normals::tangents(&sphere.vertices, &sphere.indices))
+    {
+        let xyz = vec3(tangent[0], tangent[1], tangent[2]);
+        assert!((xyz.magnitude() - 1.0).abs() < 1e-5);
+        assert!(xyz.dot(vertex.normal).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn splitting_keeps_other_attributes_in_step() {
+    let (vertices, indices) = cube();
+    let count = vertices.len();
+    let mut mesh = MeshData {
+        skin: (0..count as u16)
+            .map(|joint| VertexSkin {
+                joints: [joint, 0, 0, 0],
+                weights: [1.0, 0.0, 0.0, 0.0],
+            })
+            .collect(),
+        vertices,
+        indices,
+        ..primitives::plane(1.0, 1.0, 1, 1)
+    };
+    mesh.attributes.tangents.clear();
+    mesh.attributes.colors = (0..count).map(|color| [color as f32; 4]).collect();
+    let original = mesh.clone();
+
+    mesh.generate_normals(0.0);
+    assert_eq!(mesh.vertices.len(), 24);
+    assert_eq!(mesh.skin.len(), 24);
+    assert_eq!(mesh.attributes.colors.len(), 24);
+    assert!(mesh.attributes.tangents.is_empty());
+    for (index, vertex) in mesh.vertices.iter().enumerate() {
+        let origin = mesh.skin[index].joints[0] as usize;
+        assert_eq!(vertex.position, original.vertices[origin].position);
+        assert_eq!(mesh.attributes.colors[index], [origin as f32; 4]);
+    }
+
+    mesh.generate_tangents();
+    assert_eq!(mesh.attributes.tangents.len(), 24);
+}
+
+#[test]
+fn gltf_without_normals_gets_flat_ones() {
+    let path = common::path("tests/fixtures/uri/data.gltf");
+    let model = ModelData::from_gltf(path).unwrap();
+    let mesh = &model.meshes[0];
+    for triangle in mesh.indices.chunks(3) {
+        let [a, b, c] = [0, 1, 2].map(|corner| &mesh.vertices[triangle[corner] as usize]);
+        let face = (position(b) - position(a))
+            .cross(position(c) - position(a))
+            .normalize();
+        for vertex in [a, b, c] {
+            assert!((vertex.normal - face).magnitude() < 1e-5);
+        }
+    }
+    // No normal texture, no tangents
+    assert!(mesh.attributes.tangents.is_empty());
+}
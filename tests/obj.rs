@@ -0,0 +1,191 @@
+use revenant::assets::{
+    material::{AlphaMode, MaterialData},
+    model::{obj, ModelData},
+};
+use std::path::PathBuf;
+
+mod common;
+use common::{path, EPSILON};
+
+fn scene() -> ModelData {
+    ModelData::from_foreign(path("tests/fixtures/obj/scene.obj"))
+        .expect("Failed to import the OBJ fixture.")
+}
+
+/// Signed, positive when counter clockwise seen from +z
+fn area(points: &[[f32; 3]], [a, b, c]: [usize; 3]) -> f32 {
+    let (a, b, c) = (points[a], points[b], points[c]);
+    ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.0
+}
+
+#[test]
+fn objects_and_groups_become_nodes() {
+    let model = scene();
+    let names: Vec<Option<&str>> = model
+        .nodes
+        .iter()
+        .map(|node| node.name.as_deref())
+        .collect();
+    assert_eq!(names, [Some("quad"), Some("floor"), Some("roof")]);
+    assert_eq!(model.mesh_primitives, vec![vec![0], vec![1, 2], vec![3]]);
+    assert_eq!(model.scenes[0].roots, vec![0, 1, 2]);
+    // The unknown material falls back to the default one, appended last
+    assert_eq!(model.primitive_materials, vec![0, 1, 2, 2]);
+}
+
+#[test]
+fn corners_are_merged_and_uvs_flipped() {
+    let quad = &scene().meshes[0];
+    assert_eq!(quad.vertices.len(), 4);
+    assert_eq!(quad.indices.len(), 6);
+    // v goes up in OBJ but down in the textures
+    let corner = quad
+        .vertices
+        .iter()
+        .find(|vertex| vertex.position == [-1.0, -1.0, 0.0].into())
+        .unwrap();
+    assert_eq!(corner.tex_coord, [0.0, 1.0].into());
+    assert_eq!(corner.normal, [0.0, 0.0, 1.0].into());
+}
+
+#[test]
+fn concave_faces_are_triangulated() {
+    let floor = &scene().meshes[1];
+    assert_eq!(floor.indices.len(), 12);
+    let positions: Vec<[f32; 3]> = floor
+        .vertices
+        .iter()
+        .map(|vertex| vertex.position.into())
+        .collect();
+    let total: f32 = floor
+        .indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let triangle = [0, 1, 2].map(|index| triangle[index] as usize);
+            let area = area(&positions, triangle);
+            assert!(area > 0.0, "Triangle {:?} is flipped or empty.", triangle);
+            area
+        })
+        .sum();
+    assert!((total - 3.0).abs() < EPSILON);
+}
+
+#[test]
+fn ears_avoid_reflex_corners() {
+    // Starting the fan at (2, 0) would cover the notch of this U
+    let polygon = [
+        [2.0, 0.0, 0.0],
+        [2.0, 2.0, 0.0],
+        [1.5, 2.0, 0.0],
+        [1.5, 0.5, 0.0],
+        [0.5, 0.5, 0.0],
+        [0.5, 2.0, 0.0],
+        [0.0, 2.0, 0.0],
+        [0.0, 0.0, 0.0],
+    ];
+    let triangles = obj::triangulate(&polygon);
+    assert_eq!(triangles.len(), polygon.len() - 2);
+    let total: f32 = triangles
+        .iter()
+        .map(|triangle| {
+            let area = area(&polygon, *triangle);
+            assert!(area > 0.0);
+            area
+        })
+        .sum();
+    assert!((total - 2.5).abs() < EPSILON);
+}
+
+#[test]
+fn missing_normals_are_generated() {
+    let model = scene();
+    for vertex in &model.meshes[2].vertices {
+        assert_eq!(vertex.normal, [0.0, 0.0, 1.0].into());
+    }
+}
+
+#[test]
+fn mtl_materials_map_onto_phong_and_pbr() {
+    let model = scene();
+    let MaterialData::Phong {
+        diffuse,
+        specular,
+        specular_strength,
+        emissive,
+    } = &model.materials[0]
+    else {
+        panic!("Expected a Phong material.");
+    };
+    assert_eq!((diffuse.image.width(), diffuse.image.height()), (2, 2));
+    assert_eq!(&diffuse.image.data()[..3], &[255, 0, 0]);
+    assert_eq!(specular.image.data(), &[255, 255, 255, 255]);
+    assert_eq!(*specular_strength, 64.0);
+    assert_eq!(emissive.image.data(), &[26, 51, 77, 255]);
+
+    let MaterialData::Pbr {
+        base_color_factor,
+        metallic_roughness,
+        metallic_factor,
+        roughness_factor,
+        normal,
+        normal_scale,
+        alpha_mode,
+        ..
+    } = &model.materials[1]
+    else {
+        panic!("Expected a metallic-roughness material.");
+    };
+    assert_eq!(*base_color_factor, [1.0, 0.5, 0.25, 0.5]);
+    assert_eq!(*alpha_mode, AlphaMode::Blend);
+    assert_eq!((*metallic_factor, *roughness_factor), (1.0, 1.0));
+    // Roughness in green, no metallic map so blue stays white
+    assert_eq!(
+        metallic_roughness.image.data(),
+        &[255, 64, 255, 255, 192, 255]
+    );
+    assert_eq!(normal.as_ref().unwrap().image.data(), &[128, 128, 255]);
+    assert_eq!(*normal_scale, 0.5);
+
+    assert!(matches!(model.materials[2], MaterialData::Phong { .. }));
+}
+
+#[test]
+fn unknown_extensions_are_rejected() {
+    assert!(ModelData::from_foreign(PathBuf::from("model.fbx")).is_err());
+}
+
+#[test]
+fn dependencies_list_libraries_and_maps() {
+    let folder = path("tests/fixtures/obj");
+    let dependencies = ModelData::dependencies(&folder.join("scene.obj")).unwrap();
+    assert_eq!(
+        dependencies,
+        ["scene.mtl", "checker.png", "normal.png", "roughness.png"].map(|name| folder.join(name))
+    );
+}
+
+#[test]
+fn mtllib_lines_name_several_libraries() {
+    let folder = path("tests/fixtures/obj");
+    let model = ModelData::from_foreign(folder.join("libraries.obj")).unwrap();
+    // painted and metal from scene.mtl, glass from extra.mtl, spaced from "spaced name.mtl"
+    assert_eq!(model.primitive_materials, vec![0, 2, 3]);
+    let MaterialData::Phong { diffuse, .. } = &model.materials[3] else {
+        panic!("Expected a Phong material.");
+    };
+    assert_eq!(&diffuse.image.data()[..3], &[0, 255, 0]);
+
+    let dependencies = ModelData::dependencies(&folder.join("libraries.obj")).unwrap();
+    assert_eq!(
+        dependencies,
+        [
+            "scene.mtl",
+            "checker.png",
+            "normal.png",
+            "roughness.png",
+            "extra.mtl",
+            "spaced name.mtl"
+        ]
+        .map(|name| folder.join(name))
+    );
+}
@@ -0,0 +1,195 @@
+use revenant::assets::mesh::{
+    optimize::{self, CacheStats, CACHE_SIZE},
+    primitives, MeshData, MeshDrawType, Vertex, VertexSkin,
+};
+
+mod common;
+use common::bundled;
+
+/// Triangles shuffled with a fixed seed, as a badly authored mesh would be
+fn shuffled(mut mesh: MeshData) -> MeshData {
+    let mut triangles: Vec<[u32; 3]> = mesh
+        .indices
+        .chunks(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .collect();
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    for index in (1..triangles.len()).rev() {
+        seed = seed
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        triangles.swap(index, (seed >> 33) as usize % (index + 1));
+    }
+    mesh.indices = triangles.concat();
+    mesh
+}
+
+/// Triangles by the content of their corners, starting from the smallest to keep the winding
+fn triangle_set(mesh: &MeshData) -> Vec<Vec<String>> {
+    let mut triangles: Vec<Vec<String>> = mesh
+        .indices
+        .chunks(3)
+        .map(|triangle| {
+            let corners: Vec<String> = triangle
+                .iter()
+                .map(|index| {
+                    let index = *index as usize;
+                    format!(
+                        "{:?} {:?}",
+                        mesh.vertices[index],
+                        mesh.attributes.tangents.get(index)
+                    )
+                })
+                .collect();
+            let first = (0..3).min_by_key(|corner| &corners[*corner]).unwrap();
+            (0..3)
+                .map(|corner| corners[(first + corner) % 3].clone())
+                .collect()
+        })
+        .collect();
+    triangles.sort();
+    triangles
+}
+
+fn unindexed(mesh: &MeshData) -> MeshData {
+    let mut expanded = mesh.clone();
+    expanded.vertices = mesh
+        .indices
+        .iter()
+        .map(|index| mesh.vertices[*index as usize].clone())
+        .collect();
+    expanded.attributes.tangents = mesh
+        .indices
+        .iter()
+        .map(|index| mesh.attributes.tangents[*index as usize])
+        .collect();
+    expanded.indices = Vec::new();
+    expanded.draw_type = MeshDrawType::DrawArrays;
+    expanded
+}
+
+#[test]
+fn cache_stats_count_misses() {
+    let stats = CacheStats::measure(&[0, 1, 2, 2, 1, 3], 4, CACHE_SIZE);
+    assert_eq!(stats.misses, 4);
+    assert_eq!(stats.acmr(), 2.0);
+    assert_eq!(stats.atvr(), 1.0);
+
+    // A cache of 3 forgets 0, then 1 once 0 is back
+    let stats = CacheStats::measure(&[0, 1, 2, 1, 2, 3, 3, 0, 1], 4, 3);
+    assert_eq!(stats.misses, 6);
+}
+
+#[test]
+fn reordering_lowers_the_acmr() {
+    let mut mesh = shuffled(primitives::plane(1.0, 1.0, 64, 64));
+    let triangles = triangle_set(&mesh);
+    let stats = optimize::optimize(&mut mesh);
+    assert!(stats.before.acmr() > 1.5, "{}", stats.before.acmr());
+    assert!(stats.after.acmr() < 0.8, "{}", stats.after.acmr());
+    assert!(stats.after.atvr() < stats.before.atvr());
+    assert_eq!(stats.before.triangles, stats.after.triangles);
+    assert_eq!(triangle_set(&mesh), triangles);
+}
+
+#[test]
+fn vertices_follow_their_first_use() {
+    let mut mesh = shuffled(primitives::uv_sphere(1.0, 32, 16));
+    optimize::optimize(&mut mesh);
+    let mut next = 0;
+    for index in &mesh.indices {
+        assert!(*index <= next);
+        if *index == next {
+            next += 1;
+        }
+    }
+    assert_eq!(next as usize, mesh.vertices.len());
+    assert_eq!(mesh.attributes.tangents.len(), mesh.vertices.len());
+}
+
+#[test]
+fn duplicates_are_welded() {
+    let indexed = primitives::plane(1.0, 1.0, 8, 8);
+    let mut mesh = unindexed(&indexed);
+    assert_eq!(mesh.vertices.len(), 8 * 8 * 6);
+    let stats = optimize::optimize(&mut mesh);
+    assert_eq!(stats.before.acmr(), 3.0);
+    assert_eq!(mesh.vertices.len(), 9 * 9);
+    assert_eq!(stats.after.vertices, 9 * 9);
+    assert!(matches!(mesh.draw_type, MeshDrawType::DrawElements));
+    assert_eq!(triangle_set(&mesh), triangle_set(&indexed));
+}
+
+#[test]
+fn vertices_differing_anywhere_are_kept_apart() {
+    let mut mesh = unindexed(&primitives::plane(1.0, 1.0, 1, 1));
+    let count = mesh.vertices.len();
+    // Both triangles share two positions, only the skin tells them apart
+    mesh.skin = (0..count)
+        .map(|vertex| VertexSkin {
+            joints: [(vertex / 3) as u16, 0, 0, 0],
+            weights: [1.0, 0.0, 0.0, 0.0],
+        })
+        .collect();
+    optimize::weld(&mut mesh);
+    assert_eq!(mesh.vertices.len(), 6);
+
+    mesh.skin = vec![
+        VertexSkin {
+            joints: [0; 4],
+            weights: [1.0, 0.0, 0.0, 0.0],
+        };
+        count
+    ];
+    optimize::weld(&mut mesh);
+    assert_eq!(mesh.vertices.len(), 4);
+    assert_eq!(mesh.skin.len(), 4);
+}
+
+#[test]
+fn optimizing_is_deterministic() {
+    let mesh = shuffled(primitives::torus(1.0, 0.25, 32, 16));
+    let (mut first, mut second) = (mesh.clone(), mesh);
+    optimize::optimize(&mut first);
+    optimize::optimize(&mut second);
+    assert_eq!(first.vertices, second.vertices);
+    assert_eq!(first.indices, second.indices);
+}
+
+#[test]
+fn other_modes_are_left_alone() {
+    let mut mesh = primitives::plane(1.0, 1.0, 2, 2);
+    mesh.gl_mode = gl::LINES;
+    let before = mesh.clone();
+    optimize::optimize(&mut mesh);
+    assert_eq!(mesh.vertices, before.vertices);
+    assert_eq!(mesh.indices, before.indices);
+}
+
+#[test]
+fn indices_are_16_bits_when_they_fit() {
+    let vertex = Vertex {
+        position: [0.0; 3].into(),
+        normal: [0.0; 3].into(),
+        tex_coord: [0.0; 2].into(),
+    };
+    let mut mesh = primitives::plane(1.0, 1.0, 1, 1);
+    assert_eq!(mesh.index_type(), gl::UNSIGNED_SHORT);
+    mesh.vertices = vec![vertex; u16::MAX as usize + 1];
+    assert_eq!(mesh.index_type(), gl::UNSIGNED_SHORT);
+    mesh.vertices.push(mesh.vertices[0].clone());
+    assert_eq!(mesh.index_type(), gl::UNSIGNED_INT);
+}
+
+#[test]
+fn bundled_models_keep_their_triangles() {
+    for (name, original) in bundled() {
+        let mut model = original.clone();
+        let stats = model.optimize();
+        assert!(stats.after.acmr() <= stats.before.acmr(), "{}", name);
+        assert!(stats.after.vertices <= stats.before.vertices, "{}", name);
+        for (mesh, original) in model.meshes.iter().zip(&original.meshes) {
+            assert_eq!(triangle_set(mesh), triangle_set(original), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use cgmath::{InnerSpace, Vector3};
+use revenant::assets::mesh::{primitives, MeshData};
+use std::f32::consts::PI;
+
+mod common;
+use common::triangles;
+
+fn all() -> Vec<(&'static str, MeshData)> {
+    vec![
+        ("plane", primitives::plane(2.0, 3.0, 4, 2)),
+        ("cube", primitives::cube(2.0, 3)),
+        ("uv_sphere", primitives::uv_sphere(1.0, 64, 32)),
+        ("icosphere", primitives::icosphere(1.0, 3)),
+        ("cylinder", primitives::cylinder(1.0, 2.0, 64, 2)),
+        ("cone", primitives::cone(1.0, 2.0, 64, 3)),
+        ("capsule", primitives::capsule(0.5, 2.0, 64, 16)),
+        ("torus", primitives::torus(2.0, 0.5, 64, 32)),
+        ("disc", primitives::disc(1.0, 64, 4)),
+    ]
+}
+
+fn positions(mesh: &MeshData, triangle: [usize; 3]) -> [Vector3<f32>; 3] {
+    triangle.map(|index| {
+        let position = mesh.vertices[index].position;
+        Vector3::new(position.x, position.y, position.z)
+    })
+}
+
+fn area(mesh: &MeshData) -> f32 {
+    triangles(mesh)
+        .map(|triangle| {
+            let [a, b, c] = positions(mesh, triangle);
+            (b - a).cross(c - a).magnitude() / 2.0
+        })
+        .sum()
+}
+
+/// Positive when the triangles face outwards
+fn volume(mesh: &MeshData) -> f32 {
+    triangles(mesh)
+        .map(|triangle| {
+            let [a, b, c] = positions(mesh, triangle);
+            a.dot(b.cross(c)) / 6.0
+        })
+        .sum()
+}
+
+fn assert_close(name: &str, what: &str, value: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (value - expected).abs() <= expected.abs() * tolerance,
+        "{} {}: {} instead of {}",
+        name,
+        what,
+        value,
+        expected
+    );
+}
+
+#[test]
+fn buffers_are_consistent() {
+    for (name, mesh) in all() {
+        assert_eq!(mesh.gl_mode, gl::TRIANGLES, "{}", name);
+        assert!(!mesh.indices.is_empty(), "{}", name);
+        assert!(mesh.indices.len().is_multiple_of(3), "{}", name);
+        assert!(
+            mesh.indices
+                .iter()
+                .all(|index| (*index as usize) < mesh.vertices.len()),
+            "{}",
+            name
+        );
+        assert_eq!(
+            mesh.attributes.tangents.len(),
+            mesh.vertices.len(),
+            "{}",
+            name
+        );
+        assert!(mesh.skin.is_empty() && mesh.morph_targets.is_empty());
+    }
+}
+
+#[test]
+fn normals_and_tangents_are_orthonormal() {
+    for (name, mesh) in all() {
+        for (vertex, tangent) in mesh.vertices.iter().zip(&mesh.attributes.tangents) {
+            let xyz = Vector3::new(tangent[0], tangent[1], tangent[2]);
+            assert!((vertex.normal.magnitude() - 1.0).abs() < 1e-4, "{}", name);
+            assert!((xyz.magnitude() - 1.0).abs() < 1e-4, "{}", name);
+            assert!(vertex.normal.dot(xyz).abs() < 1e-4, "{}", name);
+            assert!(tangent[3] == 1.0 || tangent[3] == -1.0, "{}", name);
+        }
+    }
+}
+
+#[test]
+fn triangles_wind_around_their_normals() {
+    for (name, mesh) in all() {
+        for triangle in triangles(&mesh) {
+            let [a, b, c] = positions(&mesh, triangle);
+            let normal: Vector3<f32> = triangle
+                .iter()
+                .map(|index| mesh.vertices[*index].normal)
+                .sum();
+            assert!((b - a).cross(c - a).dot(normal) > 0.0, "{}", name);
+        }
+    }
+}
+
+#[test]
+fn tangents_follow_the_texture_coordinates() {
+    for (name, mesh) in all() {
+        for triangle in triangles(&mesh) {
+            let [a, b, c] = positions(&mesh, triangle);
+            let [ta, tb, tc] = triangle.map(|index| mesh.vertices[index].tex_coord);
+            let (e1, e2, d1, d2) = (b - a, c - a, tb - ta, tc - ta);
+            let det = d1.x * d2.y - d2.x * d1.y;
+            if det.abs() < 1e-9 {
+                continue;
+            }
+            let towards_u = (e1 * d2.y - e2 * d1.y) / det;
+            let towards_v = (e2 * d1.x - e1 * d2.x) / det;
+            for index in triangle {
+                let tangent = mesh.attributes.tangents[index];
+                let xyz = Vector3::new(tangent[0], tangent[1], tangent[2]);
+                let bitangent = mesh.vertices[index].normal.cross(xyz) * tangent[3];
+                assert!(xyz.dot(towards_u) > 0.0, "{} tangent", name);
+                // Up in the texture, against v
+                assert!(bitangent.dot(towards_v) < 0.0, "{} bitangent", name);
+            }
+        }
+    }
+}
+
+#[test]
+fn texture_coordinates_stay_in_the_unit_square() {
+    for (name, mesh) in all() {
+        // Triangles across the seam of the icosphere wrap past 1 instead
+        let u_max = if name == "icosphere" { 1.5 } else { 1.0 };
+        for vertex in &mesh.vertices {
+            let [u, v]: [f32; 2] = vertex.tex_coord.into();
+            assert!(
+                (-1e-5..=u_max + 1e-5).contains(&u) && (-1e-5..=1.0 + 1e-5).contains(&v),
+                "{} ({}, {})",
+                name,
+                u,
+                v
+            );
+        }
+    }
+}
+
+#[test]
+fn surfaces_match_the_shapes() {
+    let expected = [
+        ("plane", 6.0),
+        ("cube", 24.0),
+        ("uv_sphere", 4.0 * PI),
+        ("icosphere", 4.0 * PI),
+        ("cylinder", 6.0 * PI),
+        ("cone", PI * 5.0f32.sqrt() + PI),
+        ("capsule", 2.0 * PI),
+        ("torus", 4.0 * PI * PI),
+        ("disc", PI),
+    ];
+    for ((name, mesh), (expected_name, expected)) in all().iter().zip(expected) {
+        assert_eq!(*name, expected_name);
+        assert_close(name, "area", area(mesh), expected, 0.02);
+    }
+}
+
+#[test]
+fn closed_shapes_enclose_their_volume() {
+    let expected = [
+        ("cube", 8.0),
+        ("uv_sphere", 4.0 / 3.0 * PI),
+        ("icosphere", 4.0 / 3.0 * PI),
+        ("cylinder", 2.0 * PI),
+        ("cone", 2.0 / 3.0 * PI),
+        ("capsule", PI / 6.0 + PI / 4.0),
+        ("torus", 2.0 * PI * PI * 2.0 * 0.25),
+    ];
+    let meshes = all();
+    for (name, expected) in expected {
+        let (_, mesh) = meshes.iter().find(|(mesh, _)| *mesh == name).unwrap();
+        assert_close(name, "volume", volume(mesh), expected, 0.02);
+    }
+}
+
+#[test]
+fn subdivisions_set_the_resolution() {
+    let plane = primitives::plane(1.0, 1.0, 4, 2);
+    assert_eq!(plane.vertices.len(), 5 * 3);
+    assert_eq!(plane.indices.len(), 4 * 2 * 2 * 3);
+
+    let cube = primitives::cube(1.0, 2);
+    assert_eq!(cube.vertices.len(), 6 * 3 * 3);
+    assert_eq!(cube.indices.len(), 6 * 2 * 2 * 2 * 3);
+
+    // Each level splits every triangle in four
+    for level in 0..4 {
+        let sphere = primitives::icosphere(1.0, level);
+        assert_eq!(sphere.indices.len(), 20 * 4usize.pow(level) * 3);
+    }
+
+    // The pole triangles have no area and are dropped
+    let sphere = primitives::uv_sphere(1.0, 8, 4);
+    assert_eq!(sphere.indices.len(), 8 * (4 * 2 - 2) * 3);
+
+    // Too few segments to close a shape are raised
+    assert_eq!(
+        primitives::cylinder(1.0, 1.0, 0, 0).indices.len(),
+        primitives::cylinder(1.0, 1.0, 3, 1).indices.len()
+    );
+}
+
+#[test]
+fn positions_lie_on_the_shapes() {
+    for vertex in &primitives::uv_sphere(2.0, 16, 8).vertices {
+        let position = Vector3::new(vertex.position.x, vertex.position.y, vertex.position.z);
+        assert!((position.magnitude() - 2.0).abs() < 1e-5);
+        assert!((position / 2.0 - vertex.normal).magnitude() < 1e-5);
+    }
+    for vertex in &primitives::icosphere(2.0, 2).vertices {
+        let position = Vector3::new(vertex.position.x, vertex.position.y, vertex.position.z);
+        assert!((position.magnitude() - 2.0).abs() < 1e-5);
+    }
+    for vertex in &primitives::torus(2.0, 0.5, 16, 8).vertices {
+        let ring = Vector3::new(vertex.position.x, 0.0, vertex.position.z).normalize() * 2.0;
+        let position = Vector3::new(vertex.position.x, vertex.position.y, vertex.position.z);
+        assert!(((position - ring).magnitude() - 0.5).abs() < 1e-5);
+    }
+    let capsule = primitives::capsule(0.5, 3.0, 16, 4);
+    let top = capsule
+        .vertices
+        .iter()
+        .map(|vertex| vertex.position.y)
+        .fold(f32::MIN, f32::max);
+    assert!((top - 1.5).abs() < 1e-5);
+}
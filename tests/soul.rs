@@ -0,0 +1,181 @@
+use cgmath::{point3, vec2, vec3};
+use revenant::assets::{
+    error::AssetError,
+    font::FontData,
+    image::Image,
+    mesh::{MeshData, MeshDrawType, Vertex},
+    model::ModelData,
+    soul::{self, Compression, Header, Soul, CONTAINER_VERSION, HEADER_LEN, MAGIC},
+};
+
+mod common;
+use common::path;
+
+fn cube() -> ModelData {
+    ModelData::from_gltf(path("assets/foreign/cube.gltf"))
+        .expect("Failed to import the bundled cube.")
+}
+
+/// A file as an older version wrote it, `payload` laid out the old way
+fn soul_file(tag: [u8; 4], version: u32, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&CONTAINER_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    bytes.extend_from_slice(&tag);
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+fn decode_error<T: Soul>(bytes: &[u8]) -> String {
+    match soul::decode::<T>(bytes) {
+        Err(AssetError::Decode(message)) => message,
+        Err(error) => panic!("Expected a decode error, got {}", error),
+        Ok(_) => panic!("Decoded a broken file."),
+    }
+}
+
+#[test]
+fn models_round_trip() {
+    let model = cube();
+    for compression in [Compression::None, Compression::Deflate] {
+        let bytes = soul::encode(&model, compression).unwrap();
+        let header = Header::read(&bytes).unwrap();
+        assert_eq!(header.tag, ModelData::TAG);
+        assert_eq!(header.version, ModelData::VERSION);
+        assert_eq!(header.compression, compression);
+        assert_eq!(header.payload_len as usize, bytes.len() - HEADER_LEN);
+
+        let decoded: ModelData = soul::decode(&bytes).unwrap();
+        assert_eq!(soul::encode(&decoded, compression).unwrap(), bytes);
+    }
+}
+
+#[test]
+fn corrupt_payloads_fail_the_checksum() {
+    let mut bytes = soul::encode(&cube(), Compression::None).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    assert!(decode_error::<ModelData>(&bytes).contains("checksum"));
+}
+
+#[test]
+fn truncated_files_are_rejected() {
+    let bytes = soul::encode(&cube(), Compression::None).unwrap();
+    assert!(decode_error::<ModelData>(&bytes[..HEADER_LEN - 1]).contains("too short"));
+    assert!(decode_error::<ModelData>(&bytes[..bytes.len() - 1]).contains("header says"));
+}
+
+#[test]
+fn files_only_load_as_the_type_that_wrote_them() {
+    let image = Image::from_file(path("assets/foreign/icon.png"), "png").unwrap();
+    let bytes = soul::encode(&image, Compression::None).unwrap();
+    assert!(decode_error::<FontData>(&bytes).contains("holds a IMAG"));
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let mut bytes = soul::encode(&cube(), Compression::None).unwrap();
+    bytes[12..16].copy_from_slice(&(ModelData::VERSION + 1).to_le_bytes());
+    assert!(decode_error::<ModelData>(&bytes).contains("newer"));
+
+    let mut bytes = soul::encode(&cube(), Compression::None).unwrap();
+    bytes[4..6].copy_from_slice(&(CONTAINER_VERSION + 1).to_le_bytes());
+    assert!(decode_error::<ModelData>(&bytes).contains("container version"));
+}
+
+#[test]
+fn meshes_migrate_from_version_1() {
+    let vertices = vec![
+        Vertex {
+            position: point3(0.0, 0.0, 0.0),
+            normal: vec3(0.0, 0.0, 1.0),
+            tex_coord: vec2(0.0, 0.0),
+        };
+        3
+    ];
+    let indices: Vec<u32> = vec![0, 1, 2];
+    // Version 1 stopped after the draw type
+    let payload = bincode::serialize(&(
+        gl::TRIANGLES,
+        &vertices,
+        &indices,
+        MeshDrawType::DrawElements,
+    ))
+    .unwrap();
+
+    let mesh: MeshData = soul::decode(&soul_file(MeshData::TAG, 1, &payload)).unwrap();
+    assert_eq!(mesh.vertices.len(), 3);
+    assert_eq!(mesh.indices, indices);
+    assert!(mesh.skin.is_empty());
+    assert!(mesh.morph_targets.is_empty());
+    assert!(mesh.attributes.is_empty());
+    assert!(mesh.lods.is_empty());
+}
+
+#[test]
+fn models_migrate_from_version_4() {
+    let model = cube();
+    // Version 4 meshes had no levels of detail, and the model no screen sizes
+    let meshes: Vec<_> = model
+        .meshes
+        .iter()
+        .map(|mesh| {
+            (
+                mesh.gl_mode,
+                &mesh.vertices,
+                &mesh.indices,
+                mesh.draw_type,
+                &mesh.skin,
+                &mesh.morph_targets,
+                &mesh.attributes,
+            )
+        })
+        .collect();
+    let payload = bincode::serialize(&(
+        meshes,
+        &model.materials,
+        &model.primitive_materials,
+        &model.mesh_primitives,
+        &model.nodes,
+        &model.scenes,
+        model.scene,
+        &model.skins,
+        &model.animations,
+    ))
+    .unwrap();
+
+    let migrated: ModelData = soul::decode(&soul_file(ModelData::TAG, 4, &payload)).unwrap();
+    assert_eq!(migrated.meshes.len(), model.meshes.len());
+    assert_eq!(migrated.meshes[0].indices, model.meshes[0].indices);
+    assert!(migrated.meshes.iter().all(|mesh| mesh.lods.is_empty()));
+    assert!(migrated.lod_screen_sizes.is_empty());
+    assert_eq!(migrated.nodes.len(), model.nodes.len());
+
+    // Versions before 3 changed more than the meshes, those files have to be cooked again
+    assert!(decode_error::<ModelData>(&soul_file(ModelData::TAG, 2, &payload)).contains("cook"));
+}
+
+#[test]
+fn stored_models_from_versions_3_and_4_decode() {
+    // Written by versions 3 and 4 from tests/fixtures/morph.gltf, never regenerate them
+    let model = ModelData::from_gltf(path("tests/fixtures/morph.gltf")).unwrap();
+    for version in [3, 4] {
+        let file = path(&format!("tests/fixtures/soul/morph_v{}.soul", version));
+        let bytes = std::fs::read(file).unwrap();
+        assert_eq!(Header::read(&bytes).unwrap().version, version);
+
+        let migrated: ModelData = soul::decode(&bytes).unwrap();
+        assert_eq!(migrated.meshes.len(), model.meshes.len());
+        for (migrated, mesh) in migrated.meshes.iter().zip(&model.meshes) {
+            assert_eq!(migrated.indices, mesh.indices);
+            assert_eq!(migrated.morph_targets, mesh.morph_targets);
+            assert_eq!(migrated.attributes, mesh.attributes);
+            assert!(migrated.lods.is_empty());
+        }
+        assert!(migrated.lod_screen_sizes.is_empty());
+        assert_eq!(migrated.nodes.len(), model.nodes.len());
+    }
+}
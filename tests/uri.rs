@@ -0,0 +1,116 @@
+use revenant::assets::{
+    image::Image,
+    material::MaterialData,
+    model::ModelData,
+    uri::{self, Resource},
+};
+use std::path::Path;
+
+mod common;
+use common::path;
+
+fn assert_triangle(model: &ModelData) {
+    let mesh = &model.meshes[0];
+    assert_eq!(mesh.vertices.len(), 3);
+    assert_eq!(mesh.vertices[1].position, [1.0, 0.0, 0.0].into());
+    assert_eq!(mesh.indices, vec![0, 1, 2]);
+    let MaterialData::Pbr { albedo, .. } = &model.materials[0] else {
+        panic!("Expected a metallic-roughness material.");
+    };
+    assert_eq!((albedo.image.width(), albedo.image.height()), (2, 2));
+    assert_eq!(&albedo.image.data()[..3], &[255, 0, 0]);
+}
+
+#[test]
+fn data_uris_with_any_media_type() {
+    assert_triangle(&ModelData::from_gltf(path("tests/fixtures/uri/data.gltf")).unwrap());
+}
+
+#[test]
+fn relative_uris_are_percent_decoded() {
+    assert_triangle(&ModelData::from_gltf(path("tests/fixtures/uri/relative.gltf")).unwrap());
+}
+
+#[test]
+fn glb_buffers_resolve_against_the_model_folder() {
+    // The tests run from the crate root, not the folder of the model
+    assert_triangle(&ModelData::from_gltf(path("tests/fixtures/uri/external.glb")).unwrap());
+}
+
+#[test]
+fn absolute_file_uris() {
+    let source = std::fs::read_to_string(path("tests/fixtures/uri/relative.gltf")).unwrap();
+    let file_uri = |relative: &str| {
+        url::Url::from_file_path(path("tests/fixtures/uri").join(relative)).unwrap()
+    };
+    let source = source
+        .replace(
+            "buffers/tri%20angle.bin",
+            file_uri("buffers/tri angle.bin").as_str(),
+        )
+        .replace(
+            "textures/check%20er.png",
+            file_uri("textures/check er.png").as_str(),
+        );
+
+    let directory = std::env::temp_dir().join(format!("revenant-uri-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let file = directory.join("absolute.gltf");
+    std::fs::write(&file, source).unwrap();
+    assert_triangle(&ModelData::from_gltf(file).unwrap());
+}
+
+#[test]
+fn images_resolve_the_same_way() {
+    let image = Image::from_uri("check%20er.png", &path("tests/fixtures/uri/textures")).unwrap();
+    assert_eq!((image.width(), image.height()), (2, 2));
+    let image = Image::from_uri(
+        "../textures/check%20er.png",
+        &path("tests/fixtures/uri/buffers"),
+    )
+    .unwrap();
+    assert_eq!((image.width(), image.height()), (2, 2));
+}
+
+#[test]
+fn data_uris_are_decoded() {
+    let folder = Path::new("");
+    assert_eq!(
+        uri::resolve("data:,Hello%2C%20World", folder).unwrap(),
+        Resource::Data {
+            mime_type: String::new(),
+            bytes: b"Hello, World".to_vec(),
+        }
+    );
+    assert_eq!(
+        uri::resolve("data:text/plain;charset=utf-8;base64,SGk=", folder).unwrap(),
+        Resource::Data {
+            mime_type: "text/plain".to_owned(),
+            bytes: b"Hi".to_vec(),
+        }
+    );
+    assert!(uri::resolve("data:image/png;base64", folder).is_err());
+}
+
+#[test]
+fn remote_uris_are_unsupported() {
+    assert!(uri::resolve("https://example.com/model.bin", Path::new("")).is_err());
+}
+
+#[test]
+fn dependencies_list_the_referenced_files() {
+    let dependencies = ModelData::dependencies(&path("tests/fixtures/uri/relative.gltf")).unwrap();
+    assert_eq!(
+        dependencies,
+        vec![
+            path("tests/fixtures/uri/buffers/tri angle.bin"),
+            path("tests/fixtures/uri/textures/check er.png"),
+        ]
+    );
+    // Embedded data has nothing to watch
+    assert!(
+        ModelData::dependencies(&path("tests/fixtures/uri/data.gltf"))
+            .unwrap()
+            .is_empty()
+    );
+}